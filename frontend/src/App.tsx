@@ -40,10 +40,11 @@ import { Toaster } from './components/ui/toaster';
 import { HomeScreen } from './components/HomeScreen';
 import { exampleProjectsService } from './services/exampleProjectsService';
 import { createHomeTab, isHomeTab, addHomeTabIfNotExists } from './utils/homeTab';
+import { DEFAULT_BITCOIN_NETWORK } from './api/bitcoin/rpc';
+import { api, errorMessage } from './api/client';
 
 const queryClient = new QueryClient();
 console.log('API_URL', import.meta.env.VITE_API_URL);
-const API_URL = import.meta.env.VITE_API_URL || 'http://localhost:8080';
 
 interface Config {
   network: 'mainnet-beta' | 'devnet' | 'testnet';
@@ -52,9 +53,7 @@ interface Config {
   improveErrors: boolean;
   automaticAirdrop: boolean;
   regtestConfig: {
-    url: string;
-    username: string;
-    password: string;
+    network: string;
   };
 }
 
@@ -190,9 +189,7 @@ interface ArchDeployOptions {
     address: string;
   };
   regtestConfig?: {
-    url: string;
-    username: string;
-    password: string;
+    network: string;
   };
 }
 
@@ -441,9 +438,7 @@ const AppContent = () => {
       improveErrors: true,
       automaticAirdrop: true,
       regtestConfig: {
-        url: 'http://localhost:8010/proxy',
-        username: 'bitcoin',
-        password: '428bae8f3c94f8c39c50757fc89c39bc7e6ebc70ebf8f618'
+        network: DEFAULT_BITCOIN_NETWORK
       }
     };
 
@@ -452,9 +447,9 @@ const AppContent = () => {
     return {
       ...defaultConfig,
       ...savedConfig,
+      // Older saves also hold a node URL and credentials; keep only the network name
       regtestConfig: {
-        ...defaultConfig.regtestConfig,
-        ...(savedConfig.regtestConfig || {})
+        network: savedConfig.regtestConfig?.network || defaultConfig.regtestConfig.network
       }
     };
   });
@@ -1006,14 +1001,20 @@ const AppContent = () => {
         throw new Error('src directory not found or invalid');
       }
 
-      // Recursively collect all .rs files from src directory and its subdirectories
-      const collectRustFiles = (dir: FileNode[], basePath: string = ''): [string, string][] => {
+      // Mirrors the server's rules: Rust sources under these directories, data
+      // files there, under data/ and at the project root
+      const sourceDirs = ['src', 'tests', 'benches', 'examples'];
+      const dataExtensions = ['.json', '.md', '.txt', '.csv', '.yaml', '.yml'];
+      const isDataFile = (name: string) => !name.startsWith('.') && dataExtensions.some(ext => name.endsWith(ext));
+
+      // Recursively collect the files the build server accepts from a directory and its subdirectories
+      const collectFiles = (dir: FileNode[], basePath: string, rust: boolean): [string, string][] => {
         let files: [string, string][] = [];
 
         for (const node of dir) {
-          const currentPath = basePath ? `${basePath}/${node.name}` : node.name;
+          const currentPath = `${basePath}/${node.name}`;
 
-          if (node.type === 'file' && node.name.endsWith('.rs') && node.content) {
+          if (node.type === 'file' && node.content && ((rust && node.name.endsWith('.rs')) || isDataFile(node.name))) {
             let decodedContent = node.content;
 
             // Handle base64 encoded content
@@ -1026,40 +1027,40 @@ const AppContent = () => {
               }
             }
 
-            files.push([`/src/${currentPath}`, decodedContent]);
+            files.push([currentPath, decodedContent]);
           } else if (node.type === 'directory' && node.children) {
             // Recursively collect files from subdirectories
-            files = files.concat(collectRustFiles(node.children, currentPath));
+            files = files.concat(collectFiles(node.children, currentPath, rust));
           }
         }
 
         return files;
       };
 
-      const rsFiles = collectRustFiles(srcDir.children);
+      const projectFiles = fullCurrentProject.files.flatMap((node): [string, string][] => {
+        if (node.type === 'directory' && node.children && (sourceDirs.includes(node.name) || node.name === 'data')) {
+          return collectFiles(node.children, `/${node.name}`, node.name !== 'data');
+        }
+        return node.type === 'file' ? collectFiles([node], '', false) : [];
+      });
 
-      if (rsFiles.length === 0) {
+      if (!projectFiles.some(([path]) => path.startsWith('/src/') && path.endsWith('.rs'))) {
         throw new Error('No non-empty Rust source files found in src directory');
       }
 
-      console.log('Sending Rust files to compile server:', rsFiles.map(([path]) => path));
+      console.log('Sending project files to compile server:', projectFiles.map(([path]) => path));
 
       // Start the build (returns immediately)
-      const buildResponse = await fetch(`${API_URL}/build`, {
-        method: 'POST',
-        headers: {
-          'Content-Type': 'application/json',
-          'Accept': 'application/json',
-        },
-        body: JSON.stringify({
+      const { data: buildStartResult, error: buildError, response: buildResponse } = await api.post('/v1/build', {
+        body: {
           program_name: fullCurrentProject.name,
-          files: rsFiles,
+          files: projectFiles,
           uuid: fullCurrentProject.id // Send existing UUID for consistent builds
-        })
+        }
       });
 
-      if (!buildResponse.ok) {
-        const error = new Error(`Build failed with status: ${buildResponse.status}`);
+      if (!buildStartResult) {
+        const error = new Error(errorMessage(buildError, `Build failed with status: ${buildResponse.status}`));
         // Update the command message to remove loading state before throwing
         setOutputMessages(prev => {
           const messages = [...prev];
@@ -1074,7 +1075,6 @@ const AppContent = () => {
         throw error;
       }
 
-      const buildStartResult = await buildResponse.json();
       const { uuid, status: buildStatus } = buildStartResult;
 
       console.log('Build started with UUID:', uuid, 'Status:', buildStatus);
@@ -1093,7 +1093,8 @@ const AppContent = () => {
         pollCount++;
 
         // CRITICAL: Add cache-busting headers to prevent CloudFront/browser caching
-        const statusResponse = await fetch(`${API_URL}/build/status/${uuid}`, {
+        const { data: statusResult, response: statusResponse } = await api.get('/v1/build/status/{uuid}', {
+          path: { uuid },
           headers: {
             'Cache-Control': 'no-cache, no-store, must-revalidate',
             'Pragma': 'no-cache',
@@ -1101,17 +1102,16 @@ const AppContent = () => {
           cache: 'no-store', // Prevent browser cache
         });
 
-        if (!statusResponse.ok) {
+        if (!statusResult) {
           if (statusResponse.status === 404) {
             throw new Error('Build not found. It may have been cleaned up.');
           }
           throw new Error(`Failed to fetch build status: ${statusResponse.statusText}`);
         }
 
-        const statusResult = await statusResponse.json();
         console.log(`Build status poll #${pollCount}:`, statusResult.status);
 
-        if (statusResult.status === 'building') {
+        if (statusResult.status === 'queued' || statusResult.status === 'building') {
           // Show progress update every 10 polls (20 seconds)
           if (pollCount % 10 === 0) {
             const elapsed = Math.floor((pollCount * pollInterval) / 1000);
@@ -1134,10 +1134,10 @@ const AppContent = () => {
           try {
             const program_name = statusResult.program_name || fullCurrentProject.name;
 
-            const binaryResponse = await fetch(
-              `${API_URL}/deploy/${uuid}/${program_name}`,
-              { headers: { Accept: 'application/octet-stream' } }
-            );
+            const { response: binaryResponse } = await api.get('/v1/deploy/{uuid}/{program_name}', {
+              path: { uuid, program_name },
+              headers: { Accept: 'application/octet-stream' },
+            });
 
             if (!binaryResponse.ok) {
               throw new Error(`Failed to fetch binary: ${binaryResponse.statusText}`);
@@ -0,0 +1,7 @@
+// API key for build servers that set `require_api_key`. It is baked into the
+// bundle, so use a key issued for this IDE deployment, not a personal one.
+const API_KEY = import.meta.env.VITE_API_KEY;
+
+export function authHeaders(): Record<string, string> {
+  return API_KEY ? { Authorization: `Bearer ${API_KEY}` } : {};
+}
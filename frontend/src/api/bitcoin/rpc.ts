@@ -1,59 +1,43 @@
-import type { Config } from '../../types/config';
+import { api } from '../client';
+
+// Name of the server-side `bitcoin_rpc` network used for devnet. The node's
+// URL and credentials live in the server config and never reach the browser.
+export const DEFAULT_BITCOIN_NETWORK = 'regtest';
 
 export async function bitcoinRpcRequest(
-  config: { url: string; username: string; password: string },
+  config: { network: string },
   method: string,
   params: any[] = [],
   wallet?: string
 ) {
   try {
-    const isLocalhost = window.location.hostname === 'localhost';
-    const baseUrl = isLocalhost ? 'http://localhost:8010/proxy' : config.url;
-    const url = wallet ? `${baseUrl}/wallet/${wallet}` : baseUrl;
-
-    const response = await fetch(url, {
-      method: 'POST',
-      headers: {
-        'Content-Type': 'application/json',
-        'Authorization': 'Basic ' + btoa(`${config.username}:${config.password}`)
-      },
-      body: JSON.stringify({
-        jsonrpc: '2.0',
-        id: 'arch',
-        method,
-        params
-      })
+    const { data, error, response } = await api.post('/v1/bitcoin/rpc', {
+      body: { network: config.network, method, params, wallet }
     });
 
-    const data = await response.json();
+    // Node errors and server errors (e.g. a blocked method) both carry `error.message`
+    const result: any = data ?? error;
+    if (!result) {
+      throw new Error(`Bitcoin RPC failed with status: ${response.status}`);
+    }
 
-    if (data.error) {
+    if (result.error) {
       // Check if wallet needs to be loaded
-      if (wallet && data.error.message.includes('not loaded')) {
+      if (wallet && result.error.message?.includes('not loaded')) {
         // Try to load wallet
-        await fetch(baseUrl, {
-          method: 'POST',
-          headers: {
-            'Content-Type': 'application/json',
-            'Authorization': 'Basic ' + btoa(`${config.username}:${config.password}`)
-          },
-          body: JSON.stringify({
-            jsonrpc: '2.0',
-            id: 'arch',
-            method: 'loadwallet',
-            params: [wallet]
-          })
+        await api.post('/v1/bitcoin/rpc', {
+          body: { network: config.network, method: 'loadwallet', params: [wallet] }
         });
 
         // Retry original request
         return bitcoinRpcRequest(config, method, params, wallet);
       }
-      throw new Error(data.error.message || 'Bitcoin RPC error');
+      throw new Error(result.error.message || 'Bitcoin RPC error');
     }
 
-    return data;
+    return result;
   } catch (error) {
     console.error('Bitcoin RPC error:', error);
     throw error;
   }
-}
\ No newline at end of file
+}
@@ -0,0 +1,107 @@
+import { authHeaders } from './auth';
+import type { components, paths } from './schema';
+
+const API_URL = import.meta.env.VITE_API_URL || 'http://localhost:8080';
+
+export type Schemas = components['schemas'];
+export type ErrorEnvelope = Schemas['ErrorEnvelope'];
+
+type Method = 'get' | 'post';
+
+// Paths that have an operation for `M`, and that operation
+type PathsWith<M extends Method> = {
+  [P in keyof paths]: paths[P] extends Record<M, unknown> ? P : never;
+}[keyof paths];
+type Operation<P extends keyof paths, M extends Method> = paths[P] extends Record<M, infer O> ? O : never;
+
+type Json<T> = T extends { content: { 'application/json': infer B } } ? B : never;
+type Success<O> = O extends { responses: infer R }
+  ? { [S in keyof R]: S extends 200 | 201 | 202 ? Json<R[S]> : never }[keyof R]
+  : never;
+
+type PathParams<O> = O extends { parameters: { path: infer P } } ? { path: P } : { path?: never };
+type QueryParams<O> = O extends { parameters: { query?: infer Q } } ? { query?: Q } : { query?: never };
+type RequestBody<O> = O extends { requestBody: { content: { 'application/json': infer B } } }
+  ? { body: B }
+  : { body?: never };
+
+export type RequestOptions<O> = PathParams<O> & QueryParams<O> & RequestBody<O> & {
+  headers?: Record<string, string>;
+  cache?: RequestCache;
+};
+
+// The options argument may be left out when nothing in it is required
+type Args<O> = {} extends RequestOptions<O> ? [options?: RequestOptions<O>] : [options: RequestOptions<O>];
+
+export interface ApiResult<T> {
+  response: Response;
+  /** Parsed JSON body of a successful response; other bodies are left unread */
+  data?: T;
+  /** Parsed JSON body of a failed response, normally an `ErrorEnvelope` */
+  error?: ErrorEnvelope;
+}
+
+function url(path: string, params: Record<string, unknown> = {}, query: Record<string, unknown> = {}) {
+  const filled = path.replace(/\{(\w+)\}/g, (_, name) => encodeURIComponent(String(params[name])));
+  const search = new URLSearchParams();
+  for (const [name, value] of Object.entries(query)) {
+    if (value !== undefined && value !== null) search.set(name, String(value));
+  }
+  const qs = search.toString();
+  return `${API_URL}${filled}${qs ? `?${qs}` : ''}`;
+}
+
+interface Options {
+  path?: Record<string, unknown>;
+  query?: Record<string, unknown>;
+  body?: unknown;
+  headers?: Record<string, string>;
+  cache?: RequestCache;
+}
+
+async function request(method: Method, path: string, options: Options = {}): Promise<ApiResult<any>> {
+  const headers: Record<string, string> = { Accept: 'application/json', ...authHeaders(), ...options.headers };
+  if (options.body !== undefined) headers['Content-Type'] = 'application/json';
+
+  const response = await fetch(url(path, options.path, options.query), {
+    method: method.toUpperCase(),
+    headers,
+    body: options.body === undefined ? undefined : JSON.stringify(options.body),
+    cache: options.cache,
+  });
+
+  if (!response.headers.get('Content-Type')?.includes('application/json')) {
+    return { response };
+  }
+  const body = await response.json().catch(() => undefined);
+  return response.ok ? { response, data: body } : { response, error: body };
+}
+
+function get<P extends PathsWith<'get'>>(
+  path: P,
+  ...options: Args<Operation<P, 'get'>>
+): Promise<ApiResult<Success<Operation<P, 'get'>>>>;
+function get(path: string, options?: Options) {
+  return request('get', path, options);
+}
+
+function post<P extends PathsWith<'post'>>(
+  path: P,
+  ...options: Args<Operation<P, 'post'>>
+): Promise<ApiResult<Success<Operation<P, 'post'>>>>;
+function post(path: string, options?: Options) {
+  return request('post', path, options);
+}
+
+/**
+ * Typed calls to the build server, checked against `schema.ts`. Requests carry
+ * the API key from `authHeaders`.
+ */
+export const api = { get, post };
+
+/** The message of an error envelope with one line per field error, or `fallback`. */
+export function errorMessage(error: ErrorEnvelope | undefined, fallback: string): string {
+  if (!error?.error?.message) return fallback;
+  const details = (error.error.details ?? []).map((d) => `\n  ${d.message}`).join('');
+  return `${error.error.message}${details}`;
+}
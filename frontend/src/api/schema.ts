@@ -0,0 +1,1557 @@
+/**
+ * Types of the build server's API, generated from rust-server/openapi.json.
+ * Do not edit; regenerate with `UPDATE_OPENAPI=1 cargo test openapi` in rust-server.
+ */
+
+export interface paths {
+    "/health": {
+        get: operations["health"];
+    };
+    "/health/details": {
+        get: operations["health_details"];
+    };
+    "/metrics": {
+        get: operations["metrics_export"];
+    };
+    "/openapi.json": {
+        get: operations["openapi_json"];
+    };
+    "/ready": {
+        get: operations["ready"];
+    };
+    "/v1/admin/artifacts/purge": {
+        post: operations["purge_artifacts"];
+    };
+    "/v1/admin/builds": {
+        get: operations["list_builds"];
+    };
+    "/v1/admin/builds/{uuid}/cancel": {
+        post: operations["cancel_build"];
+    };
+    "/v1/admin/config/reload": {
+        post: operations["reload_config"];
+    };
+    "/v1/admin/disk": {
+        get: operations["disk_usage"];
+    };
+    "/v1/admin/usage": {
+        get: operations["admin_usage"];
+    };
+    "/v1/admin/vendor/refresh": {
+        post: operations["refresh_vendor"];
+    };
+    "/v1/admin/warmup": {
+        post: operations["rerun_warmup"];
+    };
+    "/v1/bitcoin/rpc": {
+        post: operations["bitcoin_rpc"];
+    };
+    "/v1/build": {
+        post: operations["build"];
+    };
+    "/v1/build/status/{uuid}": {
+        get: operations["build_status"];
+    };
+    "/v1/deploy/{uuid}": {
+        post: operations["deploy_program"];
+    };
+    "/v1/deploy/{uuid}/{program_name}": {
+        get: operations["deploy"];
+    };
+    "/v1/idl/decode": {
+        post: operations["idl_decode"];
+    };
+    "/v1/idl/encode": {
+        post: operations["idl_encode"];
+    };
+    "/v1/localnet/reset": {
+        post: operations["localnet_reset"];
+    };
+    "/v1/localnet/start": {
+        post: operations["localnet_start"];
+    };
+    "/v1/localnet/status": {
+        get: operations["localnet_status"];
+    };
+    "/v1/localnet/stop": {
+        post: operations["localnet_stop"];
+    };
+    "/v1/rpc": {
+        post: operations["rpc_proxy"];
+    };
+    "/v1/rpc/health": {
+        get: operations["rpc_health"];
+    };
+    "/v1/tools/bip322/sign-test": {
+        post: operations["bip322_sign_test"];
+    };
+    "/v1/tools/bip322/verify": {
+        post: operations["bip322_verify"];
+    };
+    "/v1/tools/decode": {
+        post: operations["decode"];
+    };
+    "/v1/usage": {
+        get: operations["usage"];
+    };
+}
+
+export interface components {
+    schemas: {
+        AccountKeyBreakdown: {
+            pubkey: string;
+            signer: boolean;
+            writable: boolean;
+        };
+        ArtifactStore: {
+            error?: string | null;
+            /**
+             * @description `gcs` or `local`
+             */
+            kind: string;
+            reachable: boolean;
+        };
+        BitcoinInputBreakdown: {
+            previous_output: string;
+            script_sig: string;
+            sequence: number;
+            witness: string[];
+        };
+        BitcoinOutputBreakdown: {
+            address?: string | null;
+            script_pubkey: string;
+            value: number;
+        };
+        BitcoinRpcRequest: {
+            method: string;
+            network: string;
+            params?: unknown[];
+            /**
+             * @description Route the call to `/wallet/<name>` on the node
+             */
+            wallet?: string | null;
+        };
+        BitcoinTransactionBreakdown: {
+            inputs: components["schemas"]["BitcoinInputBreakdown"][];
+            lock_time: number;
+            outputs: components["schemas"]["BitcoinOutputBreakdown"][];
+            size: number;
+            txid: string;
+            version: number;
+            vsize: number;
+            weight: number;
+            wtxid: string;
+        };
+        BuildRequest: {
+            /**
+             * @description `[path, content]` pairs. Paths are `.rs` files under `/src/`, `/tests/`,
+             * `/benches/` or `/examples/`, or `.json`, `.md`, `.txt`, `.csv` and `.yaml`
+             * data files at the root or under those directories and `/data/`. Each
+             * file and all files together are capped in size.
+             */
+            files: string[][];
+            program_name: string;
+            /**
+             * @description Rebuild an existing build in place instead of starting a new one
+             */
+            uuid?: string | null;
+        };
+        BuildResponse: {
+            program_name: string;
+            status: string;
+            uuid: string;
+        };
+        BuildStatus: "queued" | "building" | "success" | "failed" | "cancelled";
+        BuildStatusResponse: {
+            completed_at?: string | null;
+            deployment?: components["schemas"]["DeploymentInfo"] | null;
+            program_name: string;
+            started_at: string;
+            /**
+             * @description `queued`, `building`, `success`, `failed` or `cancelled`
+             */
+            status: string;
+            stderr?: string | null;
+            uuid: string;
+        };
+        BuildSummary: {
+            completed_at?: string | null;
+            deploying: boolean;
+            owner: string;
+            program_name: string;
+            started_at: string;
+            status: components["schemas"]["BuildStatus"];
+            uuid: string;
+        };
+        Builds: {
+            active: number;
+            capacity: number;
+            /**
+             * @description `active / capacity`; above 1 means builds are queued
+             */
+            saturation: number;
+        };
+        Change: {
+            /**
+             * @description Dotted path of the setting, e.g. `rpc_upstreams.testnet`
+             */
+            field: string;
+            new?: unknown | null;
+            old?: unknown | null;
+        };
+        DecodeKind: "arch_message" | "runtime_transaction" | "bitcoin_transaction";
+        DecodeRequest: {
+            data: string;
+            encoding?: components["schemas"]["Encoding"] | null;
+            kind?: components["schemas"]["DecodeKind"] | null;
+            /**
+             * @description Bitcoin network used to render output addresses
+             */
+            network?: string | null;
+        };
+        Decoded: (components["schemas"]["MessageBreakdown"] & {
+            kind: "arch_message";
+        }) | (components["schemas"]["TransactionBreakdown"] & {
+            kind: "runtime_transaction";
+        }) | (components["schemas"]["BitcoinTransactionBreakdown"] & {
+            kind: "bitcoin_transaction";
+        });
+        DeployRequest: {
+            fee_payer: components["schemas"]["FeePayer"];
+            network?: string | null;
+            /**
+             * @description Hex-encoded secret key of the program account
+             */
+            program_keypair: string;
+            program_name: string;
+        };
+        DeployResponse: {
+            network: string;
+            program_id: string;
+            status: string;
+            uuid: string;
+        };
+        /**
+         * @description Progress of a server-side deployment of a build's program.
+         */
+        DeploymentInfo: {
+            chunks_total: number;
+            chunks_written: number;
+            completed_at?: string | null;
+            error?: string | null;
+            network: string;
+            program_id: string;
+            stage: string;
+            started_at: string;
+            status: components["schemas"]["DeploymentStatus"];
+            txids: string[];
+        };
+        DeploymentStatus: "running" | "success" | "failed";
+        Disk: {
+            available_bytes?: number | null;
+            path: string;
+        };
+        DiskUsage: {
+            build_dirs: number;
+            /**
+             * @description Shared crate registry and sources, `programs/.cargo`
+             */
+            cargo_home_bytes: number;
+            /**
+             * @description Everything under `programs/`, including the two entries below
+             */
+            programs_bytes: number;
+            /**
+             * @description Shared incremental build cache, `programs/target`
+             */
+            target_bytes: number;
+        };
+        Encoding: "hex" | "base64";
+        ErrorBody: {
+            code: components["schemas"]["ErrorCode"];
+            details?: components["schemas"]["FieldError"][];
+            message: string;
+        };
+        /**
+         * @description Stable, machine-readable error codes. Clients should branch on these, not
+         * on the message text, which may change.
+         */
+        ErrorCode: "BAD_REQUEST" | "INVALID_JSON" | "PAYLOAD_TOO_LARGE" | "INVALID_UUID" | "INVALID_PATH" | "TOO_MANY_FILES" | "INVALID_KEYPAIR" | "INVALID_JSON_RPC" | "UNSUPPORTED_API_VERSION" | "UNAUTHORIZED" | "FORBIDDEN" | "METHOD_NOT_ALLOWED" | "NOT_FOUND" | "BUILD_NOT_FOUND" | "PROGRAM_NOT_BUILT" | "NETWORK_NOT_FOUND" | "CONFLICT" | "BUILD_NOT_READY" | "BUILD_IN_PROGRESS" | "DEPLOYMENT_IN_PROGRESS" | "QUOTA_EXCEEDED" | "SHUTTING_DOWN" | "UPSTREAM_ERROR" | "UPSTREAM_TIMEOUT" | "INTERNAL";
+        /**
+         * @description Body of every error response: `{"error": {"code", "message", "details"}}`.
+         */
+        ErrorEnvelope: {
+            error: components["schemas"]["ErrorBody"];
+        };
+        FeePayer: {
+            type: "localnet";
+        } | {
+            pubkey: string;
+            type: "callback";
+            url: string;
+        };
+        /**
+         * @description What is wrong with one field of the request, e.g. `files[2].path`.
+         */
+        FieldError: {
+            field: string;
+            message: string;
+        };
+        HealthDetails: {
+            artifact_store: components["schemas"]["ArtifactStore"];
+            builds: components["schemas"]["Builds"];
+            disk: components["schemas"]["Disk"];
+            rpc_upstreams: {
+                [key: string]: components["schemas"]["NetworkStatus"];
+            };
+            /**
+             * @description `ok`, `degraded` or `draining`
+             */
+            status: string;
+            toolchain: components["schemas"]["Toolchain"];
+            vendored_mirror?: components["schemas"]["VendorMirror"] | null;
+            /**
+             * @description One entry per configured SDK profile
+             */
+            warmup: components["schemas"]["ProfileWarmup"][];
+        };
+        IdlDecodeRequest: {
+            /**
+             * @description Account type to decode `data` as
+             */
+            account?: string | null;
+            data: string;
+            discriminator?: boolean;
+            encoding?: components["schemas"]["Encoding"] | null;
+            idl: Record<string, unknown>;
+            /**
+             * @description Instruction to decode `data` as; picked by its index byte when only `discriminator` is set
+             */
+            instruction?: string | null;
+        };
+        IdlDecodeResponse: {
+            /**
+             * @description `account` or `instruction`
+             */
+            kind: string;
+            name: string;
+            /**
+             * @description Bytes left after the decoded value, e.g. unused space in an account
+             */
+            remaining_bytes: number;
+            value: unknown;
+        };
+        IdlEncodeRequest: {
+            /**
+             * @description Object keyed by arg name, or an array in declaration order
+             */
+            args?: unknown;
+            /**
+             * @description Prefix the data with the instruction's `u8` index
+             */
+            discriminator?: boolean;
+            idl: Record<string, unknown>;
+            instruction: string;
+        };
+        IdlEncodeResponse: {
+            base64: string;
+            hex: string;
+            instruction: string;
+            length: number;
+        };
+        InstructionBreakdown: {
+            accounts: string;
+            data: string;
+            instruction_name?: string | null;
+            program_id?: string | null;
+            program_id_index: number;
+            program_name?: string | null;
+        };
+        LocalnetState: "stopped" | "running" | "exited";
+        LocalnetStatus: {
+            bitcoind?: components["schemas"]["ProcessStatus"] | null;
+            fee_payer: string;
+            network: string;
+            rpc_url: string;
+            state: components["schemas"]["LocalnetState"];
+            validator?: components["schemas"]["ProcessStatus"] | null;
+        };
+        MessageBreakdown: {
+            account_keys: components["schemas"]["AccountKeyBreakdown"][];
+            header: components["schemas"]["MessageHeader"];
+            instructions: components["schemas"]["InstructionBreakdown"][];
+            message_hash: string;
+            recent_blockhash: string;
+        };
+        MessageHeader: {
+            num_readonly_signed_accounts: number;
+            num_readonly_unsigned_accounts: number;
+            num_required_signatures: number;
+        };
+        NetworkStatus: {
+            default: boolean;
+            upstreams: components["schemas"]["UpstreamStatus"][];
+        };
+        ProcessStatus: {
+            exit_status?: string | null;
+            log_file: string;
+            pid?: number | null;
+            started_at: string;
+        };
+        /**
+         * @description Warmup of one SDK profile.
+         */
+        ProfileWarmup: {
+            /**
+             * @description Of the profile's manifest, warmup source and toolchain; a warmup built
+             * under another fingerprint is redone
+             */
+            fingerprint: string;
+            profile: string;
+            state: components["schemas"]["WarmupState"];
+        };
+        PurgeRequest: {
+            /**
+             * @description Purge every build whose project directory is older than this
+             */
+            older_than_hours?: number | null;
+            uuid?: string | null;
+        };
+        PurgeResponse: {
+            /**
+             * @description Builds that matched but could not be deleted
+             */
+            failed: string[];
+            purged: string[];
+        };
+        /**
+         * @description Daily limits for one tier of principals; `None` means unlimited.
+         */
+        Quota: {
+            artifact_bytes_per_day?: number | null;
+            build_seconds_per_day?: number | null;
+            builds_per_day?: number | null;
+            rpc_calls_per_day?: number | null;
+        };
+        Readiness: {
+            ready: boolean;
+            reasons: string[];
+        };
+        ReloadReport: {
+            applied: components["schemas"]["Change"][];
+            /**
+             * @description Changes to settings only read at startup
+             */
+            requires_restart: components["schemas"]["Change"][];
+        };
+        SignTestRequest: components["schemas"]["SignedPayload"] & {
+            /**
+             * @description Hex secret key; a throwaway key is generated when omitted
+             */
+            private_key?: string | null;
+        };
+        SignTestResponse: {
+            message_hash: string;
+            private_key: string;
+            pubkey: string;
+            serialized_message?: string | null;
+            signature: string;
+        };
+        SignatureBreakdown: {
+            signature: string;
+            signer?: string | null;
+            /**
+             * @description Whether the signature is a valid BIP-322 signature of the message by `signer`
+             */
+            valid: boolean;
+        };
+        /**
+         * @description What gets signed: an `ArchMessage`, hashed the way the validator does, or
+         * an already computed 64-character message hash.
+         */
+        SignedPayload: {
+            /**
+             * @description Hex of the serialized message, or the message as JSON
+             */
+            message?: Record<string, unknown> | null;
+            message_hash?: string | null;
+        };
+        /**
+         * @description An installed tool and the first line of its `--version`.
+         */
+        Tool: {
+            path?: string | null;
+            version?: string | null;
+        };
+        Toolchain: {
+            cargo: components["schemas"]["Tool"];
+            "cargo-build-sbf": components["schemas"]["Tool"];
+            "platform-tools": components["schemas"]["Tool"];
+        };
+        TransactionBreakdown: {
+            message: components["schemas"]["MessageBreakdown"];
+            signatures: components["schemas"]["SignatureBreakdown"][];
+            txid: string;
+            version: number;
+        };
+        /**
+         * @description Health of a single upstream RPC URL as seen by the last probe or proxied call.
+         */
+        UpstreamHealth: {
+            block_height?: number | null;
+            consecutive_failures: number;
+            healthy: boolean;
+            /**
+             * @description When a probe last saw `block_height` go up
+             */
+            height_advanced_at?: string | null;
+            last_checked?: string | null;
+            last_error?: string | null;
+            latency_ms?: number | null;
+        };
+        UpstreamStatus: components["schemas"]["UpstreamHealth"] & {
+            url: string;
+        };
+        Usage: {
+            /**
+             * @description Bytes of program binaries produced
+             */
+            artifact_bytes: number;
+            /**
+             * @description CPU seconds the build toolchain used, across all of its processes
+             */
+            build_seconds: number;
+            builds: number;
+            rpc_calls: number;
+        };
+        UsageReport: {
+            day: string;
+            limits?: components["schemas"]["Quota"] | null;
+            principal: string;
+            today: components["schemas"]["Usage"];
+            total: components["schemas"]["Usage"];
+        };
+        /**
+         * @description The vendored registry mirror offline builds use.
+         */
+        VendorMirror: {
+            /**
+             * @description Crates in the mirror; 0 when it was never refreshed
+             */
+            crates: number;
+            refreshed_at?: string | null;
+        };
+        VerifyRequest: components["schemas"]["SignedPayload"] & {
+            /**
+             * @description Untweaked x-only public key, hex
+             */
+            pubkey: string;
+            /**
+             * @description 64-byte signature, or 65 bytes with a trailing sighash type, hex
+             */
+            signature: string;
+        };
+        VerifyResponse: {
+            error?: string | null;
+            /**
+             * @description The signed bytes, i.e. the 64 UTF-8 hex characters of the message hash
+             */
+            message_hash: string;
+            serialized_message?: string | null;
+            sighash_type?: string | null;
+            valid: boolean;
+        };
+        /**
+         * @description Progress of a profile's background warmup build.
+         */
+        WarmupState: {
+            state: "not_started";
+        } | {
+            state: "queued";
+        } | {
+            crates_compiled: number;
+            started_at: string;
+            state: "running";
+        } | {
+            duration_secs?: number | null;
+            state: "ready";
+        } | {
+            error: string;
+            state: "failed";
+        };
+    };
+}
+
+export interface operations {
+    health: {
+        parameters: {
+            query?: never;
+            path?: never;
+        };
+        responses: {
+            /**
+             * @description `OK`
+             */
+            200: {
+                content: {
+                    "text/plain": string;
+                };
+            };
+        };
+    };
+    health_details: {
+        parameters: {
+            query?: never;
+            path?: never;
+        };
+        responses: {
+            /**
+             * @description Detailed health
+             */
+            200: {
+                content: {
+                    "application/json": components["schemas"]["HealthDetails"];
+                };
+            };
+        };
+    };
+    metrics_export: {
+        parameters: {
+            query?: never;
+            path?: never;
+        };
+        responses: {
+            /**
+             * @description OpenMetrics text
+             */
+            200: {
+                content: {
+                    "application/openmetrics-text": string;
+                };
+            };
+        };
+    };
+    openapi_json: {
+        parameters: {
+            query?: never;
+            path?: never;
+        };
+        responses: {
+            /**
+             * @description OpenAPI 3 document
+             */
+            200: {
+                content: {
+                    "application/json": Record<string, unknown>;
+                };
+            };
+        };
+    };
+    ready: {
+        parameters: {
+            query?: never;
+            path?: never;
+        };
+        responses: {
+            /**
+             * @description Ready for builds
+             */
+            200: {
+                content: {
+                    "application/json": components["schemas"]["Readiness"];
+                };
+            };
+            /**
+             * @description Not ready; `reasons` says why
+             */
+            503: {
+                content: {
+                    "application/json": components["schemas"]["Readiness"];
+                };
+            };
+        };
+    };
+    purge_artifacts: {
+        parameters: {
+            query?: never;
+            path?: never;
+        };
+        requestBody: {
+            content: {
+                "application/json": components["schemas"]["PurgeRequest"];
+            };
+        };
+        responses: {
+            /**
+             * @description What was purged
+             */
+            200: {
+                content: {
+                    "application/json": components["schemas"]["PurgeResponse"];
+                };
+            };
+            /**
+             * @description Neither or both of `uuid` and `older_than_hours`
+             */
+            400: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+            /**
+             * @description Not an admin
+             */
+            403: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+        };
+    };
+    list_builds: {
+        parameters: {
+            query?: {
+                /**
+                 * @description `queued`, `building`, `success`, `failed`, ...
+                 */
+                status?: string | null;
+                owner?: string | null;
+                /**
+                 * @description Only builds started at or after this RFC 3339 time
+                 */
+                since?: string | null;
+                /**
+                 * @description At most this many builds, 100 by default
+                 */
+                limit?: number | null;
+            };
+            path?: never;
+        };
+        responses: {
+            /**
+             * @description Matching builds
+             */
+            200: {
+                content: {
+                    "application/json": components["schemas"]["BuildSummary"][];
+                };
+            };
+            /**
+             * @description Not an admin
+             */
+            403: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+        };
+    };
+    cancel_build: {
+        parameters: {
+            query?: never;
+            path: {
+                /**
+                 * @description Build UUID
+                 */
+                uuid: string;
+            };
+        };
+        responses: {
+            /**
+             * @description Cancelled
+             */
+            204: {
+                content?: never;
+            };
+            /**
+             * @description Not an admin
+             */
+            403: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+            /**
+             * @description No running build with this UUID
+             */
+            404: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+        };
+    };
+    reload_config: {
+        parameters: {
+            query?: never;
+            path?: never;
+        };
+        responses: {
+            /**
+             * @description Settings applied and those needing a restart
+             */
+            200: {
+                content: {
+                    "application/json": components["schemas"]["ReloadReport"];
+                };
+            };
+            /**
+             * @description The new config is invalid
+             */
+            400: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+            /**
+             * @description Not an admin
+             */
+            403: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+        };
+    };
+    disk_usage: {
+        parameters: {
+            query?: never;
+            path?: never;
+        };
+        responses: {
+            /**
+             * @description Disk usage
+             */
+            200: {
+                content: {
+                    "application/json": components["schemas"]["DiskUsage"];
+                };
+            };
+            /**
+             * @description Not an admin
+             */
+            403: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+        };
+    };
+    admin_usage: {
+        parameters: {
+            query?: never;
+            path?: never;
+        };
+        responses: {
+            /**
+             * @description Usage by principal
+             */
+            200: {
+                content: {
+                    "application/json": components["schemas"]["UsageReport"][];
+                };
+            };
+            /**
+             * @description Not an admin
+             */
+            403: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+        };
+    };
+    refresh_vendor: {
+        parameters: {
+            query?: never;
+            path?: never;
+        };
+        responses: {
+            /**
+             * @description The refreshed mirror
+             */
+            200: {
+                content: {
+                    "application/json": components["schemas"]["VendorMirror"];
+                };
+            };
+            /**
+             * @description Not an admin
+             */
+            403: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+            /**
+             * @description A refresh is already running
+             */
+            409: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+        };
+    };
+    rerun_warmup: {
+        parameters: {
+            query?: never;
+            path?: never;
+        };
+        responses: {
+            /**
+             * @description Warmup started; follow it in `/health/details`
+             */
+            202: {
+                content?: never;
+            };
+            /**
+             * @description Not an admin
+             */
+            403: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+            /**
+             * @description Warmup is already running
+             */
+            409: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+        };
+    };
+    bitcoin_rpc: {
+        parameters: {
+            query?: never;
+            path?: never;
+        };
+        requestBody: {
+            content: {
+                "application/json": components["schemas"]["BitcoinRpcRequest"];
+            };
+        };
+        responses: {
+            /**
+             * @description The node's JSON-RPC response; node errors keep their status code
+             */
+            200: {
+                content: {
+                    "application/json": Record<string, unknown>;
+                };
+            };
+            /**
+             * @description Invalid request or wallet name
+             */
+            400: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+            /**
+             * @description Method not on the allowlist
+             */
+            403: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+            /**
+             * @description Unknown network
+             */
+            404: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+            /**
+             * @description RPC quota exceeded
+             */
+            429: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+            /**
+             * @description Node unreachable or answered garbage
+             */
+            502: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+            /**
+             * @description Node timed out
+             */
+            504: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+        };
+    };
+    build: {
+        parameters: {
+            query?: never;
+            path?: never;
+        };
+        requestBody: {
+            content: {
+                "application/json": components["schemas"]["BuildRequest"];
+            };
+        };
+        responses: {
+            /**
+             * @description Build started
+             */
+            200: {
+                content: {
+                    "application/json": components["schemas"]["BuildResponse"];
+                };
+            };
+            /**
+             * @description Invalid files or UUID
+             */
+            400: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+            /**
+             * @description The UUID belongs to someone else's build
+             */
+            404: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+            /**
+             * @description A build or deployment of this UUID is still running
+             */
+            409: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+            /**
+             * @description A file or all files together are too large
+             */
+            413: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+            /**
+             * @description Build quota exceeded
+             */
+            429: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+            /**
+             * @description Server is shutting down
+             */
+            503: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+        };
+    };
+    build_status: {
+        parameters: {
+            query?: never;
+            path: {
+                /**
+                 * @description Build UUID
+                 */
+                uuid: string;
+            };
+        };
+        responses: {
+            /**
+             * @description Build found
+             */
+            200: {
+                content: {
+                    "application/json": components["schemas"]["BuildStatusResponse"];
+                };
+            };
+            /**
+             * @description No such build
+             */
+            404: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+        };
+    };
+    deploy_program: {
+        parameters: {
+            query?: never;
+            path: {
+                /**
+                 * @description Build UUID
+                 */
+                uuid: string;
+            };
+        };
+        requestBody: {
+            content: {
+                "application/json": components["schemas"]["DeployRequest"];
+            };
+        };
+        responses: {
+            /**
+             * @description Deployment started
+             */
+            202: {
+                content: {
+                    "application/json": components["schemas"]["DeployResponse"];
+                };
+            };
+            /**
+             * @description Invalid keypair or fee payer, or callback host not allowed
+             */
+            400: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+            /**
+             * @description No such build, or it has no binary
+             */
+            404: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+            /**
+             * @description Build not finished, or already deploying
+             */
+            409: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+            /**
+             * @description Server is shutting down
+             */
+            503: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+        };
+    };
+    deploy: {
+        parameters: {
+            query?: never;
+            path: {
+                /**
+                 * @description Build UUID
+                 */
+                uuid: string;
+                /**
+                 * @description Name of the built program
+                 */
+                program_name: string;
+            };
+        };
+        responses: {
+            /**
+             * @description Program binary
+             */
+            200: {
+                content: {
+                    "application/octet-stream": string;
+                };
+            };
+            /**
+             * @description No such build, or it has no binary
+             */
+            404: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+        };
+    };
+    idl_decode: {
+        parameters: {
+            query?: never;
+            path?: never;
+        };
+        requestBody: {
+            content: {
+                "application/json": components["schemas"]["IdlDecodeRequest"];
+            };
+        };
+        responses: {
+            /**
+             * @description Decoded value
+             */
+            200: {
+                content: {
+                    "application/json": components["schemas"]["IdlDecodeResponse"];
+                };
+            };
+            /**
+             * @description Data that does not fit the IDL
+             */
+            400: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+        };
+    };
+    idl_encode: {
+        parameters: {
+            query?: never;
+            path?: never;
+        };
+        requestBody: {
+            content: {
+                "application/json": components["schemas"]["IdlEncodeRequest"];
+            };
+        };
+        responses: {
+            /**
+             * @description Encoded instruction data
+             */
+            200: {
+                content: {
+                    "application/json": components["schemas"]["IdlEncodeResponse"];
+                };
+            };
+            /**
+             * @description Unknown instruction or args that do not fit the IDL
+             */
+            400: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+        };
+    };
+    localnet_reset: {
+        parameters: {
+            query?: never;
+            path?: never;
+        };
+        responses: {
+            /**
+             * @description Status after restarting from a clean ledger
+             */
+            200: {
+                content: {
+                    "application/json": components["schemas"]["LocalnetStatus"];
+                };
+            };
+            /**
+             * @description Not an admin
+             */
+            403: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+            /**
+             * @description Localnet is disabled
+             */
+            404: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+        };
+    };
+    localnet_start: {
+        parameters: {
+            query?: never;
+            path?: never;
+        };
+        responses: {
+            /**
+             * @description Status after starting
+             */
+            200: {
+                content: {
+                    "application/json": components["schemas"]["LocalnetStatus"];
+                };
+            };
+            /**
+             * @description Not an admin
+             */
+            403: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+            /**
+             * @description Localnet is disabled
+             */
+            404: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+        };
+    };
+    localnet_status: {
+        parameters: {
+            query?: never;
+            path?: never;
+        };
+        responses: {
+            /**
+             * @description Current status
+             */
+            200: {
+                content: {
+                    "application/json": components["schemas"]["LocalnetStatus"];
+                };
+            };
+            /**
+             * @description Localnet is disabled
+             */
+            404: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+        };
+    };
+    localnet_stop: {
+        parameters: {
+            query?: never;
+            path?: never;
+        };
+        responses: {
+            /**
+             * @description Status after stopping
+             */
+            200: {
+                content: {
+                    "application/json": components["schemas"]["LocalnetStatus"];
+                };
+            };
+            /**
+             * @description Not an admin
+             */
+            403: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+            /**
+             * @description Localnet is disabled
+             */
+            404: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+        };
+    };
+    rpc_proxy: {
+        parameters: {
+            query?: {
+                /**
+                 * @description Named network to send to; the configured default when omitted
+                 */
+                network?: string | null;
+            };
+            path?: never;
+        };
+        requestBody: {
+            content: {
+                "application/json": Record<string, unknown>;
+            };
+        };
+        responses: {
+            /**
+             * @description The upstream's JSON-RPC response, with its status code
+             */
+            200: {
+                content: {
+                    "application/json": Record<string, unknown>;
+                };
+            };
+            /**
+             * @description Malformed JSON-RPC
+             */
+            400: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+            /**
+             * @description Method not on the allowlist
+             */
+            403: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+            /**
+             * @description Unknown network
+             */
+            404: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+            /**
+             * @description RPC quota exceeded
+             */
+            429: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+            /**
+             * @description No upstream answered
+             */
+            502: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+            /**
+             * @description Upstream timed out
+             */
+            504: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+        };
+    };
+    rpc_health: {
+        parameters: {
+            query?: never;
+            path?: never;
+        };
+        responses: {
+            /**
+             * @description Upstreams by network name
+             */
+            200: {
+                content: {
+                    "application/json": {
+                        [key: string]: components["schemas"]["NetworkStatus"];
+                    };
+                };
+            };
+        };
+    };
+    bip322_sign_test: {
+        parameters: {
+            query?: never;
+            path?: never;
+        };
+        requestBody: {
+            content: {
+                "application/json": components["schemas"]["SignTestRequest"];
+            };
+        };
+        responses: {
+            /**
+             * @description Signature and the key that made it
+             */
+            200: {
+                content: {
+                    "application/json": components["schemas"]["SignTestResponse"];
+                };
+            };
+            /**
+             * @description Malformed key or message
+             */
+            400: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+        };
+    };
+    bip322_verify: {
+        parameters: {
+            query?: never;
+            path?: never;
+        };
+        requestBody: {
+            content: {
+                "application/json": components["schemas"]["VerifyRequest"];
+            };
+        };
+        responses: {
+            /**
+             * @description Verification result; a wrong signature is `valid: false`, not an error
+             */
+            200: {
+                content: {
+                    "application/json": components["schemas"]["VerifyResponse"];
+                };
+            };
+            /**
+             * @description Malformed key, signature or message
+             */
+            400: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+        };
+    };
+    decode: {
+        parameters: {
+            query?: never;
+            path?: never;
+        };
+        requestBody: {
+            content: {
+                "application/json": components["schemas"]["DecodeRequest"];
+            };
+        };
+        responses: {
+            /**
+             * @description The decoded data, tagged by `kind`
+             */
+            200: {
+                content: {
+                    "application/json": components["schemas"]["Decoded"];
+                };
+            };
+            /**
+             * @description The data decodes as none of the kinds tried
+             */
+            400: {
+                content: {
+                    "application/json": components["schemas"]["ErrorEnvelope"];
+                };
+            };
+        };
+    };
+    usage: {
+        parameters: {
+            query?: never;
+            path?: never;
+        };
+        responses: {
+            /**
+             * @description The caller's usage
+             */
+            200: {
+                content: {
+                    "application/json": components["schemas"]["UsageReport"];
+                };
+            };
+        };
+    };
+}
@@ -2,11 +2,11 @@ import React, { useState, Dispatch, SetStateAction, useCallback, useEffect } fro
 import { Select, SelectContent, SelectItem, SelectTrigger, SelectValue } from "./ui/select";
 import { Switch } from "./ui/switch";
 import { Label } from "./ui/label";
-import { X, Globe, Link, Server, Key, Lock, Eye, AlertTriangle, Coins, Loader2, Wifi, HelpCircle } from 'lucide-react';
+import { X, Globe, Link, Server, Eye, AlertTriangle, Coins, Loader2, Wifi, HelpCircle } from 'lucide-react';
 import { Button } from './ui/button';
 import { Input } from './ui/input';
 import type { Config } from '../types';
-import { bitcoinRpcRequest } from '../api/bitcoin/rpc';
+import { bitcoinRpcRequest, DEFAULT_BITCOIN_NETWORK } from '../api/bitcoin/rpc';
 import { getSmartRpcUrl } from '../utils/smartRpcConnection';
 import { RpcConnection } from '@saturnbtcio/arch-sdk';
 import { Tooltip, TooltipContent, TooltipProvider, TooltipTrigger } from './ui/tooltip';
@@ -88,13 +88,13 @@ export const ConfigPanel = ({ isOpen, onClose, config, onConfigChange }: ConfigP
     [config.rpcUrl]
   );
 
-  const handleRegtestChange = (field: 'url' | 'username' | 'password', value: string) => {
-    console.log('handleRegtestChange called for field:', field);
+  const handleRegtestChange = (value: string) => {
+    console.log('handleRegtestChange called');
     onConfigChange(prevConfig => ({
       ...prevConfig,
       regtestConfig: {
         ...prevConfig.regtestConfig,
-        [field]: value
+        network: value
       }
     }));
     debouncedTestBitcoinConnection();
@@ -318,7 +318,7 @@ export const ConfigPanel = ({ isOpen, onClose, config, onConfigChange }: ConfigP
                   <div>
                     <h4 className="text-sm font-medium text-blue-400">Bitcoin Regtest Settings</h4>
                     <p className="text-xs text-gray-400 mt-1">
-                      These settings allow direct interaction with a Bitcoin node, enabling transactions without a web wallet.
+                      Bitcoin RPC calls go through the IDE server, which holds the node's URL and credentials.
                       Perfect for development and testing.
                     </p>
                   </div>
@@ -327,36 +327,12 @@ export const ConfigPanel = ({ isOpen, onClose, config, onConfigChange }: ConfigP
                     <div className="space-y-2">
                       <Label className="flex items-center gap-2">
                         <Server className="h-4 w-4 text-gray-400" />
-                        Bitcoin RPC URL
+                        Bitcoin Network
                       </Label>
                       <Input
-                        value={config.regtestConfig?.url || 'http://bitcoin-node.dev.aws.archnetwork.xyz:18443'}
-                        onChange={(e) => handleRegtestChange('url', e.target.value)}
-                        placeholder="http://bitcoin-node.dev.aws.archnetwork.xyz:18443"
-                      />
-                    </div>
-
-                    <div className="space-y-2">
-                      <Label className="flex items-center gap-2">
-                        <Key className="h-4 w-4 text-gray-400" />
-                        RPC Username
-                      </Label>
-                      <Input
-                        value={config.regtestConfig?.username || 'bitcoin'}
-                        onChange={(e) => handleRegtestChange('username', e.target.value)}
-                        placeholder="bitcoin"
-                      />
-                    </div>
-
-                    <div className="space-y-2">
-                      <Label className="flex items-center gap-2">
-                        <Lock className="h-4 w-4 text-gray-400" />
-                        RPC Password
-                      </Label>
-                      <Input type="password"
-                        value={config.regtestConfig?.password || '428bae8f3c94f8c39c50757fc89c39bc7e6ebc70ebf8f618'}
-                        onChange={(e) => handleRegtestChange('password', e.target.value)}
-                        placeholder="428bae8f3c94f8c39c50757fc89c39bc7e6ebc70ebf8f618"
+                        value={config.regtestConfig?.network || DEFAULT_BITCOIN_NETWORK}
+                        onChange={(e) => handleRegtestChange(e.target.value)}
+                        placeholder={DEFAULT_BITCOIN_NETWORK}
                       />
                     </div>
 
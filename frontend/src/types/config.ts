@@ -5,9 +5,8 @@ export interface Config {
     improveErrors: boolean;
     automaticAirdrop: boolean;
     regtestConfig: {
-        url: string;
-        username: string;
-        password: string;
+        // Server-side Bitcoin network name; see DEFAULT_BITCOIN_NETWORK
+        network: string;
     };
 }
 
@@ -19,8 +18,6 @@ export const DEFAULT_CONFIG: Config = {
     improveErrors: true,
     automaticAirdrop: true,
     regtestConfig: {
-        url: 'http://bitcoin-node.dev.aws.archnetwork.xyz:18443',
-        username: 'bitcoin',
-        password: '428bae8f3c94f8c39c50757fc89c39bc7e6ebc70ebf8f618'
+        network: 'regtest'
     }
 };
\ No newline at end of file
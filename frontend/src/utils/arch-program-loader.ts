@@ -96,9 +96,7 @@ interface ArchDeployOptions {
     address: string;
   };
   regtestConfig?: {
-    url: string;
-    username: string;
-    password: string;
+    network: string;
   };
   utxoInfo?: {
     txid: string;
@@ -187,7 +185,7 @@ export class ArchProgramLoader {
     programId: string,
     network: string,
     rpcUrl: string,
-    regtestConfig?: { url: string; username: string; password: string },
+    regtestConfig?: { network: string },
     utxoInfo?: { txid: string; vout: number }
   ): Promise<Instruction> {
     const address = await this.getAccountAddress(programId, rpcUrl);
@@ -260,7 +258,7 @@ export class ArchProgramLoader {
   private static async sendUtxo(
     address: string,
     network: string,
-    regtestConfig?: { url: string; username: string; password: string }
+    regtestConfig?: { network: string }
   ): Promise<{txid: string, vout: number}> {
     if (!address) {
       throw new Error('Bitcoin address is required');
@@ -299,10 +297,7 @@ export class ArchProgramLoader {
           throw new Error('Regtest configuration required for devnet');
         }
 
-        const isLocalhost = typeof window !== 'undefined' && window.location.hostname === 'localhost';
-        regtestConfig.url = isLocalhost ? 'http://localhost:8010/proxy' : regtestConfig.url;
-
-        // Use the bitcoinRpcRequest helper for all RPC calls
+        // Use the bitcoinRpcRequest helper for all RPC calls; the server holds the node credentials
         const sendResponse = await bitcoinRpcRequest(
           regtestConfig,
           'sendtoaddress',
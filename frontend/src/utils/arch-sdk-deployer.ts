@@ -117,9 +117,7 @@ interface DeployOptions {
     address: string;
   };
   regtestConfig?: {
-    url: string;
-    username: string;
-    password: string;
+    network: string;
   };
   utxoInfo?: {
     txid: string;
@@ -1621,7 +1619,7 @@ export class ArchProgramLoader {
     network: string;
     programBinary: Uint8Array;
     keypair: { privkey: string; pubkey: string; address: string };
-    regtestConfig?: { url: string; username: string; password: string };
+    regtestConfig?: { network: string };
     utxoInfo?: { txid: string; vout: number };
   }, onMessage?: (type: 'info' | 'success' | 'error', message: string) => void) {
 
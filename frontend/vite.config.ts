@@ -163,25 +163,6 @@ export default defineConfig({
         headers: {
           'Origin': process.env.VITE_CLIENT_URL || 'http://localhost:3000'
         }
-      },
-      '/api/bitcoin': {
-        target: 'http://localhost:8010/proxy',
-        changeOrigin: true,
-        secure: false,
-        rewrite: (path) => {
-          const url = new URL(path, 'http://localhost:8010');
-          const wallet = url.searchParams.get('wallet');
-          return wallet ? `/proxy/wallet/${wallet}` : '/proxy';
-        },
-        configure: (proxy, _options) => {
-          proxy.on('error', (err, _req, _res) => {
-            console.log('proxy error', err);
-          });
-          proxy.on('proxyReq', (proxyReq, req, _res) => {
-            const auth = Buffer.from('bitcoin:428bae8f3c94f8c39c50757fc89c39bc7e6ebc70ebf8f618').toString('base64');
-            proxyReq.setHeader('Authorization', `Basic ${auth}`);
-          });
-        }
       }
     }
   }
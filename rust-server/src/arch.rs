@@ -0,0 +1,519 @@
+//! Wire types of the Arch runtime, mirroring `arch_program::sanitized` and the
+//! system/loader instruction encodings used by `arch_sdk` program deployment.
+
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, bail};
+use bitcoin::hashes::{sha256, Hash as _};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+pub type Pubkey = [u8; 32];
+pub type Hash = [u8; 32];
+
+/// `Pubkey::system_program()`
+pub const SYSTEM_PROGRAM_ID: Pubkey = {
+    let mut id = [0u8; 32];
+    id[31] = 1;
+    id
+};
+
+/// `Pubkey(*b"BpfLoader11111111111111111111111")`
+pub const BPF_LOADER_ID: Pubkey = *b"BpfLoader11111111111111111111111";
+
+/// Size of `bpf_loader::LoaderState` stored in front of the ELF in a program account.
+pub const LOADER_STATE_SIZE: usize = 40;
+
+/// Maximum serialized size of a single `RuntimeTransaction`.
+pub const RUNTIME_TX_SIZE_LIMIT: usize = 10240;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct MessageHeader {
+    pub num_required_signatures: u8,
+    pub num_readonly_signed_accounts: u8,
+    pub num_readonly_unsigned_accounts: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SanitizedInstruction {
+    pub program_id_index: u8,
+    pub accounts: Vec<u8>,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchMessage {
+    pub header: MessageHeader,
+    pub account_keys: Vec<Pubkey>,
+    pub recent_blockhash: Hash,
+    pub instructions: Vec<SanitizedInstruction>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuntimeTransaction {
+    pub version: u32,
+    pub signatures: Vec<Vec<u8>>,
+    pub message: ArchMessage,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccountMeta {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+impl AccountMeta {
+    pub fn new(pubkey: Pubkey, is_signer: bool) -> Self {
+        Self { pubkey, is_signer, is_writable: true }
+    }
+
+    pub fn new_readonly(pubkey: Pubkey, is_signer: bool) -> Self {
+        Self { pubkey, is_signer, is_writable: false }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    pub program_id: Pubkey,
+    pub accounts: Vec<AccountMeta>,
+    pub data: Vec<u8>,
+}
+
+#[derive(Default)]
+struct KeyMeta {
+    is_signer: bool,
+    is_writable: bool,
+}
+
+impl ArchMessage {
+    /// Compiles instructions the way `ArchMessage::new` does: the payer first,
+    /// then writable signers, readonly signers, writable non-signers and
+    /// readonly non-signers, each group ordered by pubkey.
+    pub fn new(instructions: &[Instruction], payer: Option<Pubkey>, recent_blockhash: Hash) -> Self {
+        let mut metas: BTreeMap<Pubkey, KeyMeta> = BTreeMap::new();
+        for ix in instructions {
+            metas.entry(ix.program_id).or_default();
+            for account in &ix.accounts {
+                let meta = metas.entry(account.pubkey).or_default();
+                meta.is_signer |= account.is_signer;
+                meta.is_writable |= account.is_writable;
+            }
+        }
+        if let Some(payer) = payer {
+            let meta = metas.entry(payer).or_default();
+            meta.is_signer = true;
+            meta.is_writable = true;
+        }
+
+        let group = |signer: bool, writable: bool| -> Vec<Pubkey> {
+            metas
+                .iter()
+                .filter(|(key, meta)| {
+                    Some(**key) != payer && meta.is_signer == signer && meta.is_writable == writable
+                })
+                .map(|(key, _)| *key)
+                .collect()
+        };
+        let writable_signers = group(true, true);
+        let readonly_signers = group(true, false);
+        let writable_non_signers = group(false, true);
+        let readonly_non_signers = group(false, false);
+
+        let header = MessageHeader {
+            num_required_signatures: (payer.is_some() as usize
+                + writable_signers.len()
+                + readonly_signers.len()) as u8,
+            num_readonly_signed_accounts: readonly_signers.len() as u8,
+            num_readonly_unsigned_accounts: readonly_non_signers.len() as u8,
+        };
+
+        let account_keys: Vec<Pubkey> = payer
+            .into_iter()
+            .chain(writable_signers)
+            .chain(readonly_signers)
+            .chain(writable_non_signers)
+            .chain(readonly_non_signers)
+            .collect();
+
+        let index_of = |key: &Pubkey| account_keys.iter().position(|k| k == key).unwrap() as u8;
+        let instructions = instructions
+            .iter()
+            .map(|ix| SanitizedInstruction {
+                program_id_index: index_of(&ix.program_id),
+                accounts: ix.accounts.iter().map(|a| index_of(&a.pubkey)).collect(),
+                data: ix.data.clone(),
+            })
+            .collect();
+
+        Self { header, account_keys, recent_blockhash, instructions }
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut buf = vec![
+            self.header.num_required_signatures,
+            self.header.num_readonly_signed_accounts,
+            self.header.num_readonly_unsigned_accounts,
+        ];
+        buf.extend((self.account_keys.len() as u32).to_le_bytes());
+        for key in &self.account_keys {
+            buf.extend(key);
+        }
+        buf.extend(self.recent_blockhash);
+        buf.extend((self.instructions.len() as u32).to_le_bytes());
+        for ix in &self.instructions {
+            buf.push(ix.program_id_index);
+            buf.extend((ix.accounts.len() as u32).to_le_bytes());
+            buf.extend(&ix.accounts);
+            buf.extend((ix.data.len() as u32).to_le_bytes());
+            buf.extend(&ix.data);
+        }
+        buf
+    }
+
+    /// Parses the output of [`ArchMessage::serialize`], rejecting trailing bytes.
+    pub fn deserialize(bytes: &[u8]) -> anyhow::Result<Self> {
+        let mut reader = Reader { bytes, pos: 0 };
+        let message = Self::read(&mut reader)?;
+        if reader.pos != bytes.len() {
+            bail!("{} trailing byte(s) after message", bytes.len() - reader.pos);
+        }
+        Ok(message)
+    }
+
+    fn read(reader: &mut Reader) -> anyhow::Result<Self> {
+        let header = MessageHeader {
+            num_required_signatures: reader.u8()?,
+            num_readonly_signed_accounts: reader.u8()?,
+            num_readonly_unsigned_accounts: reader.u8()?,
+        };
+        let account_keys = (0..reader.u32()?).map(|_| reader.array()).collect::<anyhow::Result<Vec<_>>>()?;
+        let recent_blockhash = reader.array()?;
+        let instructions = (0..reader.u32()?)
+            .map(|_| {
+                let program_id_index = reader.u8()?;
+                let len = reader.u32()? as usize;
+                let accounts = reader.take(len)?.to_vec();
+                let len = reader.u32()? as usize;
+                let data = reader.take(len)?.to_vec();
+                Ok(SanitizedInstruction { program_id_index, accounts, data })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let required = header.num_required_signatures as usize;
+        if header.num_readonly_signed_accounts > header.num_required_signatures
+            || required + header.num_readonly_unsigned_accounts as usize > account_keys.len()
+        {
+            bail!("Header {:?} does not fit {} account key(s)", header, account_keys.len());
+        }
+        Ok(Self { header, account_keys, recent_blockhash, instructions })
+    }
+
+    /// The bytes that get BIP-322 signed: the hex string of
+    /// `sha256(hex(sha256(serialized)))`, taken as UTF-8 (64 bytes), not hex-decoded.
+    pub fn hash(&self) -> Vec<u8> {
+        let first = hex::encode(sha256::Hash::hash(&self.serialize()).to_byte_array());
+        hex::encode(sha256::Hash::hash(first.as_bytes()).to_byte_array()).into_bytes()
+    }
+
+    pub fn signers(&self) -> &[Pubkey] {
+        &self.account_keys[..self.header.num_required_signatures as usize]
+    }
+
+    pub fn is_signer(&self, index: usize) -> bool {
+        index < self.header.num_required_signatures as usize
+    }
+
+    /// Writability implied by the account's position, following the ordering of [`ArchMessage::new`].
+    pub fn is_writable(&self, index: usize) -> bool {
+        let header = &self.header;
+        if self.is_signer(index) {
+            index < header.num_required_signatures.saturating_sub(header.num_readonly_signed_accounts) as usize
+        } else {
+            index < self.account_keys.len().saturating_sub(header.num_readonly_unsigned_accounts as usize)
+        }
+    }
+}
+
+impl RuntimeTransaction {
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut buf = vec![self.version as u8];
+        buf.extend((self.signatures.len() as u32).to_le_bytes());
+        for signature in &self.signatures {
+            buf.extend(signature);
+        }
+        buf.extend(self.message.serialize());
+        buf
+    }
+
+    /// Parses the output of [`RuntimeTransaction::serialize`], assuming 64-byte signatures.
+    pub fn deserialize(bytes: &[u8]) -> anyhow::Result<Self> {
+        let mut reader = Reader { bytes, pos: 0 };
+        let version = reader.u8()? as u32;
+        let signatures = (0..reader.u32()?)
+            .map(|_| Ok(reader.take(64)?.to_vec()))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let message = ArchMessage::read(&mut reader)?;
+        if reader.pos != bytes.len() {
+            bail!("{} trailing byte(s) after transaction", bytes.len() - reader.pos);
+        }
+        Ok(Self { version, signatures, message })
+    }
+
+    /// Transaction id as reported by the validator: the hex message hash.
+    pub fn txid(&self) -> String {
+        String::from_utf8(self.message.hash()).expect("message hash is hex")
+    }
+}
+
+/// Cursor over serialized wire data that fails on truncated input.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> anyhow::Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| anyhow!("Unexpected end of input at byte {}", self.pos))?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> anyhow::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> anyhow::Result<u32> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn array<const N: usize>(&mut self) -> anyhow::Result<[u8; N]> {
+        Ok(self.take(N)?.try_into().expect("take returns N bytes"))
+    }
+}
+
+/// Rent-exempt minimum for an account of `data_len` bytes (`arch_program::rent::minimum_rent`).
+pub fn minimum_rent(data_len: usize) -> u64 {
+    const ACCOUNT_STORAGE_OVERHEAD: u64 = 128;
+    const DEFAULT_LAMPORTS_PER_BYTE_YEAR: u64 = 2;
+    (ACCOUNT_STORAGE_OVERHEAD + data_len as u64) * DEFAULT_LAMPORTS_PER_BYTE_YEAR
+}
+
+/// Display name of the built-in programs.
+pub fn program_name(program_id: &Pubkey) -> Option<&'static str> {
+    match *program_id {
+        SYSTEM_PROGRAM_ID => Some("System Program"),
+        BPF_LOADER_ID => Some("BPF Loader"),
+        _ => None,
+    }
+}
+
+/// Name of a built-in program instruction, read from its bincode variant tag.
+pub fn instruction_name(program_id: &Pubkey, data: &[u8]) -> Option<&'static str> {
+    let tag = u32::from_le_bytes(data.get(..4)?.try_into().ok()?);
+    match *program_id {
+        SYSTEM_PROGRAM_ID => system_instruction::name(tag),
+        BPF_LOADER_ID => loader_instruction::name(tag),
+        _ => None,
+    }
+}
+
+/// `SystemInstruction` builders (bincode 1.x, u32 variant tags).
+pub mod system_instruction {
+    use super::*;
+
+    const CREATE_ACCOUNT: u32 = 0;
+    const ASSIGN: u32 = 2;
+    const TRANSFER: u32 = 5;
+
+    pub fn create_account(from: Pubkey, to: Pubkey, lamports: u64, space: u64, owner: Pubkey) -> Instruction {
+        let mut data = CREATE_ACCOUNT.to_le_bytes().to_vec();
+        data.extend(lamports.to_le_bytes());
+        data.extend(space.to_le_bytes());
+        data.extend(owner);
+        Instruction {
+            program_id: SYSTEM_PROGRAM_ID,
+            accounts: vec![AccountMeta::new(from, true), AccountMeta::new(to, true)],
+            data,
+        }
+    }
+
+    pub fn assign(pubkey: Pubkey, owner: Pubkey) -> Instruction {
+        let mut data = ASSIGN.to_le_bytes().to_vec();
+        data.extend(owner);
+        Instruction {
+            program_id: SYSTEM_PROGRAM_ID,
+            accounts: vec![AccountMeta::new(pubkey, true)],
+            data,
+        }
+    }
+
+    pub fn transfer(from: Pubkey, to: Pubkey, lamports: u64) -> Instruction {
+        let mut data = TRANSFER.to_le_bytes().to_vec();
+        data.extend(lamports.to_le_bytes());
+        Instruction {
+            program_id: SYSTEM_PROGRAM_ID,
+            accounts: vec![AccountMeta::new(from, true), AccountMeta::new(to, false)],
+            data,
+        }
+    }
+
+    pub fn name(tag: u32) -> Option<&'static str> {
+        match tag {
+            CREATE_ACCOUNT => Some("CreateAccount"),
+            ASSIGN => Some("Assign"),
+            TRANSFER => Some("Transfer"),
+            _ => None,
+        }
+    }
+}
+
+/// `LoaderInstruction` builders (bincode 1.x, u32 variant tags).
+pub mod loader_instruction {
+    use super::*;
+
+    const WRITE: u32 = 0;
+    const TRUNCATE: u32 = 1;
+    const DEPLOY: u32 = 2;
+    const RETRACT: u32 = 3;
+
+    /// Fixed bytes of a single-signer `Write` transaction besides the chunk itself:
+    /// version, signature, header, two keys, blockhash, instruction framing and
+    /// the `Write` variant/offset/length prefix.
+    const WRITE_TX_OVERHEAD: usize = 1 + 4 + 64 + 3 + 4 + 32 * 3 + 32 + 4 + (1 + 4 + 2 + 4) + (4 + 4 + 8);
+
+    /// Largest ELF chunk that still fits a `Write` transaction under `RUNTIME_TX_SIZE_LIMIT`.
+    pub const MAX_CHUNK_SIZE: usize = RUNTIME_TX_SIZE_LIMIT - WRITE_TX_OVERHEAD - 100;
+
+    pub fn write(program: Pubkey, authority: Pubkey, offset: u32, bytes: &[u8]) -> Instruction {
+        let mut data = WRITE.to_le_bytes().to_vec();
+        data.extend(offset.to_le_bytes());
+        data.extend((bytes.len() as u64).to_le_bytes());
+        data.extend(bytes);
+        Instruction {
+            program_id: BPF_LOADER_ID,
+            accounts: vec![AccountMeta::new(program, false), AccountMeta::new_readonly(authority, true)],
+            data,
+        }
+    }
+
+    pub fn truncate(program: Pubkey, authority: Pubkey, new_size: u32) -> Instruction {
+        let mut data = TRUNCATE.to_le_bytes().to_vec();
+        data.extend(new_size.to_le_bytes());
+        Instruction {
+            program_id: BPF_LOADER_ID,
+            accounts: vec![AccountMeta::new(program, true), AccountMeta::new_readonly(authority, true)],
+            data,
+        }
+    }
+
+    pub fn deploy(program: Pubkey, authority: Pubkey) -> Instruction {
+        Instruction {
+            program_id: BPF_LOADER_ID,
+            accounts: vec![AccountMeta::new(program, false), AccountMeta::new_readonly(authority, true)],
+            data: DEPLOY.to_le_bytes().to_vec(),
+        }
+    }
+
+    pub fn retract(program: Pubkey, authority: Pubkey) -> Instruction {
+        Instruction {
+            program_id: BPF_LOADER_ID,
+            accounts: vec![AccountMeta::new(program, false), AccountMeta::new_readonly(authority, true)],
+            data: RETRACT.to_le_bytes().to_vec(),
+        }
+    }
+
+    pub fn name(tag: u32) -> Option<&'static str> {
+        match tag {
+            WRITE => Some("Write"),
+            TRUNCATE => Some("Truncate"),
+            DEPLOY => Some("Deploy"),
+            RETRACT => Some("Retract"),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compile_orders_payer_then_signers_then_readonly() {
+        let authority = [9u8; 32];
+        let program = [3u8; 32];
+        let ix = loader_instruction::write(program, authority, 0, &[1, 2, 3]);
+        let message = ArchMessage::new(&[ix], Some(authority), [0u8; 32]);
+
+        assert_eq!(message.account_keys, vec![authority, program, BPF_LOADER_ID]);
+        assert_eq!(
+            message.header,
+            MessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 1,
+            }
+        );
+        assert_eq!(message.instructions[0].program_id_index, 2);
+        assert_eq!(message.instructions[0].accounts, vec![1, 0]);
+    }
+
+    #[test]
+    fn hash_is_hex_of_double_sha256_over_hex() {
+        let message = ArchMessage {
+            header: MessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 0,
+            },
+            account_keys: vec![[2u8; 32]],
+            recent_blockhash: [1u8; 32],
+            instructions: vec![],
+        };
+        let hash = message.hash();
+        assert_eq!(hash.len(), 64);
+        assert!(hash.iter().all(|b| b.is_ascii_hexdigit()));
+
+        let serialized = message.serialize();
+        assert_eq!(serialized.len(), 3 + 4 + 32 + 32 + 4);
+    }
+
+    #[test]
+    fn deserialize_round_trips_and_rejects_truncation() {
+        let ix = system_instruction::transfer([9u8; 32], [4u8; 32], 1_000);
+        let message = ArchMessage::new(&[ix], Some([9u8; 32]), [7u8; 32]);
+        let serialized = message.serialize();
+
+        assert_eq!(ArchMessage::deserialize(&serialized).unwrap(), message);
+        assert!(ArchMessage::deserialize(&serialized[..serialized.len() - 1]).is_err());
+        assert!(ArchMessage::deserialize(&[serialized.as_slice(), &[0]].concat()).is_err());
+    }
+
+    #[test]
+    fn runtime_transaction_round_trips() {
+        let authority = [9u8; 32];
+        let ix = loader_instruction::write([3u8; 32], authority, 0, &[1, 2, 3]);
+        let message = ArchMessage::new(&[ix], Some(authority), [0u8; 32]);
+        assert!(message.is_writable(0) && message.is_writable(1) && !message.is_writable(2));
+
+        let tx = RuntimeTransaction { version: 0, signatures: vec![vec![5u8; 64]], message };
+        assert_eq!(RuntimeTransaction::deserialize(&tx.serialize()).unwrap(), tx);
+    }
+
+    #[test]
+    fn write_transaction_fits_size_limit() {
+        let authority = [9u8; 32];
+        let ix = loader_instruction::write([3u8; 32], authority, 0, &[0u8; loader_instruction::MAX_CHUNK_SIZE]);
+        let tx = RuntimeTransaction {
+            version: 0,
+            signatures: vec![vec![0u8; 64]],
+            message: ArchMessage::new(&[ix], Some(authority), [0u8; 32]),
+        };
+        assert!(tx.serialize().len() <= RUNTIME_TX_SIZE_LIMIT);
+    }
+}
@@ -0,0 +1,134 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+use tracing::debug;
+
+use crate::{
+    arch::{Hash, Pubkey, RuntimeTransaction},
+    upstreams::UpstreamPool,
+};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AccountInfo {
+    pub lamports: u64,
+    pub owner: Pubkey,
+    pub data: Vec<u8>,
+    pub is_executable: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TxStatus {
+    Processing,
+    Processed,
+    Failed(String),
+}
+
+/// Typed client for the Arch validator JSON-RPC, sent through the upstream
+/// pool so it gets the same failover as the `/rpc` proxy.
+#[derive(Clone)]
+pub struct ArchRpcClient {
+    client: reqwest::Client,
+    upstreams: UpstreamPool,
+    network: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("RPC error {code}: {message}")]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl ArchRpcClient {
+    pub fn new(client: reqwest::Client, upstreams: UpstreamPool, network: String) -> Self {
+        Self { client, upstreams, network }
+    }
+
+    async fn call<T: DeserializeOwned>(&self, method: &str, params: Value) -> anyhow::Result<T> {
+        let body = json!({ "jsonrpc": "2.0", "id": "arch-server", "method": method, "params": params });
+        let response: Value = self
+            .upstreams
+            .send(&self.client, &self.network, method, body.to_string())
+            .await?
+            .json()
+            .await
+            .with_context(|| format!("Invalid response to {method}"))?;
+
+        if let Some(error) = response.get("error").filter(|e| !e.is_null()) {
+            return Err(RpcError {
+                code: error["code"].as_i64().unwrap_or_default(),
+                message: error["message"].as_str().unwrap_or_default().to_string(),
+            }
+            .into());
+        }
+
+        serde_json::from_value(response["result"].clone())
+            .with_context(|| format!("Unexpected result for {method}: {}", response["result"]))
+    }
+
+    /// Returns `None` when the validator does not know the account.
+    pub async fn read_account_info(&self, pubkey: &Pubkey) -> anyhow::Result<Option<AccountInfo>> {
+        match self.call("read_account_info", json!(pubkey)).await {
+            Ok(info) => Ok(Some(info)),
+            Err(e) if e.is::<RpcError>() => {
+                debug!("read_account_info({}) failed: {}", hex::encode(pubkey), e);
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    pub async fn get_best_block_hash(&self) -> anyhow::Result<Hash> {
+        let hash: String = self.call("get_best_block_hash", json!([])).await?;
+        hex::decode(&hash)?
+            .try_into()
+            .map_err(|_| anyhow!("Block hash {hash} is not 32 bytes"))
+    }
+
+    pub async fn send_transaction(&self, tx: &RuntimeTransaction) -> anyhow::Result<String> {
+        self.call("send_transaction", json!(tx)).await
+    }
+
+    pub async fn send_transactions(&self, txs: &[RuntimeTransaction]) -> anyhow::Result<Vec<String>> {
+        self.call("send_transactions", json!(txs)).await
+    }
+
+    /// Asks the validator faucet for a funding transaction for `pubkey`; the
+    /// caller still has to add the account's own signature before sending it.
+    pub async fn create_account_with_faucet(&self, pubkey: &Pubkey) -> anyhow::Result<RuntimeTransaction> {
+        self.call("create_account_with_faucet", json!(pubkey)).await
+    }
+
+    pub async fn get_processed_transaction(&self, txid: &str) -> anyhow::Result<Option<TxStatus>> {
+        let result: Value = match self.call("get_processed_transaction", json!(txid)).await {
+            Ok(result) => result,
+            Err(e) if e.is::<RpcError>() => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        Ok(match &result["status"] {
+            Value::Null => None,
+            Value::String(s) if s == "Processed" => Some(TxStatus::Processed),
+            Value::String(_) => Some(TxStatus::Processing),
+            Value::Object(o) if o.contains_key("Failed") => Some(TxStatus::Failed(o["Failed"].to_string())),
+            other => Some(TxStatus::Failed(other.to_string())),
+        })
+    }
+
+    /// Polls until `txid` is processed or fails, giving up after `timeout`.
+    pub async fn wait_for_transaction(&self, txid: &str, timeout: Duration) -> anyhow::Result<TxStatus> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            match self.get_processed_transaction(txid).await? {
+                Some(status @ (TxStatus::Processed | TxStatus::Failed(_))) => return Ok(status),
+                _ if tokio::time::Instant::now() >= deadline => {
+                    return Err(anyhow!("Transaction {txid} not confirmed after {}s", timeout.as_secs()))
+                }
+                _ => {}
+            }
+        }
+    }
+}
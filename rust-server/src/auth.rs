@@ -0,0 +1,165 @@
+//! API-key authentication. Keys are stored only as SHA-256 hashes in a local
+//! JSON file; requests without a key fall into the anonymous tier unless the
+//! server requires a key.
+
+use std::{collections::HashMap, fs, net::IpAddr, sync::Arc};
+
+use anyhow::{anyhow, Context};
+use bitcoin::hashes::{sha256, Hash};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+/// Principal id shared by every request made without an API key. Usage and
+/// build ownership are keyed by [`Principal::usage_key`] instead, which
+/// separates anonymous callers by address.
+pub const ANONYMOUS: &str = "anonymous";
+
+/// The caller a request is made on behalf of.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Principal {
+    pub id: String,
+    pub admin: bool,
+    /// Address the request came from; separates anonymous callers for usage and ownership
+    #[serde(skip)]
+    pub client_ip: Option<IpAddr>,
+}
+
+impl Principal {
+    pub fn anonymous() -> Self {
+        Self { id: ANONYMOUS.to_string(), admin: false, client_ip: None }
+    }
+
+    pub fn is_anonymous(&self) -> bool {
+        self.id == ANONYMOUS
+    }
+
+    /// Key usage is accounted and builds are owned under: the principal id, or
+    /// the client address for anonymous callers.
+    pub fn usage_key(&self) -> String {
+        match (self.is_anonymous(), self.client_ip) {
+            (true, Some(ip)) => format!("{}:{}", ANONYMOUS, ip),
+            _ => self.id.clone(),
+        }
+    }
+
+    pub fn require_admin(&self) -> Result<()> {
+        if self.admin {
+            Ok(())
+        } else {
+            Err(Error::Forbidden("Admin access required".to_string()))
+        }
+    }
+
+    /// Whether this principal may see resources owned by `owner`.
+    pub fn can_access(&self, owner: &str) -> bool {
+        self.admin || self.usage_key() == owner
+    }
+}
+
+/// One line of the key store: `{"principal": "team-a", "key_sha256": "<hex>", "admin": false}`.
+#[derive(Deserialize)]
+struct ApiKeyEntry {
+    principal: String,
+    key_sha256: String,
+    #[serde(default)]
+    admin: bool,
+}
+
+#[derive(Clone)]
+pub struct Auth {
+    keys: Arc<HashMap<[u8; 32], Principal>>,
+    allow_anonymous: bool,
+}
+
+pub fn hash_key(key: &str) -> [u8; 32] {
+    sha256::Hash::hash(key.as_bytes()).to_byte_array()
+}
+
+impl Auth {
+    /// Loads the hashed keys from `path`, a JSON array of key entries.
+    pub fn load(path: Option<&str>, allow_anonymous: bool) -> anyhow::Result<Self> {
+        let entries: Vec<ApiKeyEntry> = match path {
+            Some(path) => {
+                let contents = fs::read_to_string(path).with_context(|| format!("Failed to read API key file {path}"))?;
+                serde_json::from_str(&contents).with_context(|| format!("Invalid API key file {path}"))?
+            }
+            None => Vec::new(),
+        };
+
+        let keys = entries
+            .into_iter()
+            .map(|entry| {
+                let hash = hex::decode(&entry.key_sha256)
+                    .ok()
+                    .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+                    .ok_or_else(|| anyhow!("key_sha256 for '{}' must be 32 hex-encoded bytes", entry.principal))?;
+                if entry.principal == ANONYMOUS {
+                    return Err(anyhow!("'{ANONYMOUS}' is reserved for requests without a key"));
+                }
+                Ok((hash, Principal { id: entry.principal, admin: entry.admin, client_ip: None }))
+            })
+            .collect::<anyhow::Result<HashMap<_, _>>>()?;
+
+        if keys.is_empty() && !allow_anonymous {
+            return Err(anyhow!("An API key file is required when anonymous access is disabled"));
+        }
+
+        Ok(Self { keys: Arc::new(keys), allow_anonymous })
+    }
+
+    /// True when API keys are configured, i.e. browsers may send credentials.
+    pub fn has_keys(&self) -> bool {
+        !self.keys.is_empty()
+    }
+
+    pub fn authenticate(&self, key: Option<&str>) -> Result<Principal> {
+        match key {
+            Some(key) => self
+                .keys
+                .get(&hash_key(key))
+                .cloned()
+                .ok_or_else(|| Error::Unauthorized("Invalid API key".to_string())),
+            None if self.allow_anonymous => Ok(Principal::anonymous()),
+            None => Err(Error::Unauthorized("An API key is required".to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_are_matched_by_hash_and_anonymous_is_optional() {
+        let dir = std::env::temp_dir().join(format!("arch-auth-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("keys.json");
+        fs::write(
+            &path,
+            format!(r#"[{{"principal": "team-a", "key_sha256": "{}"}}]"#, hex::encode(hash_key("secret"))),
+        )
+        .unwrap();
+
+        let auth = Auth::load(path.to_str(), false).unwrap();
+        assert_eq!(auth.authenticate(Some("secret")).unwrap().id, "team-a");
+        assert!(matches!(auth.authenticate(Some("wrong")), Err(Error::Unauthorized(_))));
+        assert!(matches!(auth.authenticate(None), Err(Error::Unauthorized(_))));
+
+        let auth = Auth::load(path.to_str(), true).unwrap();
+        assert_eq!(auth.authenticate(None).unwrap(), Principal::anonymous());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn anonymous_callers_only_access_their_own_builds() {
+        let from = |ip: &str| Principal { client_ip: Some(ip.parse().unwrap()), ..Principal::anonymous() };
+        let (alice, bob) = (from("10.0.0.1"), from("10.0.0.2"));
+
+        assert!(alice.can_access(&alice.usage_key()));
+        assert!(!bob.can_access(&alice.usage_key()));
+        assert!(!alice.can_access(ANONYMOUS));
+        assert!(Principal { admin: true, ..bob }.can_access(&alice.usage_key()));
+    }
+}
@@ -0,0 +1,134 @@
+//! BIP-322 "simple" signatures over P2TR key-spends, as produced by
+//! `arch_sdk::helper::sign_message_bip322` and checked by the validator.
+
+use bitcoin::{
+    absolute::LockTime,
+    hashes::{sha256, Hash, HashEngine},
+    key::{Keypair, TapTweak, XOnlyPublicKey},
+    opcodes::{all::OP_RETURN, OP_0},
+    script::Builder,
+    secp256k1::{schnorr, Message, Secp256k1, Verification},
+    sighash::{Prevouts, SighashCache, TapSighashType},
+    transaction::Version,
+    Amount, OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Witness,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum VerifyError {
+    #[error("Invalid public key: expected a 32-byte x-only key")]
+    InvalidPublicKey,
+    #[error("Invalid signature length {0}: expected 64 or 65 bytes")]
+    InvalidSignatureLength(usize),
+    #[error("Invalid sighash type 0x{0:02x}")]
+    InvalidSighashType(u8),
+    #[error("Signature does not match the message and public key")]
+    Mismatch,
+}
+
+const TAG: &[u8] = b"BIP0322-signed-message";
+
+fn message_hash(message: &[u8]) -> [u8; 32] {
+    let tag = sha256::Hash::hash(TAG);
+    let mut engine = sha256::Hash::engine();
+    engine.input(tag.as_ref());
+    engine.input(tag.as_ref());
+    engine.input(message);
+    sha256::Hash::from_engine(engine).to_byte_array()
+}
+
+fn to_spend(script_pubkey: ScriptBuf, message: &[u8]) -> Transaction {
+    let script_sig = Builder::new()
+        .push_opcode(OP_0)
+        .push_slice(message_hash(message))
+        .into_script();
+
+    Transaction {
+        version: Version(0),
+        lock_time: LockTime::ZERO,
+        input: vec![TxIn {
+            previous_output: OutPoint { txid: Hash::all_zeros(), vout: 0xFFFFFFFF },
+            script_sig,
+            sequence: Sequence::ZERO,
+            witness: Witness::new(),
+        }],
+        output: vec![TxOut { value: Amount::ZERO, script_pubkey }],
+    }
+}
+
+fn to_sign(to_spend: &Transaction) -> Transaction {
+    Transaction {
+        version: Version(0),
+        lock_time: LockTime::ZERO,
+        input: vec![TxIn {
+            previous_output: OutPoint { txid: to_spend.compute_txid(), vout: 0 },
+            script_sig: ScriptBuf::new(),
+            sequence: Sequence::ZERO,
+            witness: Witness::new(),
+        }],
+        output: vec![TxOut {
+            value: Amount::ZERO,
+            script_pubkey: Builder::new().push_opcode(OP_RETURN).into_script(),
+        }],
+    }
+}
+
+fn sighash<C: Verification>(
+    secp: &Secp256k1<C>,
+    internal_key: XOnlyPublicKey,
+    message: &[u8],
+    sighash_type: TapSighashType,
+) -> Message {
+    let script_pubkey = ScriptBuf::new_p2tr(secp, internal_key, None);
+    let to_spend = to_spend(script_pubkey, message);
+    let to_sign = to_sign(&to_spend);
+
+    let sighash = SighashCache::new(&to_sign)
+        .taproot_key_spend_signature_hash(0, &Prevouts::All(&to_spend.output), sighash_type)
+        .expect("to_sign has exactly one input");
+    Message::from_digest(sighash.to_byte_array())
+}
+
+/// Signs `message` for the taproot address of `keypair` and returns the bare
+/// 64-byte Schnorr signature (SIGHASH_ALL, sighash byte stripped).
+pub fn sign_message(keypair: &Keypair, message: &[u8]) -> [u8; 64] {
+    let secp = Secp256k1::new();
+    let (internal_key, _) = keypair.x_only_public_key();
+    let digest = sighash(&secp, internal_key, message, TapSighashType::All);
+
+    let tweaked = keypair.tap_tweak(&secp, None).to_keypair();
+    secp.sign_schnorr(&digest, &tweaked).serialize()
+}
+
+/// Verifies a signature over `message` by the untweaked x-only `pubkey`,
+/// returning the sighash type it was made with.
+///
+/// A 65-byte signature carries its sighash type in the last byte. A bare
+/// 64-byte one is tried as SIGHASH_DEFAULT and then SIGHASH_ALL, the same
+/// fallback the validator applies to `arch_sdk` signatures.
+pub fn verify_message(pubkey: &[u8], message: &[u8], signature: &[u8]) -> Result<TapSighashType, VerifyError> {
+    let secp = Secp256k1::verification_only();
+    let internal_key = XOnlyPublicKey::from_slice(pubkey).map_err(|_| VerifyError::InvalidPublicKey)?;
+
+    let (signature, candidates) = match signature {
+        [signature @ .., sighash_byte] if signature.len() == 64 => {
+            // SIGHASH_DEFAULT is only valid in the 64-byte encoding (BIP-341)
+            let sighash_type = TapSighashType::from_consensus_u8(*sighash_byte)
+                .ok()
+                .filter(|t| *t != TapSighashType::Default)
+                .ok_or(VerifyError::InvalidSighashType(*sighash_byte))?;
+            (signature, vec![sighash_type])
+        }
+        signature if signature.len() == 64 => (signature, vec![TapSighashType::Default, TapSighashType::All]),
+        signature => return Err(VerifyError::InvalidSignatureLength(signature.len())),
+    };
+    let signature = schnorr::Signature::from_slice(signature).map_err(|_| VerifyError::Mismatch)?;
+    let (output_key, _) = internal_key.tap_tweak(&secp, None);
+
+    candidates
+        .into_iter()
+        .find(|&sighash_type| {
+            let digest = sighash(&secp, internal_key, message, sighash_type);
+            secp.verify_schnorr(&signature, &digest, &output_key.to_x_only_public_key()).is_ok()
+        })
+        .ok_or(VerifyError::Mismatch)
+}
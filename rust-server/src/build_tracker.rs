@@ -1,68 +1,326 @@
-use std::collections::HashMap;
-use std::sync::Arc;
-use tokio::sync::RwLock;
-use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::{sync::RwLock, task::AbortHandle};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
+use utoipa::ToSchema;
 
-#[derive(Debug, Clone, Serialize)]
+use crate::error::ErrorCode;
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum BuildStatus {
+    /// Waiting for one of the `build.capacity` build slots
     Queued,
     Building,
     Success,
     Failed,
+    Cancelled,
+}
+
+impl BuildStatus {
+    /// Queued or building, i.e. not finished yet.
+    pub fn is_running(&self) -> bool {
+        matches!(self, BuildStatus::Queued | BuildStatus::Building)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DeploymentStatus {
+    Running,
+    Success,
+    Failed,
+}
+
+/// Progress of a server-side deployment of a build's program.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DeploymentInfo {
+    pub status: DeploymentStatus,
+    pub network: String,
+    pub program_id: String,
+    pub stage: String,
+    pub chunks_total: usize,
+    pub chunks_written: usize,
+    pub txids: Vec<String>,
+    pub error: Option<String>,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub completed_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BuildInfo {
     pub uuid: String,
     pub program_name: String,
+    /// Id of the principal that started the build
+    pub owner: String,
     pub status: BuildStatus,
     pub stderr: Option<String>,
     pub started_at: chrono::DateTime<chrono::Utc>,
     pub completed_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub deployment: Option<DeploymentInfo>,
+    /// Background task running the build, while it is running
+    #[serde(skip)]
+    task: Option<AbortHandle>,
+}
+
+impl BuildInfo {
+    /// Still building, or deploying its program.
+    pub fn is_busy(&self) -> bool {
+        self.status.is_running() || self.deployment.as_ref().is_some_and(|d| d.completed_at.is_none())
+    }
 }
 
 #[derive(Clone)]
 pub struct BuildTracker {
     builds: Arc<RwLock<HashMap<String, BuildInfo>>>,
+    /// Set once shutdown begins; no new builds are accepted after that
+    draining: Arc<AtomicBool>,
+    /// Builds whose artifacts are being deleted. Only added to while holding
+    /// the `builds` lock, so starting a build or deployment can check it.
+    purging: Arc<Mutex<HashSet<String>>>,
+}
+
+/// Clears a build's purging mark when the purge ends, even if it is dropped.
+struct PurgeGuard<'a> {
+    purging: &'a Mutex<HashSet<String>>,
+    uuid: &'a str,
+}
+
+impl Drop for PurgeGuard<'_> {
+    fn drop(&mut self) {
+        self.purging.lock().unwrap().remove(self.uuid);
+    }
 }
 
 impl BuildTracker {
     pub fn new() -> Self {
+        Self::from_builds(HashMap::new())
+    }
+
+    fn from_builds(builds: HashMap<String, BuildInfo>) -> Self {
         Self {
-            builds: Arc::new(RwLock::new(HashMap::new())),
+            builds: Arc::new(RwLock::new(builds)),
+            draining: Arc::new(AtomicBool::new(false)),
+            purging: Arc::new(Mutex::new(HashSet::new())),
+        }
+    }
+
+    /// Restores the builds saved by `save`. Work that was still running when
+    /// the previous process stopped is marked as failed.
+    pub fn load(path: &Path) -> Self {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(_) => return Self::new(),
+        };
+        let mut builds: HashMap<String, BuildInfo> = match serde_json::from_str(&contents) {
+            Ok(builds) => builds,
+            Err(e) => {
+                warn!("Ignoring unreadable build tracker state {:?}: {}", path, e);
+                return Self::new();
+            }
+        };
+
+        let now = chrono::Utc::now();
+        for info in builds.values_mut() {
+            if info.status.is_running() {
+                info.status = BuildStatus::Failed;
+                info.stderr = Some("Build interrupted by a server restart".to_string());
+                info.completed_at = Some(now);
+            }
+            if let Some(deployment) = info.deployment.as_mut().filter(|d| d.completed_at.is_none()) {
+                deployment.status = DeploymentStatus::Failed;
+                deployment.error = Some("Deployment interrupted by a server restart".to_string());
+                deployment.completed_at = Some(now);
+            }
         }
+        info!("Restored {} builds from {:?}", builds.len(), path);
+        Self::from_builds(builds)
     }
 
-    pub async fn start_build(&self, uuid: String, program_name: String) {
+    pub async fn save(&self, path: &Path) -> std::io::Result<()> {
+        let builds = self.builds.read().await;
+        let contents = serde_json::to_vec(&*builds)?;
+        // Write then rename so a crash never leaves a truncated file behind
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, contents)?;
+        std::fs::rename(tmp, path)
+    }
+
+    pub fn start_draining(&self) {
+        self.draining.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
+
+    /// Builds and deployments still running.
+    pub async fn active_count(&self) -> usize {
+        let builds = self.builds.read().await;
+        builds.values().filter(|info| info.is_busy()).count()
+    }
+
+    /// Starts tracking a queued build. Returns false, leaving the existing
+    /// entry alone, when a build or deployment of this uuid is still running
+    /// or its artifacts are being purged.
+    /// Builds queued or building.
+    pub async fn running_count(&self) -> usize {
+        self.builds.read().await.values().filter(|info| info.status.is_running()).count()
+    }
+
+    pub async fn start_build(&self, uuid: String, program_name: String, owner: String) -> bool {
         let mut builds = self.builds.write().await;
+        if builds.get(&uuid).is_some_and(BuildInfo::is_busy) || self.purging.lock().unwrap().contains(&uuid) {
+            return false;
+        }
         builds.insert(
             uuid.clone(),
             BuildInfo {
                 uuid,
                 program_name,
-                status: BuildStatus::Building,
+                owner,
+                status: BuildStatus::Queued,
                 stderr: None,
                 started_at: chrono::Utc::now(),
                 completed_at: None,
+                deployment: None,
+                task: None,
             },
         );
+        true
+    }
+
+    /// Attaches the task running a build so it can be cancelled.
+    pub async fn set_task(&self, uuid: &str, task: AbortHandle) {
+        let mut builds = self.builds.write().await;
+        if let Some(info) = builds.get_mut(uuid).filter(|info| info.status.is_running()) {
+            info.task = Some(task);
+        }
+    }
+
+    /// Moves a queued build to building once it holds a build slot. Returns
+    /// false when it is no longer queued, e.g. because it was cancelled.
+    pub async fn mark_building(&self, uuid: &str) -> bool {
+        let mut builds = self.builds.write().await;
+        match builds.get_mut(uuid) {
+            Some(info) if matches!(info.status, BuildStatus::Queued) => {
+                info.status = BuildStatus::Building;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Records the outcome of a running build. Returns false when the build was
+    /// no longer running, e.g. because it was cancelled.
+    pub async fn complete_build(&self, uuid: &str, stderr: String, program_name: String, success: bool) -> bool {
+        let mut builds = self.builds.write().await;
+        match builds.get_mut(uuid) {
+            Some(info) if info.status.is_running() => {
+                info.status = if success { BuildStatus::Success } else { BuildStatus::Failed };
+                info.stderr = Some(stderr);
+                info.program_name = program_name;
+                info.completed_at = Some(chrono::Utc::now());
+                info.task = None;
+                debug!("Build {} is now {:?}", uuid, info.status);
+                true
+            }
+            Some(_) => false,
+            None => {
+                warn!("Build info not found for UUID: {}", uuid);
+                false
+            }
+        }
+    }
+
+    /// Stops a queued or running build, killing its toolchain process.
+    /// Returns false when the build is unknown or already finished.
+    pub async fn cancel_build(&self, uuid: &str, reason: &str) -> bool {
+        let mut builds = self.builds.write().await;
+        let Some(info) = builds.get_mut(uuid).filter(|info| info.status.is_running()) else {
+            return false;
+        };
+        if let Some(task) = info.task.take() {
+            task.abort();
+        }
+        info.status = BuildStatus::Cancelled;
+        info.stderr = Some(reason.to_string());
+        info.completed_at = Some(chrono::Utc::now());
+        true
     }
 
-    pub async fn complete_build(&self, uuid: &str, stderr: String, program_name: String, success: bool) {
-        println!("[TRACKER] complete_build called for UUID: {}, success: {}", uuid, success);
+    /// Forgets builds that finished before `before`, keeping ones still deploying.
+    /// Returns how many were evicted.
+    pub async fn evict_finished(&self, before: chrono::DateTime<chrono::Utc>) -> usize {
         let mut builds = self.builds.write().await;
-        println!("[TRACKER] Got write lock for UUID: {}", uuid);
-
-        if let Some(info) = builds.get_mut(uuid) {
-            println!("[TRACKER] Found build info for UUID: {}, updating status", uuid);
-            info.status = if success { BuildStatus::Success } else { BuildStatus::Failed };
-            info.stderr = Some(stderr);
-            info.program_name = program_name;
-            info.completed_at = Some(chrono::Utc::now());
-            println!("[TRACKER] Updated build info for UUID: {}, new status: {:?}", uuid, info.status);
-        } else {
-            println!("[TRACKER] WARNING: Build info NOT FOUND for UUID: {}", uuid);
+        let count = builds.len();
+        builds.retain(|_, info| {
+            let deploying = info.deployment.as_ref().is_some_and(|d| d.completed_at.is_none());
+            deploying || info.completed_at.is_none_or(|completed_at| completed_at >= before)
+        });
+        count - builds.len()
+    }
+
+    /// Runs `purge` and forgets the build once it succeeds, unless the build is
+    /// busy or already being purged. The build is marked as purging meanwhile,
+    /// so no build or deployment of it can start halfway, but the tracker is
+    /// not held locked during the purge. Returns None when the build was busy.
+    pub async fn purge_idle(
+        &self,
+        uuid: &str,
+        purge: impl Future<Output = anyhow::Result<()>>,
+    ) -> Option<anyhow::Result<()>> {
+        {
+            let builds = self.builds.read().await;
+            if builds.get(uuid).is_some_and(BuildInfo::is_busy) || !self.purging.lock().unwrap().insert(uuid.to_string()) {
+                return None;
+            }
+        }
+        let _guard = PurgeGuard { purging: &self.purging, uuid };
+        let result = purge.await;
+        if result.is_ok() {
+            self.builds.write().await.remove(uuid);
+        }
+        Some(result)
+    }
+
+    /// Marks a deployment of a successful build as running. Checked and set
+    /// under one lock, so concurrent requests cannot both start deploying.
+    pub async fn start_deployment(&self, uuid: &str, network: String, program_id: String) -> Result<(), ErrorCode> {
+        let mut builds = self.builds.write().await;
+        if self.purging.lock().unwrap().contains(uuid) {
+            return Err(ErrorCode::BuildNotFound);
+        }
+        let info = builds.get_mut(uuid).ok_or(ErrorCode::BuildNotFound)?;
+        if !matches!(info.status, BuildStatus::Success) {
+            return Err(ErrorCode::BuildNotReady);
+        }
+        if info.deployment.as_ref().is_some_and(|d| matches!(d.status, DeploymentStatus::Running)) {
+            return Err(ErrorCode::DeploymentInProgress);
+        }
+        info.deployment = Some(DeploymentInfo {
+            status: DeploymentStatus::Running,
+            network,
+            program_id,
+            stage: "starting".to_string(),
+            chunks_total: 0,
+            chunks_written: 0,
+            txids: Vec::new(),
+            error: None,
+            started_at: chrono::Utc::now(),
+            completed_at: None,
+        });
+        Ok(())
+    }
+
+    pub async fn update_deployment(&self, uuid: &str, update: impl FnOnce(&mut DeploymentInfo)) {
+        let mut builds = self.builds.write().await;
+        if let Some(deployment) = builds.get_mut(uuid).and_then(|info| info.deployment.as_mut()) {
+            update(deployment);
         }
     }
 
@@ -70,4 +328,83 @@ impl BuildTracker {
         let builds = self.builds.read().await;
         builds.get(uuid).cloned()
     }
+
+    pub async fn list_builds(&self) -> Vec<BuildInfo> {
+        self.builds.read().await.values().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn saved_state_is_restored_with_running_builds_failed() {
+        let path = std::env::temp_dir().join(format!("arch-tracker-{}.json", std::process::id()));
+        let tracker = BuildTracker::new();
+        tracker.start_build("a".to_string(), "prog".to_string(), "team-a".to_string()).await;
+        tracker.start_build("b".to_string(), "prog".to_string(), "team-a".to_string()).await;
+        tracker.complete_build("b", String::new(), "prog".to_string(), true).await;
+        tracker.save(&path).await.unwrap();
+
+        let restored = BuildTracker::load(&path);
+        assert!(matches!(restored.get_build("a").await.unwrap().status, BuildStatus::Failed));
+        assert!(matches!(restored.get_build("b").await.unwrap().status, BuildStatus::Success));
+        assert_eq!(restored.active_count().await, 0);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn cancelled_builds_ignore_late_completion() {
+        let tracker = BuildTracker::new();
+        tracker.start_build("a".to_string(), "prog".to_string(), "team-a".to_string()).await;
+        let task = tokio::spawn(std::future::pending::<()>());
+        tracker.set_task("a", task.abort_handle()).await;
+
+        assert!(tracker.cancel_build("a", "cancelled").await);
+        assert!(task.await.unwrap_err().is_cancelled());
+        assert!(!tracker.cancel_build("a", "cancelled").await);
+        assert!(!tracker.complete_build("a", String::new(), "prog".to_string(), true).await);
+        assert!(matches!(tracker.get_build("a").await.unwrap().status, BuildStatus::Cancelled));
+    }
+
+    #[tokio::test]
+    async fn builds_are_queued_until_they_get_a_slot() {
+        let tracker = BuildTracker::new();
+        tracker.start_build("a".to_string(), "prog".to_string(), "team-a".to_string()).await;
+        tracker.start_build("b".to_string(), "prog".to_string(), "team-a".to_string()).await;
+        assert!(matches!(tracker.get_build("a").await.unwrap().status, BuildStatus::Queued));
+        assert_eq!(tracker.active_count().await, 2);
+
+        assert!(tracker.mark_building("a").await);
+        assert!(matches!(tracker.get_build("a").await.unwrap().status, BuildStatus::Building));
+        // Nor can a rebuild of it start while it runs
+        assert!(!tracker.start_build("a".to_string(), "prog".to_string(), "team-a".to_string()).await);
+
+        // A build cancelled while it waits never starts
+        assert!(tracker.cancel_build("b", "cancelled").await);
+        assert!(!tracker.mark_building("b").await);
+    }
+
+    #[tokio::test]
+    async fn busy_builds_are_not_purged() {
+        let tracker = BuildTracker::new();
+        tracker.start_build("a".to_string(), "prog".to_string(), "team-a".to_string()).await;
+        tracker.complete_build("a", String::new(), "prog".to_string(), true).await;
+        tracker.start_deployment("a", "testnet".to_string(), "id".to_string()).await.unwrap();
+        assert!(tracker.purge_idle("a", async { Ok(()) }).await.is_none());
+        let again = tracker.start_deployment("a", "testnet".to_string(), "id".to_string()).await;
+        assert_eq!(again, Err(ErrorCode::DeploymentInProgress));
+
+        tracker.update_deployment("a", |d| d.completed_at = Some(chrono::Utc::now())).await;
+        // The tracker is usable during the purge, but the build cannot be restarted
+        let during = tracker.clone();
+        let purge = async move {
+            assert!(!during.start_build("a".to_string(), "prog".to_string(), "team-a".to_string()).await);
+            Ok(())
+        };
+        assert!(tracker.purge_idle("a", purge).await.unwrap().is_ok());
+        assert!(tracker.get_build("a").await.is_none());
+        assert!(tracker.start_build("a".to_string(), "prog".to_string(), "team-a".to_string()).await);
+    }
 }
@@ -0,0 +1,138 @@
+//! API versions and the compatibility policy.
+//!
+//! The API is served under `/v1`. Within a version, changes are additive
+//! only: new routes, new optional request fields, new response fields and
+//! new error codes. Clients must ignore fields and codes they do not know.
+//! Removing or renaming a field, changing its type, or changing what a status
+//! code means requires a new schema version.
+//!
+//! Clients pick the schema with the `Api-Version` request header: `1`, or
+//! `legacy` for the shapes the unversioned paths had before `/v1` existed.
+//! Without the header, `/v1/...` answers with `1` and the unversioned paths,
+//! kept as deprecated aliases, with `legacy`. Every response names the schema
+//! it used in `Api-Version`. The aliases also carry `Deprecation` and a `Link`
+//! to their `/v1` successor.
+
+use std::convert::Infallible;
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Request},
+    http::{header, request::Parts, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::error::{Error, ErrorCode, Result};
+
+pub const API_VERSION_HEADER: &str = "api-version";
+pub const DEPRECATION_HEADER: &str = "deprecation";
+
+/// Prefix of the current API version.
+pub const V1_PREFIX: &str = "/v1";
+
+/// When the unversioned aliases were deprecated, as an RFC 9745 date (2026-10-18).
+const ALIASES_DEPRECATED: &str = "@1792281600";
+
+/// Response schema a request is answered with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiVersion {
+    /// Shapes of the unversioned paths, e.g. a `not_found` build status instead of an error
+    Legacy,
+    V1,
+}
+
+impl ApiVersion {
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "legacy" => Some(Self::Legacy),
+            "1" | "v1" => Some(Self::V1),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Legacy => "legacy",
+            Self::V1 => "1",
+        }
+    }
+}
+
+/// The version negotiated by [`negotiate`]; `V1` on routes it does not wrap.
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ApiVersion {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> std::result::Result<Self, Self::Rejection> {
+        Ok(parts.extensions.get::<ApiVersion>().copied().unwrap_or(ApiVersion::V1))
+    }
+}
+
+/// Resolves the request's `ApiVersion` from `Api-Version` or, failing that,
+/// the path, and marks responses of the unversioned aliases as deprecated.
+pub async fn negotiate(mut req: Request, next: Next) -> Result<Response> {
+    let path = req.uri().path().to_string();
+    let alias = !path.starts_with(&format!("{V1_PREFIX}/"));
+
+    let requested = match req.headers().get(API_VERSION_HEADER) {
+        Some(value) => Some(value.to_str().ok().and_then(ApiVersion::parse).ok_or_else(|| {
+            Error::coded(ErrorCode::UnsupportedApiVersion, "Unsupported API version")
+                .with_detail("Api-Version", "must be 1 or legacy")
+        })?),
+        None => None,
+    };
+    let version = requested.unwrap_or(if alias { ApiVersion::Legacy } else { ApiVersion::V1 });
+    req.extensions_mut().insert(version);
+
+    let mut response = next.run(req).await;
+    let headers = response.headers_mut();
+    headers.insert(API_VERSION_HEADER, HeaderValue::from_static(version.as_str()));
+    if alias {
+        headers.insert(DEPRECATION_HEADER, HeaderValue::from_static(ALIASES_DEPRECATED));
+        if let Ok(link) = HeaderValue::from_str(&format!("<{V1_PREFIX}{path}>; rel=\"successor-version\"")) {
+            headers.insert(header::LINK, link);
+        }
+    }
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{body::Body, http::StatusCode, middleware, routing::get, Router};
+    use tower::ServiceExt;
+
+    use super::*;
+
+    async fn call(uri: &str, version: Option<&str>) -> Response {
+        let routes = || Router::new().route("/thing", get(|version: ApiVersion| async move { version.as_str() }));
+        let app = Router::new()
+            .nest(V1_PREFIX, routes())
+            .merge(routes())
+            .layer(middleware::from_fn(negotiate));
+        let mut req = Request::builder().uri(uri);
+        if let Some(version) = version {
+            req = req.header(API_VERSION_HEADER, version);
+        }
+        app.oneshot(req.body(Body::empty()).unwrap()).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn version_follows_the_header_then_the_path() {
+        let response = call("/v1/thing", None).await;
+        assert_eq!(response.headers()[API_VERSION_HEADER], "1");
+        assert!(response.headers().get(DEPRECATION_HEADER).is_none());
+
+        let response = call("/thing", None).await;
+        assert_eq!(response.headers()[API_VERSION_HEADER], "legacy");
+        assert_eq!(response.headers()[header::LINK], "</v1/thing>; rel=\"successor-version\"");
+        assert!(response.headers().get(DEPRECATION_HEADER).is_some());
+
+        let response = call("/thing", Some("1")).await;
+        assert_eq!(response.headers()[API_VERSION_HEADER], "1");
+        assert!(response.headers().get(DEPRECATION_HEADER).is_some());
+
+        let response = call("/v1/thing", Some("2")).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
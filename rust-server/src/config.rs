@@ -1,27 +1,761 @@
-use std::env;
+use std::{
+    collections::HashMap,
+    env, fmt, fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Arc, RwLock},
+};
 
-#[derive(Debug)]
+use serde::{Deserialize, Serialize, Serializer};
+use utoipa::ToSchema;
+
+use crate::{
+    localnet::LOCALNET_NETWORK,
+    log::LogFormat,
+    sdk::{self, SdkProfile},
+};
+
+/// Server configuration. Loaded from an optional TOML file (`--config` or
+/// `CONFIG_FILE`), then overridden by environment variables, then validated;
+/// see [`Config::load`]. Every field has a default, so an empty file is valid.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct Config {
     pub port: u16,
     pub client_url: String,
     pub verbose: bool,
+    pub log_format: LogFormat,
+    /// Request body limit in MiB
     pub payload_limit: usize,
+    pub rpc_allowed_methods: Option<Vec<String>>,
+    pub rpc_upstreams: HashMap<String, Vec<String>>,
+    pub rpc_default_network: String,
+    pub rpc_request_timeout_secs: u64,
+    pub rpc_health_interval_secs: u64,
+    pub rpc_max_block_lag: u64,
+    /// An upstream whose block height has not advanced for this long is
+    /// unhealthy; 0 disables the check
+    pub rpc_max_stall_secs: u64,
+    /// Networks whose faucet can fund a fresh deployment fee payer
+    pub rpc_faucet_networks: Vec<String>,
+    pub bitcoin_rpc: HashMap<String, BitcoinRpcConfig>,
+    pub localnet: Option<LocalnetConfig>,
+    pub deploy_chunk_retries: u32,
+    /// Hosts a deployment's signing callback may be sent to; empty disables
+    /// callback signers
+    pub deploy_callback_hosts: Vec<String>,
+    pub api_keys_file: Option<String>,
+    pub require_api_key: bool,
+    pub trust_forwarded_for: bool,
+    pub quota_anonymous: Quota,
+    pub quota_authenticated: Quota,
+    pub gc: GcConfig,
+    pub build: BuildConfig,
+    pub storage: StorageConfig,
+    /// How long shutdown waits for running builds before cancelling them
+    pub shutdown_deadline_secs: u64,
+    /// Seconds between checks of the config file for changes; 0 disables
+    /// watching, `POST /admin/config/reload` still works
+    pub config_watch_secs: u64,
 }
 
-impl Config {
-    pub fn from_env() -> Self {
+/// Where and how user programs are built.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BuildConfig {
+    /// Build projects, the shared target cache and the tracker state live here
+    pub programs_dir: String,
+    /// Most source files accepted in one build request
+    pub max_file_amount: usize,
+    pub max_path_length: usize,
+    /// Largest single file accepted in a build request
+    pub max_file_bytes: usize,
+    /// Largest combined size of the files of one build request
+    pub max_files_total_bytes: usize,
+    /// Builds run at once; further builds wait as `queued` for a free slot
+    pub capacity: usize,
+    /// Refuse to start without cargo, cargo-build-sbf and platform-tools
+    /// instead of starting and reporting not ready
+    pub require_toolchain: bool,
+    /// SDK profiles pre-compiled at startup and by `POST /admin/warmup`;
+    /// empty disables the warmup
+    pub warmup_profiles: Vec<String>,
+    /// Build without the network, against the vendored registry mirror
+    /// (`POST /admin/vendor/refresh`) with `--offline --locked`
+    pub offline: bool,
+}
+
+/// Where built binaries are kept besides the local build directory.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageConfig {
+    /// Artifacts and owner markers are mirrored to this GCS bucket when set
+    pub gcs_bucket: Option<String>,
+}
+
+/// Retention of finished builds; see `crate::gc`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GcConfig {
+    /// Seconds between collections; 0 disables the background task
+    pub interval_secs: u64,
+    /// Builds untouched for longer than this are removed
+    pub retention_hours: u64,
+    /// Cap on the combined size of all build directories
+    pub max_total_bytes: Option<u64>,
+    /// Newest builds kept per owner; older ones are removed
+    pub max_builds_per_owner: Option<usize>,
+    /// Size budget of the shared `programs/target` cache
+    pub target_max_bytes: Option<u64>,
+}
+
+/// Daily limits for one tier of principals; `None` means unlimited.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct Quota {
+    pub builds_per_day: Option<u64>,
+    pub build_seconds_per_day: Option<u64>,
+    pub artifact_bytes_per_day: Option<u64>,
+    pub rpc_calls_per_day: Option<u64>,
+}
+
+/// Child processes managed by the localnet subsystem. Only present when
+/// configured or `LOCALNET_ENABLED` is set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LocalnetConfig {
+    pub data_dir: String,
+    pub validator_bin: String,
+    pub validator_args: Vec<String>,
+    pub rpc_port: u16,
+    pub fee_payer_key: Option<Secret>,
+    pub bitcoind: Option<LocalBitcoindConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LocalBitcoindConfig {
+    pub bin: String,
+    pub rpc_port: u16,
+    pub rpc_username: String,
+    pub rpc_password: Secret,
+}
+
+/// Credentials for a Bitcoin Core node, held by the server so clients never see them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BitcoinRpcConfig {
+    pub url: String,
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub password: Secret,
+    #[serde(default = "default_bitcoin_rpc_methods")]
+    pub allowed_methods: Vec<String>,
+    /// Whether the IDE may fund devnet fee payers from the node's wallet,
+    /// which allows `sendtoaddress` on top of `allowed_methods`. Only for
+    /// nodes whose coins are worthless; defaults to on for `regtest`.
+    #[serde(default)]
+    pub faucet: Option<bool>,
+}
+
+impl BitcoinRpcConfig {
+    /// Methods `network` may call: `allowed_methods`, plus `sendtoaddress` for faucets.
+    pub fn methods(&self, network: &str) -> Vec<String> {
+        let mut methods = self.allowed_methods.clone();
+        if self.faucet.unwrap_or(network == "regtest") {
+            methods.push("sendtoaddress".to_string());
+        }
+        methods
+    }
+}
+
+/// A config value that must not show up in the startup `Config loaded` log
+/// or in `--print-config` output.
+#[derive(Clone, Default, Deserialize)]
+#[serde(transparent)]
+pub struct Secret(pub String);
+
+/// What a `Secret` prints as; `validate` rejects it so a printed config
+/// cannot be loaded back with the placeholder as the real value.
+const REDACTED: &str = "<redacted>";
+
+impl Secret {
+    fn is_redacted(&self) -> bool {
+        self.0 == REDACTED
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl Serialize for Secret {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(REDACTED)
+    }
+}
+
+const DEFAULT_BITCOIN_RPC_METHODS: &[&str] = &[
+    "getblockchaininfo",
+    "getblockcount",
+    "getblockhash",
+    "getblock",
+    "getrawtransaction",
+    "sendrawtransaction",
+    "testmempoolaccept",
+    "gettxout",
+    // The IDE reloads its funding wallet when the node reports it as not loaded
+    "loadwallet",
+];
+
+const DEFAULT_GCS_BUCKET: &str = "arch-ide-build-artifacts";
+
+fn default_bitcoin_rpc_methods() -> Vec<String> {
+    DEFAULT_BITCOIN_RPC_METHODS.iter().map(|m| m.to_string()).collect()
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            port: 8080,
+            client_url: "http://localhost:3000".to_string(),
+            verbose: false,
+            log_format: LogFormat::Text,
+            payload_limit: 10,
+            rpc_allowed_methods: None,
+            rpc_upstreams: HashMap::from([
+                ("testnet".to_string(), vec!["https://rpc-beta.test.arch.network".to_string()]),
+                ("mainnet".to_string(), vec!["https://rpc.arch.network".to_string()]),
+            ]),
+            rpc_default_network: "testnet".to_string(),
+            rpc_request_timeout_secs: 30,
+            rpc_health_interval_secs: 15,
+            rpc_max_block_lag: 5,
+            rpc_max_stall_secs: 300,
+            rpc_faucet_networks: vec!["testnet".to_string(), LOCALNET_NETWORK.to_string()],
+            bitcoin_rpc: HashMap::new(),
+            localnet: None,
+            deploy_chunk_retries: 3,
+            deploy_callback_hosts: Vec::new(),
+            api_keys_file: None,
+            require_api_key: false,
+            trust_forwarded_for: false,
+            quota_anonymous: Quota::default(),
+            quota_authenticated: Quota::default(),
+            gc: GcConfig::default(),
+            build: BuildConfig::default(),
+            storage: StorageConfig::default(),
+            shutdown_deadline_secs: 300,
+            config_watch_secs: 10,
+        }
+    }
+}
+
+impl Default for BuildConfig {
+    fn default() -> Self {
         Self {
-            port: env::var("PORT")
-                .unwrap_or_else(|_| "8080".to_string())
-                .parse()
-                .expect("PORT must be a number"),
-            client_url: env::var("CLIENT_URL")
-                .unwrap_or_else(|_| "http://localhost:3000".to_string()),
-            verbose: env::var("VERBOSE").is_ok(),
-            payload_limit: env::var("PAYLOAD_LIMIT")
-                .unwrap_or_else(|_| "10".to_string())
-                .parse()
-                .expect("PAYLOAD_LIMIT must be a number"),
-        }
-    }
-}
\ No newline at end of file
+            programs_dir: "programs".to_string(),
+            max_file_amount: 64,
+            max_path_length: 128,
+            max_file_bytes: 256 * 1024,
+            max_files_total_bytes: 2 * 1024 * 1024,
+            capacity: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            require_toolchain: false,
+            warmup_profiles: sdk::PROFILES.iter().map(|profile| profile.name.to_string()).collect(),
+            offline: false,
+        }
+    }
+}
+
+impl Default for GcConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: 3600,
+            retention_hours: 168,
+            max_total_bytes: None,
+            max_builds_per_owner: None,
+            target_max_bytes: None,
+        }
+    }
+}
+
+impl Default for LocalnetConfig {
+    fn default() -> Self {
+        Self {
+            data_dir: "localnet".to_string(),
+            validator_bin: "local_validator".to_string(),
+            validator_args: Vec::new(),
+            rpc_port: 9002,
+            fee_payer_key: None,
+            bitcoind: None,
+        }
+    }
+}
+
+impl Default for LocalBitcoindConfig {
+    fn default() -> Self {
+        Self {
+            bin: "bitcoind".to_string(),
+            rpc_port: 18443,
+            rpc_username: "bitcoin".to_string(),
+            rpc_password: Secret("bitcoin".to_string()),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("failed to read config file {}: {source}", path.display())]
+    Read { path: PathBuf, source: std::io::Error },
+    #[error("invalid config file {}: {source}", path.display())]
+    Parse { path: PathBuf, source: toml::de::Error },
+    #[error("invalid configuration:\n  - {}", .0.join("\n  - "))]
+    Invalid(Vec<String>),
+}
+
+impl Config {
+    /// Reads the TOML file at `path` (or `CONFIG_FILE`) if there is one,
+    /// applies environment overrides and validates the result. All problems
+    /// are reported at once rather than stopping at the first.
+    pub fn load(path: Option<&Path>) -> Result<Self, ConfigError> {
+        let path = Self::resolve_path(path);
+        let mut config = match &path {
+            Some(path) => Self::from_file(path)?,
+            None => Self::default(),
+        };
+        let mut errors = config.apply_env(|name| env::var(name).ok());
+        errors.extend(config.validate());
+        if errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(ConfigError::Invalid(errors))
+        }
+    }
+
+    /// The config file to read: `path` if given, else `CONFIG_FILE`.
+    pub fn resolve_path(path: Option<&Path>) -> Option<PathBuf> {
+        path.map(Path::to_path_buf).or_else(|| env::var_os("CONFIG_FILE").map(PathBuf::from))
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        let contents = fs::read_to_string(path)
+            .map_err(|source| ConfigError::Read { path: path.to_path_buf(), source })?;
+        toml::from_str(&contents).map_err(|source| ConfigError::Parse { path: path.to_path_buf(), source })
+    }
+
+    /// Overrides file settings with the environment variables the server has
+    /// always read. Returns the variables that could not be parsed.
+    fn apply_env(&mut self, var: impl Fn(&str) -> Option<String>) -> Vec<String> {
+        let mut env = EnvOverrides { var, errors: Vec::new() };
+
+        env.parse("PORT", &mut self.port);
+        env.string("CLIENT_URL", &mut self.client_url);
+        env.flag("VERBOSE", &mut self.verbose);
+        env.parse("LOG_FORMAT", &mut self.log_format);
+        env.parse("PAYLOAD_LIMIT", &mut self.payload_limit);
+        if let Some(methods) = env.get("RPC_ALLOWED_METHODS") {
+            self.rpc_allowed_methods = Some(split_list(&methods));
+        }
+        match env.get("RPC_UPSTREAMS") {
+            Some(value) => match parse_upstreams(&value) {
+                Ok(upstreams) => self.rpc_upstreams = upstreams,
+                Err(e) => env.errors.push(e),
+            },
+            None => {
+                if let Some(url) = env.get("RPC_URL") {
+                    self.rpc_upstreams.insert("testnet".to_string(), vec![url]);
+                }
+            }
+        }
+        env.string("RPC_DEFAULT_NETWORK", &mut self.rpc_default_network);
+        env.parse("RPC_REQUEST_TIMEOUT_SECS", &mut self.rpc_request_timeout_secs);
+        env.parse("RPC_HEALTH_INTERVAL_SECS", &mut self.rpc_health_interval_secs);
+        env.parse("RPC_MAX_BLOCK_LAG", &mut self.rpc_max_block_lag);
+        env.parse("RPC_MAX_STALL_SECS", &mut self.rpc_max_stall_secs);
+        if let Some(networks) = env.get("RPC_FAUCET_NETWORKS") {
+            self.rpc_faucet_networks = split_list(&networks);
+        }
+        self.apply_bitcoin_rpc_env(&mut env);
+        self.apply_localnet_env(&mut env);
+        env.parse("DEPLOY_CHUNK_RETRIES", &mut self.deploy_chunk_retries);
+        if let Some(hosts) = env.get("DEPLOY_CALLBACK_HOSTS") {
+            self.deploy_callback_hosts = split_list(&hosts);
+        }
+        if let Some(path) = env.get("API_KEYS_FILE") {
+            self.api_keys_file = Some(path);
+        }
+        env.flag("REQUIRE_API_KEY", &mut self.require_api_key);
+        env.flag("TRUST_X_FORWARDED_FOR", &mut self.trust_forwarded_for);
+
+        for (prefix, quota) in [
+            ("QUOTA_ANONYMOUS", &mut self.quota_anonymous),
+            ("QUOTA_AUTHENTICATED", &mut self.quota_authenticated),
+        ] {
+            env.optional(&format!("{prefix}_BUILDS_PER_DAY"), &mut quota.builds_per_day);
+            env.optional(&format!("{prefix}_BUILD_SECONDS_PER_DAY"), &mut quota.build_seconds_per_day);
+            env.optional(&format!("{prefix}_ARTIFACT_BYTES_PER_DAY"), &mut quota.artifact_bytes_per_day);
+            env.optional(&format!("{prefix}_RPC_CALLS_PER_DAY"), &mut quota.rpc_calls_per_day);
+        }
+
+        env.parse("GC_INTERVAL_SECS", &mut self.gc.interval_secs);
+        env.parse("BUILD_RETENTION_HOURS", &mut self.gc.retention_hours);
+        env.optional("BUILDS_MAX_BYTES", &mut self.gc.max_total_bytes);
+        env.optional("MAX_BUILDS_PER_OWNER", &mut self.gc.max_builds_per_owner);
+        env.optional("TARGET_DIR_MAX_BYTES", &mut self.gc.target_max_bytes);
+
+        env.string("PROGRAMS_DIR", &mut self.build.programs_dir);
+        env.parse("MAX_FILE_AMOUNT", &mut self.build.max_file_amount);
+        env.parse("MAX_PATH_LENGTH", &mut self.build.max_path_length);
+        env.parse("MAX_FILE_BYTES", &mut self.build.max_file_bytes);
+        env.parse("MAX_FILES_TOTAL_BYTES", &mut self.build.max_files_total_bytes);
+        env.parse("BUILD_CAPACITY", &mut self.build.capacity);
+        env.flag("REQUIRE_TOOLCHAIN", &mut self.build.require_toolchain);
+        if let Some(profiles) = env.get("WARMUP_PROFILES") {
+            self.build.warmup_profiles = split_list(&profiles);
+        }
+        env.flag("BUILD_OFFLINE", &mut self.build.offline);
+
+        // `USE_GCS` switches artifact storage on, `GCS_BUCKET` only names the bucket
+        let mut use_gcs = self.storage.gcs_bucket.is_some();
+        env.flag("USE_GCS", &mut use_gcs);
+        if use_gcs {
+            let bucket = env.get("GCS_BUCKET").or(self.storage.gcs_bucket.take());
+            self.storage.gcs_bucket = Some(bucket.unwrap_or_else(|| DEFAULT_GCS_BUCKET.to_string()));
+        } else {
+            self.storage.gcs_bucket = None;
+        }
+
+        env.parse("SHUTDOWN_DEADLINE_SECS", &mut self.shutdown_deadline_secs);
+        env.parse("CONFIG_WATCH_SECS", &mut self.config_watch_secs);
+        env.errors
+    }
+
+    /// Reads `BITCOIN_RPC_NETWORKS=regtest,testnet` and, for each network,
+    /// `BITCOIN_RPC_<NETWORK>_URL`, `_USERNAME`, `_PASSWORD` and the optional
+    /// `_ALLOWED_METHODS` and `_FAUCET` overrides. Networks from the file are kept.
+    fn apply_bitcoin_rpc_env<F: Fn(&str) -> Option<String>>(&mut self, env: &mut EnvOverrides<F>) {
+        let Some(networks) = env.get("BITCOIN_RPC_NETWORKS") else {
+            return;
+        };
+        for network in split_list(&networks) {
+            let prefix = format!("BITCOIN_RPC_{}", network.to_uppercase());
+            let url = env.get(&format!("{prefix}_URL")).or_else(|| self.bitcoin_rpc.get(&network).map(|c| c.url.clone()));
+            let Some(url) = url else {
+                env.errors.push(format!("{prefix}_URL must be set"));
+                continue;
+            };
+            let config = self.bitcoin_rpc.entry(network).or_insert_with(|| BitcoinRpcConfig {
+                url: String::new(),
+                username: String::new(),
+                password: Secret::default(),
+                allowed_methods: default_bitcoin_rpc_methods(),
+                faucet: None,
+            });
+            config.url = url;
+            env.string(&format!("{prefix}_USERNAME"), &mut config.username);
+            if let Some(password) = env.get(&format!("{prefix}_PASSWORD")) {
+                config.password = Secret(password);
+            }
+            if let Some(methods) = env.get(&format!("{prefix}_ALLOWED_METHODS")) {
+                config.allowed_methods = split_list(&methods);
+            }
+            env.optional(&format!("{prefix}_FAUCET"), &mut config.faucet);
+        }
+    }
+
+    /// `LOCALNET_ENABLED` turns localnet on (or off, when `0`/`false`) with
+    /// the `LOCALNET_*` variables layered over the file's `[localnet]`.
+    fn apply_localnet_env<F: Fn(&str) -> Option<String>>(&mut self, env: &mut EnvOverrides<F>) {
+        let mut enabled = self.localnet.is_some();
+        env.flag("LOCALNET_ENABLED", &mut enabled);
+        if !enabled {
+            self.localnet = None;
+            return;
+        }
+
+        let localnet = self.localnet.get_or_insert_with(LocalnetConfig::default);
+        env.string("LOCALNET_DATA_DIR", &mut localnet.data_dir);
+        env.string("LOCALNET_VALIDATOR_BIN", &mut localnet.validator_bin);
+        if let Some(args) = env.get("LOCALNET_VALIDATOR_ARGS") {
+            localnet.validator_args = args.split_whitespace().map(str::to_string).collect();
+        }
+        env.parse("LOCALNET_RPC_PORT", &mut localnet.rpc_port);
+        if let Some(key) = env.get("LOCALNET_FEE_PAYER_KEY") {
+            localnet.fee_payer_key = Some(Secret(key));
+        }
+
+        if let Some(bin) = env.get("LOCALNET_BITCOIND_BIN") {
+            localnet.bitcoind.get_or_insert_with(LocalBitcoindConfig::default).bin = bin;
+        }
+        if let Some(bitcoind) = &mut localnet.bitcoind {
+            env.parse("LOCALNET_BITCOIND_RPC_PORT", &mut bitcoind.rpc_port);
+            env.string("LOCALNET_BITCOIND_RPC_USERNAME", &mut bitcoind.rpc_username);
+            if let Some(password) = env.get("LOCALNET_BITCOIND_RPC_PASSWORD") {
+                bitcoind.rpc_password = Secret(password);
+            }
+        }
+    }
+
+    /// Checks what would otherwise only fail on first use, e.g. an upstream
+    /// URL that does not parse.
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        let mut check = |ok: bool, message: String| {
+            if !ok {
+                errors.push(message);
+            }
+        };
+
+        check(self.port != 0, "port must not be 0".to_string());
+        check(self.payload_limit > 0, "payload_limit must be at least 1 (MiB)".to_string());
+        for origin in split_list(&self.client_url) {
+            check(is_http_url(&origin), format!("client_url: '{origin}' is not an http(s) URL"));
+        }
+
+        check(!self.rpc_upstreams.is_empty(), "rpc_upstreams must list at least one network".to_string());
+        for (network, urls) in &self.rpc_upstreams {
+            check(!urls.is_empty(), format!("rpc_upstreams.{network} has no URLs"));
+            for url in urls {
+                check(is_http_url(url), format!("rpc_upstreams.{network}: '{url}' is not an http(s) URL"));
+            }
+        }
+        check(
+            self.rpc_upstreams.contains_key(&self.rpc_default_network),
+            format!("rpc_default_network '{}' is not one of rpc_upstreams", self.rpc_default_network),
+        );
+        check(self.rpc_request_timeout_secs > 0, "rpc_request_timeout_secs must be at least 1".to_string());
+        check(self.rpc_health_interval_secs > 0, "rpc_health_interval_secs must be at least 1".to_string());
+        for (network, config) in &self.bitcoin_rpc {
+            check(is_http_url(&config.url), format!("bitcoin_rpc.{network}.url: '{}' is not an http(s) URL", config.url));
+            check(!config.password.is_redacted(), format!("bitcoin_rpc.{network}.password is the {REDACTED} placeholder"));
+        }
+
+        if let Some(localnet) = &self.localnet {
+            check(localnet.rpc_port != 0, "localnet.rpc_port must not be 0".to_string());
+            check(!localnet.validator_bin.is_empty(), "localnet.validator_bin must not be empty".to_string());
+            check(
+                !localnet.fee_payer_key.as_ref().is_some_and(Secret::is_redacted),
+                format!("localnet.fee_payer_key is the {REDACTED} placeholder"),
+            );
+            if let Some(bitcoind) = &localnet.bitcoind {
+                check(
+                    !bitcoind.rpc_password.is_redacted(),
+                    format!("localnet.bitcoind.rpc_password is the {REDACTED} placeholder"),
+                );
+                // Both are written to bitcoind's line-based config file
+                check(
+                    ![&bitcoind.rpc_username, &bitcoind.rpc_password.0].iter().any(|v| v.contains(['\n', '\r'])),
+                    "localnet.bitcoind RPC credentials must not contain line breaks".to_string(),
+                );
+            }
+        }
+
+        check(
+            !self.require_api_key || self.api_keys_file.is_some(),
+            "require_api_key needs api_keys_file to be set".to_string(),
+        );
+
+        check(!self.build.programs_dir.is_empty(), "build.programs_dir must not be empty".to_string());
+        check(self.build.max_file_amount > 0, "build.max_file_amount must be at least 1".to_string());
+        check(self.build.max_path_length > 0, "build.max_path_length must be at least 1".to_string());
+        check(self.build.max_file_bytes > 0, "build.max_file_bytes must be at least 1".to_string());
+        check(
+            self.build.max_files_total_bytes >= self.build.max_file_bytes,
+            "build.max_files_total_bytes must be at least build.max_file_bytes".to_string(),
+        );
+        check(self.build.capacity > 0, "build.capacity must be at least 1".to_string());
+        for profile in &self.build.warmup_profiles {
+            check(
+                SdkProfile::find(profile).is_some(),
+                format!("build.warmup_profiles: '{profile}' is not a known SDK profile"),
+            );
+        }
+        if let Some(bucket) = &self.storage.gcs_bucket {
+            check(!bucket.trim().is_empty(), "storage.gcs_bucket must not be empty".to_string());
+        }
+
+        errors
+    }
+}
+
+/// The config in effect, swapped as a whole by `crate::reload`. Handlers
+/// take a snapshot with [`SharedConfig::current`] and keep it for the request.
+#[derive(Clone)]
+pub struct SharedConfig {
+    /// File the config was loaded from, if any; reloads read it again
+    path: Option<PathBuf>,
+    current: Arc<RwLock<Arc<Config>>>,
+}
+
+impl SharedConfig {
+    pub fn new(config: Config, path: Option<PathBuf>) -> Self {
+        Self { path, current: Arc::new(RwLock::new(Arc::new(config))) }
+    }
+
+    pub fn current(&self) -> Arc<Config> {
+        self.current.read().unwrap().clone()
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn replace(&self, config: Config) {
+        *self.current.write().unwrap() = Arc::new(config);
+    }
+}
+
+/// Environment lookups for [`Config::apply_env`], collecting parse errors
+/// instead of panicking on the first one.
+struct EnvOverrides<F> {
+    var: F,
+    errors: Vec<String>,
+}
+
+impl<F: Fn(&str) -> Option<String>> EnvOverrides<F> {
+    fn get(&self, name: &str) -> Option<String> {
+        (self.var)(name)
+    }
+
+    fn string(&self, name: &str, target: &mut String) {
+        if let Some(value) = self.get(name) {
+            *target = value;
+        }
+    }
+
+    /// Set and not `0`, `false` or empty.
+    fn flag(&self, name: &str, target: &mut bool) {
+        if let Some(value) = self.get(name) {
+            *target = !matches!(value.trim(), "" | "0" | "false");
+        }
+    }
+
+    fn parse<T: FromStr>(&mut self, name: &str, target: &mut T)
+    where
+        T::Err: fmt::Display,
+    {
+        if let Some(value) = self.get(name) {
+            match value.trim().parse() {
+                Ok(parsed) => *target = parsed,
+                Err(e) => self.errors.push(format!("{name}='{value}': {e}")),
+            }
+        }
+    }
+
+    fn optional<T: FromStr>(&mut self, name: &str, target: &mut Option<T>)
+    where
+        T::Err: fmt::Display,
+    {
+        if let Some(value) = self.get(name) {
+            match value.trim().parse() {
+                Ok(parsed) => *target = Some(parsed),
+                Err(e) => self.errors.push(format!("{name}='{value}': {e}")),
+            }
+        }
+    }
+}
+
+/// Parses `RPC_UPSTREAMS=testnet=https://a,https://b;mainnet=https://c`.
+fn parse_upstreams(value: &str) -> Result<HashMap<String, Vec<String>>, String> {
+    value
+        .split(';')
+        .filter(|entry| !entry.trim().is_empty())
+        .map(|entry| {
+            let (name, urls) = entry
+                .split_once('=')
+                .ok_or_else(|| format!("RPC_UPSTREAMS entry '{entry}' must be name=url[,url]"))?;
+            Ok((name.trim().to_string(), split_list(urls)))
+        })
+        .collect()
+}
+
+fn is_http_url(value: &str) -> bool {
+    reqwest::Url::parse(value).is_ok_and(|url| matches!(url.scheme(), "http" | "https"))
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env_of(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn env_overrides_file_and_errors_are_collected() {
+        let mut config: Config = toml::from_str(
+            r#"
+            port = 9000
+            rpc_default_network = "devnet"
+
+            [rpc_upstreams]
+            devnet = ["http://127.0.0.1:9002"]
+
+            [build]
+            max_file_amount = 16
+
+            [storage]
+            gcs_bucket = "artifacts"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.build.max_path_length, 128);
+
+        let errors = config.apply_env(env_of(&[("PORT", "8081"), ("USE_GCS", "false"), ("PAYLOAD_LIMIT", "ten")]));
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("PAYLOAD_LIMIT"));
+        assert_eq!((config.port, config.build.max_file_amount), (8081, 16));
+        assert_eq!(config.storage.gcs_bucket, None);
+        assert!(config.validate().is_empty());
+
+        config.rpc_default_network = "testnet".to_string();
+        config.build.capacity = 0;
+        config.build.warmup_profiles.push("arch-0.4".to_string());
+        assert_eq!(config.validate().len(), 3);
+
+        assert!(toml::from_str::<Config>("prot = 8080").is_err());
+    }
+
+    #[test]
+    fn printed_config_redacts_secrets_and_fails_validation_when_loaded_back() {
+        let mut config = Config::default();
+        config.apply_env(env_of(&[("LOCALNET_ENABLED", "1"), ("LOCALNET_FEE_PAYER_KEY", "deadbeef")]));
+        let printed = toml::to_string_pretty(&config).unwrap();
+        assert!(!printed.contains("deadbeef"));
+
+        let reloaded: Config = toml::from_str(&printed).unwrap();
+        assert_eq!(reloaded.localnet.as_ref().unwrap().rpc_port, 9002);
+        assert_eq!(reloaded.validate(), ["localnet.fee_payer_key is the <redacted> placeholder"]);
+    }
+
+    #[test]
+    fn only_faucet_bitcoin_networks_allow_sendtoaddress() {
+        let mut config = Config::default();
+        let errors = config.apply_env(env_of(&[
+            ("BITCOIN_RPC_NETWORKS", "regtest,signet,testnet"),
+            ("BITCOIN_RPC_REGTEST_URL", "http://127.0.0.1:18443"),
+            ("BITCOIN_RPC_SIGNET_URL", "http://127.0.0.1:38332"),
+            ("BITCOIN_RPC_SIGNET_FAUCET", "true"),
+            ("BITCOIN_RPC_TESTNET_URL", "http://127.0.0.1:18332"),
+        ]));
+        assert!(errors.is_empty(), "{errors:?}");
+
+        let faucet = |network: &str| config.bitcoin_rpc[network].methods(network).contains(&"sendtoaddress".to_string());
+        assert!(faucet("regtest"));
+        assert!(faucet("signet"));
+        assert!(!faucet("testnet"));
+    }
+}
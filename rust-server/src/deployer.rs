@@ -0,0 +1,395 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use bitcoin::key::Keypair;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::{
+    arch::{
+        loader_instruction, minimum_rent, system_instruction, ArchMessage, Instruction, Pubkey,
+        RuntimeTransaction, BPF_LOADER_ID, LOADER_STATE_SIZE, RUNTIME_TX_SIZE_LIMIT, SYSTEM_PROGRAM_ID,
+    },
+    arch_rpc::{AccountInfo, ArchRpcClient, TxStatus},
+    bip322,
+    build_tracker::{BuildTracker, DeploymentStatus},
+};
+
+/// Matches the SDK's `MAX_TX_BATCH_SIZE` for `send_transactions`.
+const MAX_TX_BATCH_SIZE: usize = 100;
+const CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Produces BIP-322 signatures for one account, either with a key the server
+/// holds or by asking a remote signer.
+pub enum Signer {
+    Keypair(Keypair),
+    Callback {
+        pubkey: Pubkey,
+        url: String,
+        client: reqwest::Client,
+    },
+}
+
+#[derive(Serialize)]
+struct SignRequest<'a> {
+    pubkey: String,
+    /// The message to sign, as text; sign its UTF-8 bytes.
+    message: &'a str,
+}
+
+#[derive(Deserialize)]
+struct SignResponse {
+    signature: String,
+}
+
+impl Signer {
+    pub fn pubkey(&self) -> Pubkey {
+        match self {
+            Signer::Keypair(keypair) => keypair.x_only_public_key().0.serialize(),
+            Signer::Callback { pubkey, .. } => *pubkey,
+        }
+    }
+
+    async fn sign(&self, message_hash: &[u8]) -> anyhow::Result<Vec<u8>> {
+        match self {
+            Signer::Keypair(keypair) => Ok(bip322::sign_message(keypair, message_hash).to_vec()),
+            Signer::Callback { pubkey, url, client } => {
+                let message = std::str::from_utf8(message_hash)?;
+                let response: SignResponse = client
+                    .post(url)
+                    .json(&SignRequest { pubkey: hex::encode(pubkey), message })
+                    .send()
+                    .await
+                    .and_then(|r| r.error_for_status())
+                    .with_context(|| format!("Signing callback {url} failed"))?
+                    .json()
+                    .await
+                    .with_context(|| format!("Invalid response from signing callback {url}"))?;
+
+                let signature = hex::decode(&response.signature)?;
+                if signature.len() != 64 {
+                    return Err(anyhow!("Signing callback returned {} bytes, expected 64", signature.len()));
+                }
+                Ok(signature)
+            }
+        }
+    }
+}
+
+/// Uploads a built ELF to an Arch network, following the SDK's
+/// `program_deployment` flow, and records progress in the build tracker.
+pub struct Deployer {
+    rpc: ArchRpcClient,
+    tracker: BuildTracker,
+    uuid: String,
+    chunk_retries: u32,
+    fund_authority: bool,
+}
+
+impl Deployer {
+    pub fn new(
+        rpc: ArchRpcClient,
+        tracker: BuildTracker,
+        uuid: String,
+        chunk_retries: u32,
+        fund_authority: bool,
+    ) -> Self {
+        Self { rpc, tracker, uuid, chunk_retries, fund_authority }
+    }
+
+    async fn stage(&self, stage: &str) {
+        info!(stage, "Deployment stage");
+        self.tracker
+            .update_deployment(&self.uuid, |d| d.stage = stage.to_string())
+            .await;
+    }
+
+    async fn record_txids(&self, txids: &[String]) {
+        self.tracker
+            .update_deployment(&self.uuid, |d| d.txids.extend_from_slice(txids))
+            .await;
+    }
+
+    /// Runs the deployment and marks it succeeded or failed in the tracker.
+    pub async fn run(self, elf: Vec<u8>, program: Signer, authority: Signer) {
+        let result = self.deploy(&elf, &program, &authority).await;
+
+        self.tracker
+            .update_deployment(&self.uuid, |d| {
+                d.completed_at = Some(chrono::Utc::now());
+                match &result {
+                    Ok(()) => {
+                        d.status = DeploymentStatus::Success;
+                        d.stage = "deployed".to_string();
+                    }
+                    Err(e) => {
+                        d.status = DeploymentStatus::Failed;
+                        d.error = Some(format!("{e:#}"));
+                    }
+                }
+            })
+            .await;
+
+        match result {
+            Ok(()) => info!("Program deployed"),
+            Err(e) => warn!("Deployment failed: {:#}", e),
+        }
+    }
+
+    async fn deploy(&self, elf: &[u8], program: &Signer, authority: &Signer) -> anyhow::Result<()> {
+        let program_id = program.pubkey();
+        let authority_id = authority.pubkey();
+
+        if self.fund_authority {
+            self.stage("funding authority").await;
+            self.fund(authority).await?;
+        }
+
+        self.stage("reading program account").await;
+        let mut account = self.rpc.read_account_info(&program_id).await?;
+
+        if let Some(existing) = &account {
+            if existing.data.get(LOADER_STATE_SIZE..) == Some(elf) {
+                if !existing.is_executable {
+                    self.make_executable(program_id, authority).await?;
+                }
+                return Ok(());
+            }
+
+            if existing.owner != BPF_LOADER_ID {
+                self.stage("assigning program account to loader").await;
+                let ix = system_instruction::assign(program_id, BPF_LOADER_ID);
+                self.send_and_confirm(&[ix], &[program], program_id).await?;
+                account = self.rpc.read_account_info(&program_id).await?;
+            }
+        }
+
+        let account = match account {
+            Some(account) => account,
+            None => {
+                self.stage("creating program account").await;
+                // Like the SDK, allocate nothing here; Truncate sizes the account
+                let lamports = minimum_rent(LOADER_STATE_SIZE + elf.len());
+                let ix = system_instruction::create_account(authority_id, program_id, lamports, 0, BPF_LOADER_ID);
+                self.send_and_confirm(&[ix], &[authority, program], authority_id).await?;
+
+                let account = self
+                    .rpc
+                    .read_account_info(&program_id)
+                    .await?
+                    .ok_or_else(|| anyhow!("Program account was not created"))?;
+                if account.owner != BPF_LOADER_ID {
+                    return Err(anyhow!("Program account created with owner {}", hex::encode(account.owner)));
+                }
+                account
+            }
+        };
+
+        self.prepare_account(&account, elf, program, authority).await?;
+        self.write_chunks(elf, program_id, authority).await?;
+
+        self.stage("verifying upload").await;
+        let account = self
+            .rpc
+            .read_account_info(&program_id)
+            .await?
+            .ok_or_else(|| anyhow!("Program account disappeared after upload"))?;
+        if account.data.get(LOADER_STATE_SIZE..) != Some(elf) {
+            return Err(anyhow!("Deployed ELF does not match the build artifact"));
+        }
+
+        if !account.is_executable {
+            self.make_executable(program_id, authority).await?;
+        }
+        Ok(())
+    }
+
+    async fn fund(&self, authority: &Signer) -> anyhow::Result<()> {
+        let authority_id = authority.pubkey();
+        if let Some(account) = self.rpc.read_account_info(&authority_id).await? {
+            if account.owner != SYSTEM_PROGRAM_ID {
+                return Err(anyhow!(
+                    "Fee payer {} is owned by {}, not the system program",
+                    hex::encode(authority_id),
+                    hex::encode(account.owner)
+                ));
+            }
+            return Ok(());
+        }
+
+        let mut tx = self.rpc.create_account_with_faucet(&authority_id).await?;
+        tx.signatures.push(authority.sign(&tx.message.hash()).await?);
+        let txid = self.rpc.send_transaction(&tx).await?;
+        self.confirm(&txid).await?;
+        self.record_txids(&[txid]).await;
+        Ok(())
+    }
+
+    /// Retracts an executable program and resizes the account to fit `elf`.
+    async fn prepare_account(
+        &self,
+        account: &AccountInfo,
+        elf: &[u8],
+        program: &Signer,
+        authority: &Signer,
+    ) -> anyhow::Result<()> {
+        let program_id = program.pubkey();
+        let authority_id = authority.pubkey();
+
+        if account.is_executable {
+            self.stage("retracting program").await;
+            let ix = loader_instruction::retract(program_id, authority_id);
+            self.send_and_confirm(&[ix], &[authority], authority_id).await?;
+        }
+
+        let required_size = LOADER_STATE_SIZE + elf.len();
+        if account.data.len() != required_size {
+            let missing_lamports = minimum_rent(required_size).saturating_sub(account.lamports);
+            if missing_lamports > 0 {
+                self.stage("topping up rent").await;
+                let ix = system_instruction::transfer(authority_id, program_id, missing_lamports);
+                self.send_and_confirm(&[ix], &[authority], authority_id).await?;
+            }
+
+            self.stage("resizing program account").await;
+            let ix = loader_instruction::truncate(program_id, authority_id, elf.len() as u32);
+            self.send_and_confirm(&[ix], &[program, authority], authority_id).await?;
+        }
+        Ok(())
+    }
+
+    /// Uploads `elf` in `Write` chunks, batching sends and re-sending only the
+    /// chunks that failed or went unconfirmed, up to `chunk_retries` times.
+    async fn write_chunks(&self, elf: &[u8], program_id: Pubkey, authority: &Signer) -> anyhow::Result<()> {
+        let authority_id = authority.pubkey();
+        let mut pending: Vec<usize> = (0..elf.len()).step_by(loader_instruction::MAX_CHUNK_SIZE).collect();
+        let total = pending.len();
+
+        self.tracker
+            .update_deployment(&self.uuid, |d| {
+                d.chunks_total = total;
+                d.chunks_written = 0;
+            })
+            .await;
+
+        for attempt in 0..=self.chunk_retries {
+            if pending.is_empty() {
+                break;
+            }
+            if attempt > 0 {
+                warn!(chunks = pending.len(), attempt, "Retrying failed chunks");
+            }
+            self.stage(&format!("writing {} chunk(s)", pending.len())).await;
+
+            let recent_blockhash = self.rpc.get_best_block_hash().await?;
+            let mut failed = Vec::new();
+
+            for batch in pending.chunks(MAX_TX_BATCH_SIZE) {
+                let mut txs = Vec::with_capacity(batch.len());
+                for &offset in batch {
+                    let end = (offset + loader_instruction::MAX_CHUNK_SIZE).min(elf.len());
+                    let ix = loader_instruction::write(program_id, authority_id, offset as u32, &elf[offset..end]);
+                    let message = ArchMessage::new(&[ix], Some(authority_id), recent_blockhash);
+                    txs.push(self.sign(message, &[authority]).await?);
+                }
+
+                let txids = match self.rpc.send_transactions(&txs).await {
+                    Ok(txids) if txids.len() == txs.len() => txids,
+                    // Fall back to the locally computed ids so no chunk goes unconfirmed
+                    Ok(_) => txs.iter().map(RuntimeTransaction::txid).collect(),
+                    Err(e) => {
+                        warn!("Batch send failed: {:#}", e);
+                        failed.extend_from_slice(batch);
+                        continue;
+                    }
+                };
+
+                let confirmations = futures_util::future::join_all(
+                    txids.iter().map(|txid| self.rpc.wait_for_transaction(txid, CONFIRMATION_TIMEOUT)),
+                )
+                .await;
+
+                let mut confirmed = Vec::new();
+                for ((offset, txid), result) in batch.iter().zip(&txids).zip(confirmations) {
+                    match result {
+                        Ok(TxStatus::Processed) => confirmed.push(txid.clone()),
+                        Ok(status) => {
+                            warn!(offset, ?status, "Chunk failed");
+                            failed.push(*offset);
+                        }
+                        Err(e) => {
+                            warn!(offset, "Chunk failed: {:#}", e);
+                            failed.push(*offset);
+                        }
+                    }
+                }
+
+                self.tracker
+                    .update_deployment(&self.uuid, |d| {
+                        d.chunks_written += confirmed.len();
+                        d.txids.extend(confirmed);
+                    })
+                    .await;
+            }
+
+            pending = failed;
+        }
+
+        if !pending.is_empty() {
+            return Err(anyhow!(
+                "{} of {} chunk(s) failed after {} retries",
+                pending.len(),
+                total,
+                self.chunk_retries
+            ));
+        }
+        Ok(())
+    }
+
+    async fn make_executable(&self, program_id: Pubkey, authority: &Signer) -> anyhow::Result<()> {
+        self.stage("making program executable").await;
+        let authority_id = authority.pubkey();
+        let ix = loader_instruction::deploy(program_id, authority_id);
+        self.send_and_confirm(&[ix], &[authority], authority_id).await
+    }
+
+    async fn sign(&self, message: ArchMessage, signers: &[&Signer]) -> anyhow::Result<RuntimeTransaction> {
+        let hash = message.hash();
+        let mut signatures = Vec::with_capacity(message.signers().len());
+        for key in message.signers() {
+            let signer = signers
+                .iter()
+                .find(|s| s.pubkey() == *key)
+                .ok_or_else(|| anyhow!("No signer for account {}", hex::encode(key)))?;
+            signatures.push(signer.sign(&hash).await?);
+        }
+        let tx = RuntimeTransaction { version: 0, signatures, message };
+        let size = tx.serialize().len();
+        if size > RUNTIME_TX_SIZE_LIMIT {
+            return Err(anyhow!("Transaction is {size} bytes, over the {RUNTIME_TX_SIZE_LIMIT} byte limit"));
+        }
+        Ok(tx)
+    }
+
+    async fn send_and_confirm(
+        &self,
+        instructions: &[Instruction],
+        signers: &[&Signer],
+        payer: Pubkey,
+    ) -> anyhow::Result<()> {
+        let recent_blockhash = self.rpc.get_best_block_hash().await?;
+        let message = ArchMessage::new(instructions, Some(payer), recent_blockhash);
+        let tx = self.sign(message, signers).await?;
+        let txid = self.rpc.send_transaction(&tx).await?;
+        self.confirm(&txid).await?;
+        self.record_txids(&[txid]).await;
+        Ok(())
+    }
+
+    async fn confirm(&self, txid: &str) -> anyhow::Result<()> {
+        match self.rpc.wait_for_transaction(txid, CONFIRMATION_TIMEOUT).await? {
+            TxStatus::Processed => Ok(()),
+            status => Err(anyhow!("Transaction {txid} failed: {status:?}")),
+        }
+    }
+}
@@ -1,34 +1,238 @@
 use axum::{
+    extract::rejection::JsonRejection,
     http::StatusCode,
     response::{IntoResponse, Response},
+    Json,
 };
+use serde::Serialize;
 use thiserror::Error;
+use utoipa::ToSchema;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// Stable, machine-readable error codes. Clients should branch on these, not
+/// on the message text, which may change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    BadRequest,
+    /// The body is not valid JSON or does not match the expected shape
+    InvalidJson,
+    PayloadTooLarge,
+    InvalidUuid,
+    InvalidPath,
+    TooManyFiles,
+    InvalidKeypair,
+    InvalidJsonRpc,
+    /// The `Api-Version` header names no supported schema
+    UnsupportedApiVersion,
+    Unauthorized,
+    Forbidden,
+    /// The RPC method is not on the allowlist
+    MethodNotAllowed,
+    NotFound,
+    BuildNotFound,
+    /// The build exists but has no binary to deploy
+    ProgramNotBuilt,
+    NetworkNotFound,
+    Conflict,
+    /// The build has not finished successfully
+    BuildNotReady,
+    /// A build or deployment of this uuid is still running, or it is being purged
+    BuildInProgress,
+    DeploymentInProgress,
+    QuotaExceeded,
+    ShuttingDown,
+    UpstreamError,
+    UpstreamTimeout,
+    Internal,
+}
+
+impl ErrorCode {
+    pub fn status(self) -> StatusCode {
+        match self {
+            Self::BadRequest
+            | Self::InvalidJson
+            | Self::InvalidUuid
+            | Self::InvalidPath
+            | Self::TooManyFiles
+            | Self::InvalidKeypair
+            | Self::InvalidJsonRpc
+            | Self::UnsupportedApiVersion => StatusCode::BAD_REQUEST,
+            Self::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::Unauthorized => StatusCode::UNAUTHORIZED,
+            Self::Forbidden | Self::MethodNotAllowed => StatusCode::FORBIDDEN,
+            Self::NotFound | Self::BuildNotFound | Self::ProgramNotBuilt | Self::NetworkNotFound => {
+                StatusCode::NOT_FOUND
+            }
+            Self::Conflict | Self::BuildNotReady | Self::BuildInProgress | Self::DeploymentInProgress => {
+                StatusCode::CONFLICT
+            }
+            Self::QuotaExceeded => StatusCode::TOO_MANY_REQUESTS,
+            Self::ShuttingDown => StatusCode::SERVICE_UNAVAILABLE,
+            Self::UpstreamError => StatusCode::BAD_GATEWAY,
+            Self::UpstreamTimeout => StatusCode::GATEWAY_TIMEOUT,
+            Self::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// What is wrong with one field of the request, e.g. `files[2].path`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("Invalid request: {0}")]
     BadRequest(String),
 
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
+    #[error("Quota exceeded: {0}")]
+    QuotaExceeded(String),
+
     #[error("Not found: {0}")]
     NotFound(String),
 
+    #[error("Upstream error: {0}")]
+    BadGateway(String),
+
     #[error("Internal error: {0}")]
     Internal(String),
 
+    /// An error with a more specific code than the variants above
+    #[error("{message}")]
+    Coded {
+        code: ErrorCode,
+        message: String,
+        details: Vec<FieldError>,
+    },
+
     #[error(transparent)]
     Anyhow(#[from] anyhow::Error),
 }
 
+impl Error {
+    pub fn coded(code: ErrorCode, message: impl Into<String>) -> Self {
+        Error::Coded { code, message: message.into(), details: Vec::new() }
+    }
+
+    /// Attaches a field-level detail; other variants become `Coded` with their own code.
+    pub fn with_detail(self, field: impl Into<String>, message: impl Into<String>) -> Self {
+        let detail = FieldError { field: field.into(), message: message.into() };
+        match self {
+            Error::Coded { code, message, mut details } => {
+                details.push(detail);
+                Error::Coded { code, message, details }
+            }
+            other => Error::Coded { code: other.code(), message: other.message(), details: vec![detail] },
+        }
+    }
+
+    /// A failed call to an RPC upstream or Bitcoin node, told apart by whether it timed out.
+    pub fn upstream(message: impl Into<String>, timed_out: bool) -> Self {
+        let code = if timed_out { ErrorCode::UpstreamTimeout } else { ErrorCode::UpstreamError };
+        Error::coded(code, message)
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Error::BadRequest(_) => ErrorCode::BadRequest,
+            Error::Unauthorized(_) => ErrorCode::Unauthorized,
+            Error::Forbidden(_) => ErrorCode::Forbidden,
+            Error::NotFound(_) => ErrorCode::NotFound,
+            Error::Conflict(_) => ErrorCode::Conflict,
+            Error::QuotaExceeded(_) => ErrorCode::QuotaExceeded,
+            Error::BadGateway(_) => ErrorCode::UpstreamError,
+            Error::Coded { code, .. } => *code,
+            Error::Internal(_) | Error::Anyhow(_) => ErrorCode::Internal,
+        }
+    }
+
+    /// The message without the category prefix, which the code already conveys.
+    fn message(&self) -> String {
+        match self {
+            Error::BadRequest(message)
+            | Error::Unauthorized(message)
+            | Error::Forbidden(message)
+            | Error::Conflict(message)
+            | Error::QuotaExceeded(message)
+            | Error::NotFound(message)
+            | Error::BadGateway(message)
+            | Error::Internal(message)
+            | Error::Coded { message, .. } => message.clone(),
+            Error::Anyhow(e) => e.to_string(),
+        }
+    }
+}
+
+/// Body of every error response: `{"error": {"code", "message", "details"}}`.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorEnvelope {
+    pub error: ErrorBody,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorBody {
+    pub code: ErrorCode,
+    pub message: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub details: Vec<FieldError>,
+}
+
 impl IntoResponse for Error {
     fn into_response(self) -> Response {
-        let status = match self {
-            Error::BadRequest(_) => StatusCode::BAD_REQUEST,
-            Error::NotFound(_) => StatusCode::NOT_FOUND,
-            Error::Internal(_) | Error::Anyhow(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        let code = self.code();
+        let message = self.message();
+        let details = match self {
+            Error::Coded { details, .. } => details,
+            _ => Vec::new(),
+        };
+        (code.status(), Json(ErrorEnvelope { error: ErrorBody { code, message, details } })).into_response()
+    }
+}
+
+impl From<JsonRejection> for Error {
+    fn from(rejection: JsonRejection) -> Self {
+        let code = match rejection.status() {
+            StatusCode::PAYLOAD_TOO_LARGE => ErrorCode::PayloadTooLarge,
+            _ => ErrorCode::InvalidJson,
         };
+        Error::coded(code, rejection.body_text())
+    }
+}
+
+/// Fallback for paths no route matches.
+pub async fn not_found() -> Error {
+    Error::NotFound("No such route".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn errors_render_as_envelopes_with_stable_codes() {
+        let error = Error::coded(ErrorCode::InvalidPath, "Invalid file path").with_detail("files[1][0]", "must match /src/**.rs");
+        let response = error.into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
 
-        (status, self.to_string()).into_response()
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"]["code"], "INVALID_PATH");
+        assert_eq!(body["error"]["details"][0]["field"], "files[1][0]");
+
+        let response = Error::QuotaExceeded("daily limit".to_string()).into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
     }
-}
\ No newline at end of file
+}
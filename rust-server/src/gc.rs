@@ -0,0 +1,199 @@
+//! Background cleanup of finished builds: their project directories, stored
+//! artifacts and tracker entries, plus the size budget of the shared
+//! `programs/target` cache.
+
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, SystemTime},
+};
+
+use serde::Serialize;
+use tracing::{error, info, warn};
+
+use crate::{
+    auth::ANONYMOUS,
+    build_tracker::BuildTracker,
+    config::GcConfig,
+    program::Programs,
+};
+
+#[derive(Debug, Default, Serialize)]
+pub struct GcReport {
+    pub builds_removed: usize,
+    pub bytes_freed: u64,
+    pub tracker_entries_evicted: usize,
+    pub target_bytes_freed: u64,
+}
+
+struct Candidate {
+    uuid: String,
+    owner: String,
+    modified: SystemTime,
+    bytes: u64,
+}
+
+/// Runs `collect` every `config.interval_secs`, unless that is 0.
+pub fn spawn(tracker: BuildTracker, programs: Programs, config: GcConfig) {
+    if config.interval_secs == 0 {
+        info!("Build GC disabled");
+        return;
+    }
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(config.interval_secs));
+        loop {
+            interval.tick().await;
+            match collect(&tracker, &programs, &config).await {
+                Ok(report) => info!(?report, "Build GC finished"),
+                Err(e) => error!("Build GC failed: {}", e),
+            }
+        }
+    });
+}
+
+/// Builds still building or deploying.
+async fn busy_builds(tracker: &BuildTracker) -> HashSet<String> {
+    tracker
+        .list_builds()
+        .await
+        .into_iter()
+        .filter(|info| info.is_busy())
+        .map(|info| info.uuid)
+        .collect()
+}
+
+pub async fn collect(tracker: &BuildTracker, programs: &Programs, config: &GcConfig) -> anyhow::Result<GcReport> {
+    let mut report = GcReport::default();
+    let busy = busy_builds(tracker).await;
+
+    let measure = config.max_total_bytes.is_some();
+    let scan = programs.clone();
+    let mut candidates = tokio::task::spawn_blocking(move || {
+        scan.build_dirs().map(|dirs| {
+            dirs.into_iter()
+                .filter(|(uuid, _)| !busy.contains(uuid))
+                .map(|(uuid, modified)| Candidate {
+                    owner: scan.local_owner(&uuid).unwrap_or_else(|| ANONYMOUS.to_string()),
+                    bytes: if measure { scan.build_dir_size(&uuid) } else { 0 },
+                    uuid,
+                    modified,
+                })
+                .collect::<Vec<_>>()
+        })
+    })
+    .await??;
+
+    // Stored artifacts outlive their local directory when the container is replaced
+    match programs.stored_only_builds().await {
+        Ok(stored) => candidates.extend(stored.into_iter().map(|build| Candidate {
+            owner: build.owner.unwrap_or_else(|| ANONYMOUS.to_string()),
+            uuid: build.uuid,
+            modified: build.built,
+            bytes: build.bytes,
+        })),
+        Err(e) => warn!("Failed to list stored artifacts, only collecting local builds: {}", e),
+    }
+
+    let sizes: HashMap<_, _> = candidates.iter().map(|c| (c.uuid.clone(), c.bytes)).collect();
+    for uuid in select(candidates, config, SystemTime::now()) {
+        // A rebuild or deployment of this uuid may have started since the scan
+        match tracker.purge_idle(&uuid, programs.purge(&uuid)).await {
+            Some(Ok(())) => {
+                report.builds_removed += 1;
+                report.bytes_freed += sizes.get(&uuid).copied().unwrap_or(0);
+            }
+            Some(Err(e)) => warn!("Failed to remove build {}: {}", uuid, e),
+            None => {}
+        }
+    }
+
+    let cutoff = chrono::Utc::now() - chrono::Duration::hours(config.retention_hours as i64);
+    report.tracker_entries_evicted = tracker.evict_finished(cutoff).await;
+
+    if let Some(max_bytes) = config.target_max_bytes {
+        // Pruning under a running build or warmup would corrupt its incremental
+        // state, so neither may start until the prune is done
+        if let Some(target) = programs.try_lock_target() {
+            let programs = programs.clone();
+            report.target_bytes_freed = tokio::task::spawn_blocking(move || {
+                let _target = target;
+                programs.prune_target(max_bytes)
+            })
+            .await??;
+        } else {
+            info!("Builds or warmups running, skipping shared target dir pruning");
+        }
+    }
+
+    Ok(report)
+}
+
+/// Picks the builds to remove: expired ones, then each owner's oldest beyond
+/// the per-owner cap, then the oldest overall until the rest fit the size budget.
+fn select(mut candidates: Vec<Candidate>, config: &GcConfig, now: SystemTime) -> Vec<String> {
+    let retention = Duration::from_secs(config.retention_hours * 3600);
+    candidates.sort_by_key(|c| std::cmp::Reverse(c.modified));
+
+    let mut per_owner: HashMap<&str, usize> = HashMap::new();
+    let mut remove = Vec::new();
+    let mut kept = Vec::new();
+    for candidate in &candidates {
+        let expired = now.duration_since(candidate.modified).is_ok_and(|age| age > retention);
+        let owned = per_owner.entry(&candidate.owner).or_default();
+        *owned += 1;
+        let over_cap = config.max_builds_per_owner.is_some_and(|cap| *owned > cap);
+        if expired || over_cap {
+            remove.push(candidate.uuid.clone());
+        } else {
+            kept.push(candidate);
+        }
+    }
+
+    if let Some(max_bytes) = config.max_total_bytes {
+        let mut total: u64 = kept.iter().map(|c| c.bytes).sum();
+        // `kept` is newest first
+        while total > max_bytes {
+            let Some(oldest) = kept.pop() else { break };
+            total -= oldest.bytes;
+            remove.push(oldest.uuid.clone());
+        }
+    }
+    remove
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(uuid: &str, owner: &str, age_hours: u64, bytes: u64, now: SystemTime) -> Candidate {
+        Candidate {
+            uuid: uuid.to_string(),
+            owner: owner.to_string(),
+            modified: now - Duration::from_secs(age_hours * 3600),
+            bytes,
+        }
+    }
+
+    #[test]
+    fn selects_expired_then_over_cap_then_over_budget() {
+        let now = SystemTime::now();
+        let config = GcConfig {
+            interval_secs: 3600,
+            retention_hours: 48,
+            max_total_bytes: Some(250),
+            max_builds_per_owner: Some(2),
+            target_max_bytes: None,
+        };
+        let candidates = vec![
+            candidate("expired", "a", 72, 10, now),
+            candidate("a-new", "a", 1, 100, now),
+            candidate("a-mid", "a", 2, 100, now),
+            candidate("a-old", "a", 3, 100, now),
+            candidate("b-new", "b", 4, 100, now),
+        ];
+
+        let mut removed = select(candidates, &config, now);
+        removed.sort();
+        // a-old exceeds a's cap; then b-new, the oldest left, goes to fit 250 bytes
+        assert_eq!(removed, ["a-old", "b-new", "expired"]);
+    }
+}
@@ -0,0 +1,766 @@
+//! Borsh codec driven by an `ArchIdl`, mirroring `frontend/src/types/idl.ts`.
+//!
+//! Instruction data is the instruction's index in the IDL as a `u8`, followed
+//! by its Borsh-encoded args, which is how a Borsh instruction enum is laid
+//! out. Account data is the Borsh encoding of the account's fields.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Deepest nesting of defined types, guarding against self-referential IDLs.
+const MAX_DEPTH: usize = 64;
+
+/// Most elements decoded from a single buffer, and the longest fixed array
+/// an IDL may declare, guarding against bogus lengths.
+const MAX_ELEMENTS: usize = 1 << 20;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ArchIdl {
+    pub version: String,
+    pub name: String,
+    pub instructions: Vec<IdlInstruction>,
+    #[serde(default)]
+    pub accounts: Vec<IdlTypeDefinition>,
+    #[serde(default)]
+    pub types: Vec<IdlTypeDefinition>,
+    #[serde(default)]
+    pub errors: Vec<IdlErrorCode>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct IdlInstruction {
+    pub name: String,
+    #[serde(default)]
+    pub accounts: Vec<IdlInstructionAccount>,
+    #[serde(default)]
+    pub args: Vec<IdlField>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IdlInstructionAccount {
+    pub name: String,
+    pub is_mut: bool,
+    pub is_signer: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct IdlField {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: IdlType,
+}
+
+/// `ArchAccountType` and `ArchTypeDefinition`, which share a shape.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct IdlTypeDefinition {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: IdlTypeDefinitionBody,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum IdlTypeDefinitionBody {
+    Struct {
+        #[serde(default)]
+        fields: Vec<IdlField>,
+    },
+    Enum {
+        #[serde(default)]
+        variants: Vec<IdlEnumVariant>,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct IdlEnumVariant {
+    pub name: String,
+    #[serde(default)]
+    pub fields: Vec<IdlField>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct IdlErrorCode {
+    pub code: u32,
+    pub name: String,
+    pub msg: String,
+}
+
+/// `string | ComplexType`
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum IdlType {
+    Name(String),
+    Complex(Box<ComplexType>),
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ComplexType {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub option: Option<IdlType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tuple: Option<Vec<IdlType>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vec: Option<IdlType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub defined: Option<String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum IdlError {
+    #[error("Unknown instruction '{0}'")]
+    UnknownInstruction(String),
+    #[error("Unknown account '{0}'")]
+    UnknownAccount(String),
+    #[error("Instruction index {0} is out of range")]
+    UnknownInstructionIndex(u8),
+    #[error("Invalid type at {path}: {message}")]
+    InvalidType { path: String, message: String },
+    #[error("Invalid value at {path}: {message}")]
+    InvalidValue { path: String, message: String },
+    #[error("Unexpected end of data at {path}")]
+    UnexpectedEnd { path: String },
+}
+
+/// An IDL type resolved to the shapes Borsh knows about.
+#[derive(Debug, Clone, PartialEq)]
+enum Ty {
+    Bool,
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    I32,
+    U64,
+    I64,
+    U128,
+    I128,
+    F32,
+    F64,
+    String,
+    PublicKey,
+    Bytes,
+    Option(Box<Ty>),
+    Vec(Box<Ty>),
+    Array(Box<Ty>, usize),
+    Tuple(Vec<Ty>),
+    Defined(String),
+}
+
+impl Ty {
+    fn resolve(ty: &IdlType, path: &str) -> Result<Self, IdlError> {
+        let invalid = |message: &str| IdlError::InvalidType { path: path.to_string(), message: message.to_string() };
+        match ty {
+            IdlType::Name(name) => Self::parse(name).ok_or_else(|| invalid(&format!("cannot parse '{}'", name))),
+            IdlType::Complex(complex) => match &**complex {
+                ComplexType { option: Some(inner), .. } => Ok(Ty::Option(Box::new(Self::resolve(inner, path)?))),
+                ComplexType { vec: Some(inner), .. } => Ok(match Self::resolve(inner, path)? {
+                    Ty::U8 => Ty::Bytes,
+                    inner => Ty::Vec(Box::new(inner)),
+                }),
+                ComplexType { tuple: Some(items), .. } => {
+                    Ok(Ty::Tuple(items.iter().map(|item| Self::resolve(item, path)).collect::<Result<_, _>>()?))
+                }
+                ComplexType { defined: Some(name), .. } => Ok(Ty::Defined(name.clone())),
+                ComplexType { name: Some(name), .. } => {
+                    Self::parse(name).ok_or_else(|| invalid(&format!("cannot parse '{}'", name)))
+                }
+                _ => Err(invalid("empty type")),
+            },
+        }
+    }
+
+    /// Parses type strings, including Rust spellings such as `Vec<u8>`,
+    /// `Option<Pubkey>` and `[u8; 32]`. Unknown names refer to defined types.
+    /// Arrays longer than `MAX_ELEMENTS` are rejected.
+    fn parse(name: &str) -> Option<Self> {
+        let name = name.trim();
+        let generic = |prefix: &str| name.strip_prefix(prefix).and_then(|rest| rest.strip_suffix('>'));
+
+        if let Some(inner) = generic("Vec<") {
+            return Some(match Self::parse(inner)? {
+                Ty::U8 => Ty::Bytes,
+                inner => Ty::Vec(Box::new(inner)),
+            });
+        }
+        if let Some(inner) = generic("Option<") {
+            return Some(Ty::Option(Box::new(Self::parse(inner)?)));
+        }
+        if let Some(inner) = name.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+            let (inner, len) = inner.rsplit_once(';')?;
+            let len = len.trim().parse().ok().filter(|&len| len <= MAX_ELEMENTS)?;
+            return Some(Ty::Array(Box::new(Self::parse(inner)?), len));
+        }
+        if let Some(inner) = name.strip_prefix('(').and_then(|rest| rest.strip_suffix(')')) {
+            if inner.trim().is_empty() {
+                return Some(Ty::Tuple(Vec::new()));
+            }
+            // Nested generics are not split correctly here; use a ComplexType tuple for those
+            return Some(Ty::Tuple(inner.split(',').map(Self::parse).collect::<Option<_>>()?));
+        }
+
+        Some(match name {
+            "bool" => Ty::Bool,
+            "u8" => Ty::U8,
+            "i8" => Ty::I8,
+            "u16" => Ty::U16,
+            "i16" => Ty::I16,
+            "u32" => Ty::U32,
+            "i32" => Ty::I32,
+            "u64" => Ty::U64,
+            "i64" => Ty::I64,
+            "u128" => Ty::U128,
+            "i128" => Ty::I128,
+            "f32" => Ty::F32,
+            "f64" => Ty::F64,
+            "string" | "String" => Ty::String,
+            "publicKey" | "pubkey" | "Pubkey" => Ty::PublicKey,
+            "bytes" => Ty::Bytes,
+            "" => return None,
+            defined if defined.chars().all(|c| c.is_alphanumeric() || c == '_' || c == ':') => {
+                Ty::Defined(defined.rsplit("::").next().unwrap_or(defined).to_string())
+            }
+            _ => return None,
+        })
+    }
+}
+
+fn invalid_value(path: &str, message: impl Into<String>) -> IdlError {
+    IdlError::InvalidValue { path: path.to_string(), message: message.into() }
+}
+
+fn parse_u128(value: &Value) -> Option<u128> {
+    match value {
+        Value::Number(n) => n.as_u64().map(u128::from),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn parse_i128(value: &Value) -> Option<i128> {
+    match value {
+        Value::Number(n) => n.as_i64().map(i128::from).or_else(|| n.as_u64().map(i128::from)),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn parse_pubkey(value: &Value) -> Option<[u8; 32]> {
+    let s = value.as_str()?.trim();
+    let bytes = match hex::decode(s.trim_start_matches("0x")) {
+        Ok(bytes) => bytes,
+        Err(_) => bitcoin::base58::decode(s).ok()?,
+    };
+    bytes.try_into().ok()
+}
+
+fn parse_bytes(value: &Value) -> Option<Vec<u8>> {
+    match value {
+        Value::String(s) => hex::decode(s.trim().trim_start_matches("0x")).ok(),
+        Value::Array(items) => items.iter().map(|v| v.as_u64().and_then(|b| u8::try_from(b).ok())).collect(),
+        _ => None,
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    elements: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize, path: &str) -> Result<&'a [u8], IdlError> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.data.len())
+            .ok_or_else(|| IdlError::UnexpectedEnd { path: path.to_string() })?;
+        let slice = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn array<const N: usize>(&mut self, path: &str) -> Result<[u8; N], IdlError> {
+        Ok(self.take(N, path)?.try_into().expect("take returns N bytes"))
+    }
+
+    fn len(&mut self, path: &str) -> Result<usize, IdlError> {
+        let len = u32::from_le_bytes(self.array(path)?) as usize;
+        self.count(len, path)?;
+        Ok(len)
+    }
+
+    /// Counts `len` elements about to be decoded against `MAX_ELEMENTS`.
+    /// Elements may take no bytes, so the data length alone does not bound them.
+    fn count(&mut self, len: usize, path: &str) -> Result<(), IdlError> {
+        self.elements = self.elements.saturating_add(len);
+        if self.elements > MAX_ELEMENTS {
+            return Err(invalid_value(path, format!("more than {} elements", MAX_ELEMENTS)));
+        }
+        Ok(())
+    }
+}
+
+/// Encodes and decodes values of the types an IDL defines.
+pub struct Codec<'a> {
+    idl: &'a ArchIdl,
+}
+
+impl<'a> Codec<'a> {
+    pub fn new(idl: &'a ArchIdl) -> Self {
+        Self { idl }
+    }
+
+    fn definition(&self, name: &str, path: &str) -> Result<&'a IdlTypeDefinition, IdlError> {
+        self.idl
+            .types
+            .iter()
+            .chain(&self.idl.accounts)
+            .find(|def| def.name == name)
+            .ok_or_else(|| IdlError::InvalidType { path: path.to_string(), message: format!("unknown type '{}'", name) })
+    }
+
+    fn instruction(&self, name: &str) -> Result<(usize, &'a IdlInstruction), IdlError> {
+        self.idl
+            .instructions
+            .iter()
+            .enumerate()
+            .find(|(_, ix)| ix.name == name)
+            .ok_or_else(|| IdlError::UnknownInstruction(name.to_string()))
+    }
+
+    /// Encodes `args` for the named instruction, given either as an object
+    /// keyed by arg name or as an array in declaration order.
+    pub fn encode_instruction(&self, name: &str, args: &Value, discriminator: bool) -> Result<Vec<u8>, IdlError> {
+        let (index, instruction) = self.instruction(name)?;
+        let mut out = Vec::new();
+        if discriminator {
+            out.push(u8::try_from(index).map_err(|_| invalid_value(name, "instruction index does not fit a u8"))?);
+        }
+        self.encode_fields(&instruction.args, args, &mut out, name, 0)?;
+        Ok(out)
+    }
+
+    /// Decodes instruction data. With a discriminator the instruction is
+    /// picked by the leading index byte, and `name` only has to agree with it.
+    pub fn decode_instruction(
+        &self,
+        name: Option<&str>,
+        data: &[u8],
+        discriminator: bool,
+    ) -> Result<(&'a str, Value, usize), IdlError> {
+        let mut reader = Reader { data, pos: 0, elements: 0 };
+        let by_index = if discriminator {
+            let [index] = reader.array("discriminator")?;
+            Some(self.idl.instructions.get(index as usize).ok_or(IdlError::UnknownInstructionIndex(index))?)
+        } else {
+            None
+        };
+        let instruction = match (name, by_index) {
+            (Some(name), Some(instruction)) if instruction.name != name => {
+                return Err(invalid_value("discriminator", format!("data is for instruction '{}'", instruction.name)))
+            }
+            (_, Some(instruction)) => instruction,
+            (Some(name), None) => self.instruction(name)?.1,
+            (None, None) => {
+                return Err(invalid_value("instruction", "a name is required when the data has no discriminator"))
+            }
+        };
+        let value = self.decode_fields(&instruction.args, &mut reader, &instruction.name, 0)?;
+        Ok((&instruction.name, value, data.len() - reader.pos))
+    }
+
+    /// Decodes account data, returning the value and the number of unread trailing bytes.
+    pub fn decode_account(&self, name: &str, data: &[u8]) -> Result<(Value, usize), IdlError> {
+        let account = self
+            .idl
+            .accounts
+            .iter()
+            .chain(&self.idl.types)
+            .find(|def| def.name == name)
+            .ok_or_else(|| IdlError::UnknownAccount(name.to_string()))?;
+        let mut reader = Reader { data, pos: 0, elements: 0 };
+        let value = self.decode_definition(account, &mut reader, name, 0)?;
+        Ok((value, data.len() - reader.pos))
+    }
+
+    fn encode_fields(
+        &self,
+        fields: &[IdlField],
+        value: &Value,
+        out: &mut Vec<u8>,
+        path: &str,
+        depth: usize,
+    ) -> Result<(), IdlError> {
+        for (i, field) in fields.iter().enumerate() {
+            let path = format!("{}.{}", path, field.name);
+            let field_value = match value {
+                Value::Object(map) => map.get(&field.name),
+                Value::Array(items) => items.get(i),
+                _ => return Err(invalid_value(&path, "expected an object or array of fields")),
+            }
+            .ok_or_else(|| invalid_value(&path, "missing field"))?;
+            let ty = Ty::resolve(&field.ty, &path)?;
+            self.encode(&ty, field_value, out, &path, depth)?;
+        }
+        Ok(())
+    }
+
+    fn decode_fields(
+        &self,
+        fields: &[IdlField],
+        reader: &mut Reader,
+        path: &str,
+        depth: usize,
+    ) -> Result<Value, IdlError> {
+        let mut map = Map::new();
+        for field in fields {
+            let path = format!("{}.{}", path, field.name);
+            let ty = Ty::resolve(&field.ty, &path)?;
+            map.insert(field.name.clone(), self.decode(&ty, reader, &path, depth)?);
+        }
+        Ok(Value::Object(map))
+    }
+
+    fn encode(&self, ty: &Ty, value: &Value, out: &mut Vec<u8>, path: &str, depth: usize) -> Result<(), IdlError> {
+        macro_rules! int {
+            ($t:ty, $parse:ident) => {{
+                let n = $parse(value)
+                    .and_then(|n| <$t>::try_from(n).ok())
+                    .ok_or_else(|| invalid_value(path, concat!("expected a ", stringify!($t))))?;
+                out.extend(n.to_le_bytes());
+            }};
+        }
+
+        match ty {
+            Ty::Bool => out.push(value.as_bool().ok_or_else(|| invalid_value(path, "expected a bool"))? as u8),
+            Ty::U8 => int!(u8, parse_u128),
+            Ty::I8 => int!(i8, parse_i128),
+            Ty::U16 => int!(u16, parse_u128),
+            Ty::I16 => int!(i16, parse_i128),
+            Ty::U32 => int!(u32, parse_u128),
+            Ty::I32 => int!(i32, parse_i128),
+            Ty::U64 => int!(u64, parse_u128),
+            Ty::I64 => int!(i64, parse_i128),
+            Ty::U128 => int!(u128, parse_u128),
+            Ty::I128 => int!(i128, parse_i128),
+            Ty::F32 => {
+                let n = value.as_f64().ok_or_else(|| invalid_value(path, "expected a number"))?;
+                out.extend((n as f32).to_le_bytes());
+            }
+            Ty::F64 => {
+                let n = value.as_f64().ok_or_else(|| invalid_value(path, "expected a number"))?;
+                out.extend(n.to_le_bytes());
+            }
+            Ty::String => {
+                let s = value.as_str().ok_or_else(|| invalid_value(path, "expected a string"))?;
+                out.extend((s.len() as u32).to_le_bytes());
+                out.extend(s.as_bytes());
+            }
+            Ty::PublicKey => {
+                let key = parse_pubkey(value)
+                    .ok_or_else(|| invalid_value(path, "expected a 32-byte public key as hex or base58"))?;
+                out.extend(key);
+            }
+            Ty::Bytes => {
+                let bytes = parse_bytes(value)
+                    .ok_or_else(|| invalid_value(path, "expected bytes as a hex string or array"))?;
+                out.extend((bytes.len() as u32).to_le_bytes());
+                out.extend(bytes);
+            }
+            Ty::Option(inner) => match value {
+                Value::Null => out.push(0),
+                value => {
+                    out.push(1);
+                    self.encode(inner, value, out, path, depth)?;
+                }
+            },
+            Ty::Vec(inner) => {
+                let items = value.as_array().ok_or_else(|| invalid_value(path, "expected an array"))?;
+                out.extend((items.len() as u32).to_le_bytes());
+                for (i, item) in items.iter().enumerate() {
+                    self.encode(inner, item, out, &format!("{}[{}]", path, i), depth)?;
+                }
+            }
+            Ty::Array(inner, len) => {
+                // Fixed byte arrays are commonly written as hex
+                if let (Ty::U8, Some(bytes)) = (&**inner, value.as_str().and_then(|_| parse_bytes(value))) {
+                    if bytes.len() != *len {
+                        return Err(invalid_value(path, format!("expected {} bytes", len)));
+                    }
+                    out.extend(bytes);
+                    return Ok(());
+                }
+                let items = value
+                    .as_array()
+                    .filter(|items| items.len() == *len)
+                    .ok_or_else(|| invalid_value(path, format!("expected an array of {} items", len)))?;
+                for (i, item) in items.iter().enumerate() {
+                    self.encode(inner, item, out, &format!("{}[{}]", path, i), depth)?;
+                }
+            }
+            Ty::Tuple(items) => {
+                let values = value
+                    .as_array()
+                    .filter(|values| values.len() == items.len())
+                    .ok_or_else(|| invalid_value(path, format!("expected an array of {} items", items.len())))?;
+                for (i, (item, value)) in items.iter().zip(values).enumerate() {
+                    self.encode(item, value, out, &format!("{}.{}", path, i), depth)?;
+                }
+            }
+            Ty::Defined(name) => {
+                if depth >= MAX_DEPTH {
+                    return Err(invalid_value(path, "types are nested too deeply"));
+                }
+                match &self.definition(name, path)?.ty {
+                    IdlTypeDefinitionBody::Struct { fields } => {
+                        self.encode_fields(fields, value, out, path, depth + 1)?
+                    }
+                    IdlTypeDefinitionBody::Enum { variants } => {
+                        // Unit variants may be written as a bare string
+                        let (variant_name, fields_value) = match value {
+                            Value::String(name) => (name.as_str(), &Value::Null),
+                            Value::Object(map) if map.len() == 1 => {
+                                let (name, fields) = map.iter().next().expect("map has one entry");
+                                (name.as_str(), fields)
+                            }
+                            _ => return Err(invalid_value(path, "expected a variant name or {\"Variant\": {...}}")),
+                        };
+                        let (index, variant) = variants
+                            .iter()
+                            .enumerate()
+                            .find(|(_, v)| v.name == variant_name)
+                            .ok_or_else(|| invalid_value(path, format!("unknown variant '{}'", variant_name)))?;
+                        out.push(u8::try_from(index).map_err(|_| invalid_value(path, "too many variants"))?);
+                        let path = format!("{}::{}", path, variant.name);
+                        self.encode_fields(&variant.fields, fields_value, out, &path, depth + 1)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn decode_definition(
+        &self,
+        def: &IdlTypeDefinition,
+        reader: &mut Reader,
+        path: &str,
+        depth: usize,
+    ) -> Result<Value, IdlError> {
+        if depth >= MAX_DEPTH {
+            return Err(invalid_value(path, "types are nested too deeply"));
+        }
+        match &def.ty {
+            IdlTypeDefinitionBody::Struct { fields } => self.decode_fields(fields, reader, path, depth + 1),
+            IdlTypeDefinitionBody::Enum { variants } => {
+                let [index] = reader.array(path)?;
+                let variant = variants
+                    .get(index as usize)
+                    .ok_or_else(|| invalid_value(path, format!("variant index {} is out of range", index)))?;
+                if variant.fields.is_empty() {
+                    return Ok(Value::String(variant.name.clone()));
+                }
+                let path = format!("{}::{}", path, variant.name);
+                let fields = self.decode_fields(&variant.fields, reader, &path, depth + 1)?;
+                Ok(Value::Object(Map::from_iter([(variant.name.clone(), fields)])))
+            }
+        }
+    }
+
+    fn decode(&self, ty: &Ty, reader: &mut Reader, path: &str, depth: usize) -> Result<Value, IdlError> {
+        macro_rules! int {
+            ($t:ty) => {{
+                let n = <$t>::from_le_bytes(reader.array(path)?);
+                Value::from(n)
+            }};
+            // 64-bit and wider values do not survive a JavaScript number, so they are strings
+            ($t:ty, string) => {{
+                let n = <$t>::from_le_bytes(reader.array(path)?);
+                Value::String(n.to_string())
+            }};
+        }
+
+        Ok(match ty {
+            Ty::Bool => match reader.array(path)? {
+                [0] => Value::Bool(false),
+                [1] => Value::Bool(true),
+                [b] => return Err(invalid_value(path, format!("invalid bool byte {}", b))),
+            },
+            Ty::U8 => int!(u8),
+            Ty::I8 => int!(i8),
+            Ty::U16 => int!(u16),
+            Ty::I16 => int!(i16),
+            Ty::U32 => int!(u32),
+            Ty::I32 => int!(i32),
+            Ty::U64 => int!(u64, string),
+            Ty::I64 => int!(i64, string),
+            Ty::U128 => int!(u128, string),
+            Ty::I128 => int!(i128, string),
+            Ty::F32 => Value::from(f32::from_le_bytes(reader.array(path)?) as f64),
+            Ty::F64 => Value::from(f64::from_le_bytes(reader.array(path)?)),
+            Ty::String => {
+                let len = reader.len(path)?;
+                let bytes = reader.take(len, path)?;
+                Value::String(
+                    String::from_utf8(bytes.to_vec()).map_err(|_| invalid_value(path, "string is not UTF-8"))?,
+                )
+            }
+            Ty::PublicKey => Value::String(hex::encode(reader.take(32, path)?)),
+            Ty::Bytes => {
+                let len = reader.len(path)?;
+                Value::String(hex::encode(reader.take(len, path)?))
+            }
+            Ty::Option(inner) => match reader.array(path)? {
+                [0] => Value::Null,
+                [1] => self.decode(inner, reader, path, depth)?,
+                [b] => return Err(invalid_value(path, format!("invalid option tag {}", b))),
+            },
+            Ty::Vec(inner) => {
+                let len = reader.len(path)?;
+                let items = (0..len)
+                    .map(|i| self.decode(inner, reader, &format!("{}[{}]", path, i), depth))
+                    .collect::<Result<_, _>>()?;
+                Value::Array(items)
+            }
+            Ty::Array(inner, len) => {
+                reader.count(*len, path)?;
+                let items = (0..*len)
+                    .map(|i| self.decode(inner, reader, &format!("{}[{}]", path, i), depth))
+                    .collect::<Result<_, _>>()?;
+                Value::Array(items)
+            }
+            Ty::Tuple(items) => Value::Array(
+                items
+                    .iter()
+                    .enumerate()
+                    .map(|(i, item)| self.decode(item, reader, &format!("{}.{}", path, i), depth))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Ty::Defined(name) => self.decode_definition(self.definition(name, path)?, reader, path, depth)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn idl() -> ArchIdl {
+        serde_json::from_value(json!({
+            "version": "0.1.0",
+            "name": "counter",
+            "instructions": [
+                { "name": "initialize", "accounts": [], "args": [] },
+                {
+                    "name": "configure",
+                    "accounts": [{ "name": "counter", "isMut": true, "isSigner": false }],
+                    "args": [
+                        { "name": "step", "type": "u64" },
+                        { "name": "label", "type": { "option": { "name": "string" } } },
+                        { "name": "owners", "type": { "vec": "publicKey" } },
+                        { "name": "mode", "type": { "defined": "Mode" } },
+                        { "name": "limits", "type": { "tuple": ["u8", { "defined": "Limit" }] } }
+                    ]
+                }
+            ],
+            "accounts": [{
+                "name": "Counter",
+                "type": { "kind": "struct", "fields": [
+                    { "name": "count", "type": "u64" },
+                    { "name": "authority", "type": "[u8; 32]" },
+                    { "name": "history", "type": "Vec<u16>" }
+                ]}
+            }],
+            "types": [
+                { "name": "Mode", "type": { "kind": "enum", "variants": [
+                    { "name": "Off" },
+                    { "name": "Every", "fields": [{ "name": "blocks", "type": "u32" }] }
+                ]}},
+                { "name": "Limit", "type": { "kind": "struct", "fields": [{ "name": "max", "type": "i16" }] } }
+            ],
+            "errors": []
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn instruction_round_trips_with_complex_types() {
+        let idl = idl();
+        let codec = Codec::new(&idl);
+        let owner = "11".repeat(32);
+        let args = json!({
+            "step": "5",
+            "label": "hello",
+            "owners": [owner],
+            "mode": { "Every": { "blocks": 7 } },
+            "limits": [3, { "max": -2 }]
+        });
+
+        let data = codec.encode_instruction("configure", &args, true).unwrap();
+        let mut expected = vec![1u8];
+        expected.extend(5u64.to_le_bytes());
+        expected.extend([1, 5, 0, 0, 0]);
+        expected.extend(b"hello");
+        expected.extend(1u32.to_le_bytes());
+        expected.extend([0x11; 32]);
+        expected.extend([1, 7, 0, 0, 0]);
+        expected.extend([3]);
+        expected.extend((-2i16).to_le_bytes());
+        assert_eq!(data, expected);
+
+        let (name, decoded, remaining) = codec.decode_instruction(None, &data, true).unwrap();
+        assert_eq!(name, "configure");
+        assert_eq!(decoded, args);
+        assert_eq!(remaining, 0);
+    }
+
+    #[test]
+    fn account_decodes_rust_type_strings_and_reports_trailing_bytes() {
+        let idl = idl();
+        let mut data = 9u64.to_le_bytes().to_vec();
+        data.extend([0xab; 32]);
+        data.extend(2u32.to_le_bytes());
+        data.extend([1, 0, 2, 0]);
+        data.extend([0; 3]);
+
+        let (value, remaining) = Codec::new(&idl).decode_account("Counter", &data).unwrap();
+        assert_eq!(value["count"], json!("9"));
+        assert_eq!(value["authority"].as_array().unwrap().len(), 32);
+        assert_eq!(value["history"], json!([1, 2]));
+        assert_eq!(remaining, 3);
+    }
+
+    #[test]
+    fn errors_point_at_the_offending_field() {
+        let idl = idl();
+        let codec = Codec::new(&idl);
+        let err = codec
+            .encode_instruction("configure", &json!({ "step": 1, "label": null, "owners": [], "mode": "Sometimes" }), true)
+            .unwrap_err();
+        assert!(err.to_string().contains("configure.mode"), "{}", err);
+
+        let err = codec.decode_instruction(Some("configure"), &[1, 2, 3], false).unwrap_err();
+        assert!(matches!(err, IdlError::UnexpectedEnd { .. }));
+    }
+
+    #[test]
+    fn fixed_arrays_of_empty_elements_are_capped() {
+        let mut idl = idl();
+        idl.instructions[0].args =
+            serde_json::from_value(json!([{ "name": "units", "type": "[(); 1000000000000]" }])).unwrap();
+        let err = Codec::new(&idl).decode_instruction(Some("initialize"), &[], false).unwrap_err();
+        assert!(matches!(err, IdlError::InvalidType { .. }), "{}", err);
+
+        // Each array is short enough, but together they exceed the cap
+        idl.instructions[0].args =
+            serde_json::from_value(json!([{ "name": "units", "type": "[[(); 1024]; 1025]" }])).unwrap();
+        let err = Codec::new(&idl).decode_instruction(Some("initialize"), &[], false).unwrap_err();
+        assert!(err.to_string().contains("elements"), "{}", err);
+    }
+}
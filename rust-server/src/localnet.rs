@@ -0,0 +1,331 @@
+use std::{
+    fs,
+    io::Write,
+    os::unix::fs::{OpenOptionsExt, PermissionsExt},
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::Arc,
+};
+
+use anyhow::{anyhow, Context};
+use bitcoin::{key::Keypair, secp256k1::Secp256k1};
+use serde::Serialize;
+use tokio::{
+    process::{Child, Command},
+    sync::Mutex,
+};
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+use crate::{config::LocalnetConfig, upstreams::UpstreamPool};
+
+/// Name under which the local validator is registered in the RPC proxy.
+pub const LOCALNET_NETWORK: &str = "localnet";
+
+/// Environment variable the validator reads the bitcoind RPC password from,
+/// the counterpart of its `--bitcoin-rpc-password` flag.
+const VALIDATOR_PASSWORD_ENV: &str = "BITCOIN_RPC_PASSWORD";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LocalnetState {
+    Stopped,
+    Running,
+    Exited,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ProcessStatus {
+    pub pid: Option<u32>,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub exit_status: Option<String>,
+    pub log_file: String,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct LocalnetStatus {
+    pub state: LocalnetState,
+    pub network: &'static str,
+    pub rpc_url: String,
+    pub fee_payer: String,
+    pub validator: Option<ProcessStatus>,
+    pub bitcoind: Option<ProcessStatus>,
+}
+
+struct ManagedProcess {
+    child: Child,
+    status: ProcessStatus,
+}
+
+impl ManagedProcess {
+    fn spawn(bin: &str, args: &[String], envs: &[(&str, &str)], log_path: &Path) -> anyhow::Result<Self> {
+        let log = fs::File::create(log_path)
+            .with_context(|| format!("Failed to create log file {}", log_path.display()))?;
+
+        let child = Command::new(bin)
+            .args(args)
+            .envs(envs.iter().copied())
+            .stdout(Stdio::from(log.try_clone()?))
+            .stderr(Stdio::from(log))
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("Failed to start {bin}"))?;
+
+        info!("Started {} (pid {:?}), logging to {}", bin, child.id(), log_path.display());
+
+        Ok(Self {
+            status: ProcessStatus {
+                pid: child.id(),
+                started_at: chrono::Utc::now(),
+                exit_status: None,
+                log_file: log_path.to_string_lossy().to_string(),
+            },
+            child,
+        })
+    }
+
+    /// Refreshes `exit_status` and reports whether the process is still alive.
+    fn poll(&mut self) -> bool {
+        match self.child.try_wait() {
+            Ok(None) => true,
+            Ok(Some(status)) => {
+                self.status.exit_status = Some(status.to_string());
+                false
+            }
+            Err(e) => {
+                self.status.exit_status = Some(e.to_string());
+                false
+            }
+        }
+    }
+
+    async fn stop(mut self) {
+        if let Err(e) = self.child.kill().await {
+            warn!("Failed to kill pid {:?}: {}", self.status.pid, e);
+        }
+    }
+}
+
+#[derive(Default)]
+struct Processes {
+    validator: Option<ManagedProcess>,
+    bitcoind: Option<ManagedProcess>,
+}
+
+/// Starts and stops a local Arch validator (and optionally a regtest bitcoind)
+/// as child processes, registering the validator as the `localnet` RPC network.
+#[derive(Clone)]
+pub struct Localnet {
+    config: Option<Arc<LocalnetConfig>>,
+    processes: Arc<Mutex<Processes>>,
+    upstreams: UpstreamPool,
+    fee_payer: Keypair,
+}
+
+impl Localnet {
+    pub fn new(config: Option<LocalnetConfig>, upstreams: UpstreamPool) -> anyhow::Result<Self> {
+        let secp = Secp256k1::new();
+        // Without a configured key, each server run funds a fresh fee payer from the faucet
+        let fee_payer = match config.as_ref().and_then(|c| c.fee_payer_key.as_ref()) {
+            Some(key) => Keypair::from_seckey_str(&secp, &key.0)
+                .context("LOCALNET_FEE_PAYER_KEY must be a hex secret key")?,
+            None => Keypair::new(&secp, &mut bitcoin::secp256k1::rand::thread_rng()),
+        };
+
+        Ok(Self {
+            config: config.map(Arc::new),
+            processes: Arc::new(Mutex::new(Processes::default())),
+            upstreams,
+            fee_payer,
+        })
+    }
+
+    /// Server-held key used to pay for deployments to the localnet.
+    pub fn fee_payer(&self) -> Keypair {
+        self.fee_payer
+    }
+
+    fn config(&self) -> anyhow::Result<&LocalnetConfig> {
+        self.config
+            .as_deref()
+            .ok_or_else(|| anyhow!("Localnet is disabled (set LOCALNET_ENABLED to enable it)"))
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.is_some()
+    }
+
+    fn rpc_url(config: &LocalnetConfig) -> String {
+        format!("http://127.0.0.1:{}", config.rpc_port)
+    }
+
+    pub async fn start(&self) -> anyhow::Result<LocalnetStatus> {
+        let config = self.config()?;
+        let mut processes = self.processes.lock().await;
+        self.start_locked(config, &mut processes).await
+    }
+
+    async fn start_locked(&self, config: &LocalnetConfig, processes: &mut Processes) -> anyhow::Result<LocalnetStatus> {
+        if processes.validator.as_mut().is_some_and(ManagedProcess::poll) {
+            info!("Localnet is already running");
+            return Ok(self.status_of(config, processes));
+        }
+        // Clear out anything left behind by a crashed run
+        Self::stop_processes(processes).await;
+
+        let data_dir = PathBuf::from(&config.data_dir);
+        fs::create_dir_all(data_dir.join("validator"))?;
+
+        let mut validator_args = vec![
+            "--data-dir".to_string(),
+            data_dir.join("validator").to_string_lossy().to_string(),
+            "--rpc-bind-ip".to_string(),
+            "127.0.0.1".to_string(),
+            "--rpc-bind-port".to_string(),
+            config.rpc_port.to_string(),
+        ];
+
+        if let Some(bitcoind) = &config.bitcoind {
+            let bitcoind_dir = data_dir.join("bitcoind");
+            fs::create_dir_all(&bitcoind_dir)?;
+
+            // Credentials go in a file only this user can read; argv is visible to everyone
+            let conf = data_dir.join("bitcoin.conf");
+            write_private(
+                &conf,
+                &format!("rpcuser={}\nrpcpassword={}\n", bitcoind.rpc_username, bitcoind.rpc_password.0),
+            )
+            .with_context(|| format!("Failed to write {}", conf.display()))?;
+
+            let args = vec![
+                "-regtest".to_string(),
+                "-server".to_string(),
+                "-txindex".to_string(),
+                "-fallbackfee=0.0001".to_string(),
+                format!("-conf={}", fs::canonicalize(&conf)?.to_string_lossy()),
+                format!("-datadir={}", bitcoind_dir.to_string_lossy()),
+                format!("-rpcport={}", bitcoind.rpc_port),
+            ];
+            processes.bitcoind = Some(ManagedProcess::spawn(
+                &bitcoind.bin,
+                &args,
+                &[],
+                &data_dir.join("bitcoind.log"),
+            )?);
+
+            validator_args.extend([
+                "--bitcoin-network".to_string(),
+                "regtest".to_string(),
+                "--bitcoin-rpc-endpoint".to_string(),
+                "127.0.0.1".to_string(),
+                "--bitcoin-rpc-port".to_string(),
+                bitcoind.rpc_port.to_string(),
+                "--bitcoin-rpc-username".to_string(),
+                bitcoind.rpc_username.clone(),
+            ]);
+        }
+
+        // The validator takes the password from its environment, which only this user can read
+        let validator_env: Vec<_> = config
+            .bitcoind
+            .iter()
+            .map(|bitcoind| (VALIDATOR_PASSWORD_ENV, bitcoind.rpc_password.0.as_str()))
+            .collect();
+
+        validator_args.extend(config.validator_args.iter().cloned());
+
+        match ManagedProcess::spawn(
+            &config.validator_bin,
+            &validator_args,
+            &validator_env,
+            &data_dir.join("validator.log"),
+        ) {
+            Ok(validator) => processes.validator = Some(validator),
+            Err(e) => {
+                Self::stop_processes(processes).await;
+                return Err(e);
+            }
+        }
+
+        self.upstreams
+            .register(LOCALNET_NETWORK, vec![Self::rpc_url(config)])
+            .await;
+        info!("Localnet started, registered RPC network '{}'", LOCALNET_NETWORK);
+
+        Ok(self.status_of(config, processes))
+    }
+
+    pub async fn stop(&self) -> anyhow::Result<LocalnetStatus> {
+        let config = self.config()?;
+        let mut processes = self.processes.lock().await;
+        self.stop_locked(&mut processes).await;
+        Ok(self.status_of(config, &mut processes))
+    }
+
+    async fn stop_locked(&self, processes: &mut Processes) {
+        Self::stop_processes(processes).await;
+        self.upstreams.unregister(LOCALNET_NETWORK).await;
+        info!("Localnet stopped");
+    }
+
+    /// Stops the localnet, wipes its ledger and chain data and starts it again.
+    /// The process lock is held throughout so no `start` can race the wipe.
+    pub async fn reset(&self) -> anyhow::Result<LocalnetStatus> {
+        let config = self.config()?;
+        let mut processes = self.processes.lock().await;
+        self.stop_locked(&mut processes).await;
+
+        let data_dir = Path::new(&config.data_dir);
+        if data_dir.exists() {
+            fs::remove_dir_all(data_dir)
+                .with_context(|| format!("Failed to remove {}", data_dir.display()))?;
+        }
+        info!("Localnet data directory {} cleared", data_dir.display());
+
+        self.start_locked(config, &mut processes).await
+    }
+
+    pub async fn status(&self) -> anyhow::Result<LocalnetStatus> {
+        let config = self.config()?;
+        let mut processes = self.processes.lock().await;
+        Ok(self.status_of(config, &mut processes))
+    }
+
+    async fn stop_processes(processes: &mut Processes) {
+        // Stop the validator first so it does not observe bitcoind going away
+        if let Some(validator) = processes.validator.take() {
+            validator.stop().await;
+        }
+        if let Some(bitcoind) = processes.bitcoind.take() {
+            bitcoind.stop().await;
+        }
+    }
+
+    fn status_of(&self, config: &LocalnetConfig, processes: &mut Processes) -> LocalnetStatus {
+        let state = match processes.validator.as_mut().map(ManagedProcess::poll) {
+            None => LocalnetState::Stopped,
+            Some(true) => LocalnetState::Running,
+            Some(false) => LocalnetState::Exited,
+        };
+        if let Some(bitcoind) = processes.bitcoind.as_mut() {
+            bitcoind.poll();
+        }
+
+        LocalnetStatus {
+            state,
+            network: LOCALNET_NETWORK,
+            rpc_url: Self::rpc_url(config),
+            fee_payer: hex::encode(self.fee_payer.x_only_public_key().0.serialize()),
+            validator: processes.validator.as_ref().map(|p| p.status.clone()),
+            bitcoind: processes.bitcoind.as_ref().map(|p| p.status.clone()),
+        }
+    }
+}
+
+/// Writes `contents` to `path`, readable and writable only by this user.
+fn write_private(path: &Path, contents: &str) -> std::io::Result<()> {
+    let mut file = fs::OpenOptions::new().write(true).create(true).truncate(true).mode(0o600).open(path)?;
+    // `mode` only applies to new files
+    file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    file.write_all(contents.as_bytes())
+}
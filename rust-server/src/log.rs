@@ -1,7 +1,32 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
 use tracing::Level;
 use tracing_subscriber::{fmt, EnvFilter};
 
-pub fn init_logging(verbose: bool) {
+/// Output format of the log lines written to stdout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    Text,
+    /// One JSON object per line, with the fields of the enclosing spans
+    /// (`request_id`, `build_uuid`) attached
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            other => Err(format!("unknown log format '{other}'")),
+        }
+    }
+}
+
+pub fn init_logging(verbose: bool, format: LogFormat) {
     let env_filter = if verbose {
         EnvFilter::from_default_env().add_directive(Level::DEBUG.into())
     } else {
@@ -10,12 +35,16 @@ pub fn init_logging(verbose: bool) {
             .add_directive("tower_http=warn".parse().unwrap())
     };
 
-    fmt()
+    let builder = fmt()
         .with_env_filter(env_filter)
         .with_target(false)
         .with_thread_ids(true)
         .with_thread_names(true)
         .with_file(true)
-        .with_line_number(true)
-        .init();
-}
\ No newline at end of file
+        .with_line_number(true);
+
+    match format {
+        LogFormat::Text => builder.init(),
+        LogFormat::Json => builder.json().with_current_span(false).with_span_list(true).init(),
+    }
+}
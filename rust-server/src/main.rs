@@ -1,60 +1,189 @@
+mod arch;
+mod arch_rpc;
+mod auth;
+mod bip322;
 mod build_tracker;
+mod compat;
 mod config;
+mod deployer;
 mod error;
+mod extract;
+mod gc;
+mod idl;
+mod localnet;
 mod log;
+mod metrics;
 mod middlewares;
+mod openapi;
 mod program;
+mod reload;
 mod routes;
-// mod test_bip322;  // Commented out - missing dependencies (arch_sdk, bitcoin, etc.)
+mod rpc_policy;
+mod sdk;
+mod state;
+mod toolchain;
+mod upstreams;
+mod usage;
+#[cfg(test)]
+mod test_bip322;
 
-use std::net::{Ipv4Addr, SocketAddr};
+use std::{
+    env,
+    net::{Ipv4Addr, SocketAddr},
+    path::PathBuf,
+    process,
+    time::Duration,
+};
 
 use anyhow::Result;
-use axum::{
-    middleware,
-    routing::{get, post},
-    Router,
-};
+use axum::middleware;
 use tokio::net::TcpListener;
-use tracing::{info, error};
+use tracing::{info, error, warn};
 use socket2::{Socket, Domain, Type};
 
-use self::{build_tracker::BuildTracker, config::Config, log::init_logging, middlewares::*, routes::*};
+use self::{build_tracker::BuildTracker, config::{Config, SharedConfig}, log::init_logging, middlewares::*, program::Programs, state::AppState, toolchain::Toolchain};
+
+const USAGE: &str = "Usage: arch-server [--config <path>] [--print-config]
+       arch-server --update-lockfiles | --verify-lockfiles
+
+  --config <path>      TOML config file; defaults to $CONFIG_FILE. Environment
+                       variables override what it sets.
+  --print-config       Print the effective configuration and exit
+  --update-lockfiles   Resolve each SDK profile again and write its lockfile
+                       into the source tree under sdk/, then exit
+  --verify-lockfiles   Check the built-in SDK profile lockfiles resolve
+                       unchanged and fetch, then exit";
+
+/// Command line flags. Everything else is configured through the config file
+/// and environment.
+#[derive(Debug, Default, PartialEq)]
+struct Args {
+    config: Option<PathBuf>,
+    print_config: bool,
+    update_lockfiles: bool,
+    verify_lockfiles: bool,
+}
+
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Self::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--print-config" => parsed.print_config = true,
+                "--update-lockfiles" => parsed.update_lockfiles = true,
+                "--verify-lockfiles" => parsed.verify_lockfiles = true,
+                "--config" => {
+                    let path = args.next().ok_or("--config needs a path")?;
+                    parsed.config = Some(PathBuf::from(path));
+                }
+                "-h" | "--help" => {
+                    println!("{USAGE}");
+                    process::exit(0);
+                }
+                other => match other.strip_prefix("--config=") {
+                    Some(path) => parsed.config = Some(PathBuf::from(path)),
+                    None => return Err(format!("unknown argument '{other}'")),
+                },
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+/// `--update-lockfiles` and `--verify-lockfiles`, for every SDK profile.
+async fn maintain_lockfiles(update: bool) -> Result<()> {
+    let toolchain = Toolchain::detect().await;
+    let mut failed = false;
+    for profile in sdk::PROFILES {
+        let result = match update {
+            true => profile.update_lockfile(toolchain.cargo()).await.map(|path| format!("wrote {}", path.display())),
+            false => profile.verify_lockfile(toolchain.cargo()).await.map(|()| "ok".to_string()),
+        };
+        match result {
+            Ok(outcome) => println!("{}: {}", profile.name, outcome),
+            Err(e) => {
+                eprintln!("{}: {:#}", profile.name, e);
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        process::exit(1);
+    }
+    Ok(())
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let config = Config::from_env();
-    init_logging(config.verbose);
+    let args = Args::parse(env::args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("{e}\n\n{USAGE}");
+        process::exit(2);
+    });
+    if args.update_lockfiles || args.verify_lockfiles {
+        return maintain_lockfiles(args.update_lockfiles).await;
+    }
+    let config_path = Config::resolve_path(args.config.as_deref());
+    let config = Config::load(config_path.as_deref()).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        process::exit(1);
+    });
+    if args.print_config {
+        print!("{}", toml::to_string_pretty(&config)?);
+        return Ok(());
+    }
+
+    init_logging(config.verbose, config.log_format);
     info!("Config loaded: {config:#?}");
 
-    program::init().await.map_err(|e| {
+    let toolchain = Toolchain::detect().await;
+    if let Err(e) = toolchain.ensure_complete() {
+        if config.build.require_toolchain {
+            error!("{}", e);
+            return Err(e);
+        }
+        warn!("{}; builds will fail and /ready reports not ready", e);
+    }
+
+    let programs = Programs::new(&config.build, &config.storage, toolchain);
+    programs.init().await.map_err(|e| {
         error!("Failed to initialize program directory: {}", e);
         e
     })?;
     info!("Program directory initialized");
 
+    let vendored = programs.ensure_vendored_mirror();
+    if let Err(e) = &vendored {
+        warn!("{}; builds will fail and /ready reports not ready", e);
+    }
+
     // Warm up the build cache by pre-compiling dependencies
-    program::warmup().await.map_err(|e| {
-        error!("Failed to warmup build cache: {}", e);
-        e
-    })?;
+    if programs.toolchain().missing().is_empty() && vendored.is_ok() {
+        programs.warmup(false).await.map_err(|e| {
+            error!("Failed to warmup build cache: {}", e);
+            e
+        })?;
+    }
 
-    let build_tracker = BuildTracker::new();
-
-    let app = Router::new()
-        .route("/health", get(health))
-        .route("/build", post(build))
-        .route("/build/status/:uuid", get(build_status))
-        .route("/build/status/:uuid", axum::routing::options(build_status_options))
-        .route("/deploy/:uuid/:program_name", get(deploy))
-        .route("/rpc", post(rpc_proxy))
-        .route("/rpc", axum::routing::options(rpc_proxy_options))
-        // Comment out this line
-        // .layer(compression())
+    let tracker_state_file = programs.tracker_state_file();
+    let build_tracker = BuildTracker::load(&tracker_state_file);
+    let state = AppState::new(SharedConfig::new(config, config_path.clone()), programs.clone(), build_tracker)?;
+    let config = state.config.current();
+    let tracker = state.build_tracker.clone();
+    gc::spawn(state.build_tracker.clone(), programs, config.gc.clone());
+    state.rpc.upstreams.spawn_health_checks(std::time::Duration::from_secs(config.rpc_health_interval_secs));
+    match config_path {
+        Some(path) if config.config_watch_secs > 0 => {
+            reload::watch(state.clone(), path, Duration::from_secs(config.config_watch_secs))
+        }
+        _ => {}
+    }
+
+    let app = routes::router()
         .layer(payload_limit(config.payload_limit))
-        .layer(cors(config.client_url))
+        .layer(middleware::from_fn_with_state(state.clone(), authenticate))
+        .layer(cors(config.client_url.clone(), state.auth.has_keys()))
         .layer(middleware::from_fn(log))
-        .with_state(build_tracker);
+        .with_state(state);
 
     let addr = SocketAddr::from((Ipv4Addr::UNSPECIFIED, config.port));
     info!("Attempting to bind to {addr}");
@@ -72,10 +201,62 @@ async fn main() -> Result<()> {
     info!("Successfully bound to {addr}");
 
     info!("Starting server...");
-    axum::serve(listener, app).await.map_err(|e| {
-        error!("Server error: {}", e);
-        e
-    })?;
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(drain(tracker, tracker_state_file, Duration::from_secs(config.shutdown_deadline_secs)))
+        .await
+        .map_err(|e| {
+            error!("Server error: {}", e);
+            e
+        })?;
 
     Ok(())
 }
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c().await.expect("failed to listen for Ctrl+C");
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
+
+/// Resolves once the server may stop: after a shutdown signal, new builds are
+/// refused while running ones get until `deadline` to finish. Killing
+/// `cargo-build-sbf` mid-write corrupts the shared incremental target dir.
+async fn drain(tracker: BuildTracker, state_file: PathBuf, deadline: Duration) {
+    shutdown_signal().await;
+    tracker.start_draining();
+    info!("Shutdown requested, draining {} active builds", tracker.active_count().await);
+
+    let waited = tokio::time::timeout(deadline, async {
+        while tracker.active_count().await > 0 {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+    })
+    .await;
+
+    if waited.is_err() {
+        let running = tracker.list_builds().await.into_iter().filter(|info| info.status.is_running());
+        for info in running {
+            warn!("Cancelling build {} after the shutdown deadline", info.uuid);
+            tracker.cancel_build(&info.uuid, "Build cancelled by server shutdown").await;
+        }
+    }
+
+    if let Err(e) = tracker.save(&state_file).await {
+        error!("Failed to persist build tracker state: {}", e);
+    }
+    info!("Drained, shutting down");
+}
@@ -0,0 +1,165 @@
+//! Prometheus metrics, served as text on `/metrics`. Everything records into
+//! one process-wide registry reached through [`metrics()`].
+
+use std::{sync::LazyLock, time::Duration};
+
+use prometheus_client::{
+    encoding::{text::encode, EncodeLabelSet},
+    metrics::{
+        counter::Counter,
+        family::Family,
+        gauge::Gauge,
+        histogram::{exponential_buckets, Histogram},
+    },
+    registry::Registry,
+};
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+pub struct OutcomeLabels {
+    pub outcome: &'static str,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+pub struct CacheLabels {
+    pub result: &'static str,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+pub struct RpcLabels {
+    pub network: String,
+    /// Position in the network's upstream list; URLs may embed credentials
+    pub upstream: String,
+    pub method: String,
+}
+
+type HistogramFamily<L> = Family<L, Histogram, fn() -> Histogram>;
+
+pub struct Metrics {
+    registry: Registry,
+    /// Builds accepted and not yet finished; set from the build tracker on export
+    pub build_queue_depth: Gauge,
+    pub build_duration: HistogramFamily<OutcomeLabels>,
+    pub build_cache: Family<CacheLabels, Counter>,
+    pub artifact_bytes: Histogram,
+    pub rpc_latency: HistogramFamily<RpcLabels>,
+    pub rpc_errors: Family<RpcLabels, Counter>,
+    pub http_in_flight: Gauge,
+}
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+pub fn metrics() -> &'static Metrics {
+    &METRICS
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let mut registry = Registry::with_prefix("arch");
+
+        let build_queue_depth = Gauge::default();
+        registry.register("build_queue_depth", "Builds accepted and not yet finished", build_queue_depth.clone());
+
+        // 5s to ~21min
+        let build_duration: HistogramFamily<OutcomeLabels> =
+            Family::new_with_constructor(|| Histogram::new(exponential_buckets(5.0, 2.0, 9)));
+        registry.register("build_duration_seconds", "Build wall-clock time by outcome", build_duration.clone());
+
+        let build_cache = Family::<CacheLabels, Counter>::default();
+        registry.register(
+            "build_cache_lookups",
+            "Builds that reused the shared dependency cache (hit) or compiled dependencies (miss)",
+            build_cache.clone(),
+        );
+
+        // 16 KiB to 8 MiB
+        let artifact_bytes = Histogram::new(exponential_buckets(16384.0, 2.0, 10));
+        registry.register("build_artifact_bytes", "Size of program binaries produced", artifact_bytes.clone());
+
+        // 10ms to ~40s
+        let rpc_latency: HistogramFamily<RpcLabels> =
+            Family::new_with_constructor(|| Histogram::new(exponential_buckets(0.01, 2.0, 12)));
+        registry.register("rpc_proxy_latency_seconds", "RPC proxy upstream latency", rpc_latency.clone());
+
+        let rpc_errors = Family::<RpcLabels, Counter>::default();
+        registry.register(
+            "rpc_proxy_errors",
+            "RPC proxy requests that failed to connect or got a 5xx from the upstream",
+            rpc_errors.clone(),
+        );
+
+        let http_in_flight = Gauge::default();
+        registry.register("http_requests_in_flight", "HTTP requests being handled", http_in_flight.clone());
+
+        Self {
+            registry,
+            build_queue_depth,
+            build_duration,
+            build_cache,
+            artifact_bytes,
+            rpc_latency,
+            rpc_errors,
+            http_in_flight,
+        }
+    }
+
+    /// Records a finished build. `cache_hit` is known when the toolchain ran.
+    pub fn build_finished(&self, success: bool, elapsed: Duration, cache_hit: Option<bool>, artifact_bytes: u64) {
+        let outcome = if success { "success" } else { "failure" };
+        self.build_duration.get_or_create(&OutcomeLabels { outcome }).observe(elapsed.as_secs_f64());
+        if let Some(cache_hit) = cache_hit {
+            let result = if cache_hit { "hit" } else { "miss" };
+            self.build_cache.get_or_create(&CacheLabels { result }).inc();
+        }
+        if artifact_bytes > 0 {
+            self.artifact_bytes.observe(artifact_bytes as f64);
+        }
+    }
+
+    /// Records one attempt against the `upstream`th upstream of `network`.
+    pub fn rpc_attempt(&self, network: &str, upstream: usize, method: &str, elapsed: Duration, ok: bool) {
+        let labels = RpcLabels { network: network.to_string(), upstream: upstream.to_string(), method: method.to_string() };
+        self.rpc_latency.get_or_create(&labels).observe(elapsed.as_secs_f64());
+        if !ok {
+            self.rpc_errors.get_or_create(&labels).inc();
+        }
+    }
+
+    /// Counts a request as in flight until the returned guard is dropped.
+    pub fn track_request(&self) -> InFlight<'_> {
+        self.http_in_flight.inc();
+        InFlight(&self.http_in_flight)
+    }
+
+    pub fn encode(&self) -> String {
+        let mut buffer = String::new();
+        encode(&mut buffer, &self.registry).expect("writing to a String cannot fail");
+        buffer
+    }
+}
+
+pub struct InFlight<'a>(&'a Gauge);
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.0.dec();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_recorded_metrics() {
+        let metrics = Metrics::new();
+        metrics.build_queue_depth.set(2);
+        metrics.build_finished(true, Duration::from_secs(12), Some(true), 40_000);
+        metrics.rpc_attempt("testnet", 1, "get_block_count", Duration::from_millis(30), false);
+
+        let text = metrics.encode();
+        assert!(text.contains("arch_build_queue_depth 2"));
+        assert!(text.contains(r#"arch_build_duration_seconds_count{outcome="success"} 1"#));
+        assert!(text.contains(r#"arch_build_cache_lookups_total{result="hit"} 1"#));
+        assert!(text.contains(r#"arch_rpc_proxy_errors_total{network="testnet",upstream="1",method="get_block_count"} 1"#));
+    }
+}
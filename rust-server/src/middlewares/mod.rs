@@ -1,40 +1,161 @@
-use axum::{response::IntoResponse, middleware::Next};
+use std::net::{IpAddr, SocketAddr};
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    middleware::Next,
+    response::Response,
+};
 use tower_http::{
-    compression::CompressionLayer,
-    cors::{CorsLayer, Any},
+    cors::{AllowOrigin, CorsLayer, Any},
     limit::RequestBodyLimitLayer,
 };
-use http::{Method, header};
+use http::{header, HeaderName, HeaderValue, Method};
+use tracing::{info, info_span, Instrument};
+use uuid::Uuid;
 
-pub fn compression() -> CompressionLayer {
-    CompressionLayer::new()
-}
+use crate::{
+    compat::{API_VERSION_HEADER, DEPRECATION_HEADER},
+    error::Result,
+    metrics::metrics,
+    state::AppState,
+};
+
+/// Alternative to `Authorization: Bearer <key>` for clients that cannot set it.
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Routes that must answer without a key, e.g. for load balancer probes.
+const PUBLIC_PATHS: &[&str] = &["/health", "/ready", "/openapi.json"];
 
-pub fn cors(_client_url: String) -> CorsLayer {
-    CorsLayer::new()
-        .allow_origin(Any)
+/// Any origin may call the API anonymously. Once API keys are configured,
+/// only the `client_url` origins (comma-separated) may send credentials.
+pub fn cors(client_url: String, credentials: bool) -> CorsLayer {
+    let layer = CorsLayer::new()
         .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE, Method::OPTIONS])
         .allow_headers([
             header::CONTENT_TYPE,
             header::ACCEPT,
             header::CACHE_CONTROL,
             header::PRAGMA,
+            header::AUTHORIZATION,
+            HeaderName::from_static(API_KEY_HEADER),
+            HeaderName::from_static(REQUEST_ID_HEADER),
+            HeaderName::from_static(API_VERSION_HEADER),
         ])
         .expose_headers([
+            HeaderName::from_static(REQUEST_ID_HEADER),
+            HeaderName::from_static(API_VERSION_HEADER),
+            HeaderName::from_static(DEPRECATION_HEADER),
+            header::LINK,
             header::CONTENT_TYPE,
             header::CACHE_CONTROL,
             header::PRAGMA,
             header::EXPIRES,
         ])
-        .max_age(std::time::Duration::from_secs(86400)) // 24 hours cache
+        .max_age(std::time::Duration::from_secs(86400)); // 24 hours cache
+
+    if !credentials {
+        return layer.allow_origin(Any);
+    }
+    let origins = client_url
+        .split(',')
+        .filter_map(|origin| HeaderValue::from_str(origin.trim().trim_end_matches('/')).ok())
+        .collect::<Vec<_>>();
+    layer.allow_origin(AllowOrigin::list(origins)).allow_credentials(true)
 }
 
 pub fn payload_limit(limit: usize) -> RequestBodyLimitLayer {
     RequestBodyLimitLayer::new(limit * 1024 * 1024) // Convert MB to bytes
 }
 
-pub async fn log(req: axum::http::Request<axum::body::Body>, next: Next) -> impl IntoResponse {
-    use tracing::info;
-    info!("{} {}", req.method(), req.uri());
-    next.run(req).await
-}
\ No newline at end of file
+/// Correlates a request's log lines and response. Taken from the client when
+/// it sends a usable one, generated otherwise.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+fn request_id(req: &Request) -> String {
+    req.headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|id| !id.is_empty() && id.len() <= 128 && id.bytes().all(|b| b.is_ascii_graphic()))
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string())
+}
+
+/// Logs every request inside a span carrying its request id, and echoes the id
+/// back in `X-Request-Id`.
+pub async fn log(req: Request, next: Next) -> Response {
+    let id = request_id(&req);
+    let span = info_span!("request", request_id = %id, method = %req.method(), path = %req.uri().path());
+
+    let _in_flight = metrics().track_request();
+    let mut response = async move {
+        info!("{} {}", req.method(), req.uri());
+        let response = next.run(req).await;
+        info!(status = response.status().as_u16(), "Request finished");
+        response
+    }
+    .instrument(span)
+    .await;
+
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+/// The caller's address: the last `X-Forwarded-For` hop when running behind
+/// a trusted proxy, otherwise the peer of the connection. Only the last hop
+/// is the one our proxy appended; earlier ones are whatever the client sent.
+fn client_ip(req: &Request, trust_forwarded_for: bool) -> Option<IpAddr> {
+    let forwarded = trust_forwarded_for
+        .then(|| req.headers().get_all("x-forwarded-for").iter().next_back())
+        .flatten()
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.rsplit(',').next())
+        .and_then(|ip| ip.trim().parse().ok());
+    forwarded.or_else(|| req.extensions().get::<ConnectInfo<SocketAddr>>().map(|info| info.0.ip()))
+}
+
+/// Resolves the caller's `Principal` and attaches it to the request extensions.
+pub async fn authenticate(State(state): State<AppState>, mut req: Request, next: Next) -> Result<Response> {
+    if req.method() == Method::OPTIONS || PUBLIC_PATHS.contains(&req.uri().path()) {
+        return Ok(next.run(req).await);
+    }
+
+    let headers = req.headers();
+    let key = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .or_else(|| headers.get(API_KEY_HEADER).and_then(|v| v.to_str().ok()))
+        .map(str::trim);
+
+    let mut principal = state.auth.authenticate(key)?;
+    principal.client_ip = client_ip(&req, state.config.current().trust_forwarded_for);
+    req.extensions_mut().insert(principal);
+    Ok(next.run(req).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+
+    #[test]
+    fn request_id_is_reused_only_when_well_formed() {
+        let req = Request::builder().header(REQUEST_ID_HEADER, "trace-42").body(Body::empty()).unwrap();
+        assert_eq!(request_id(&req), "trace-42");
+
+        let req = Request::builder().header(REQUEST_ID_HEADER, "has spaces").body(Body::empty()).unwrap();
+        assert!(Uuid::try_parse(&request_id(&req)).is_ok());
+    }
+
+    #[test]
+    fn client_ip_is_the_hop_appended_by_the_proxy() {
+        let req = Request::builder()
+            .header("x-forwarded-for", "10.0.0.1, 203.0.113.7")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(client_ip(&req, true), Some("203.0.113.7".parse().unwrap()));
+        assert_eq!(client_ip(&req, false), None);
+    }
+}
@@ -0,0 +1,457 @@
+//! OpenAPI 3 description of the HTTP API, generated from the handlers'
+//! `#[utoipa::path]` annotations and the request and response types.
+//!
+//! It is served at `/openapi.json` and committed as `openapi.json` next to
+//! `Cargo.toml` so clients can generate typed bindings from it, e.g.
+//! the IDE's `frontend/src/api/schema.ts`, which the tests generate in the
+//! layout of `openapi-typescript`. Regenerate both with
+//! `UPDATE_OPENAPI=1 cargo test openapi`.
+
+use axum::response::IntoResponse;
+use utoipa::{
+    openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme},
+    Modify, OpenApi,
+};
+
+use crate::{
+    arch::MessageHeader,
+    build_tracker::{BuildStatus, DeploymentInfo, DeploymentStatus},
+    config::Quota,
+    error::{ErrorBody, ErrorCode, ErrorEnvelope, FieldError},
+    extract::Json,
+    localnet::{LocalnetState, LocalnetStatus, ProcessStatus},
+    middlewares::API_KEY_HEADER,
+    program::{ArtifactStore, DiskUsage, ProfileWarmup, VendorMirror, WarmupState},
+    reload::{Change, ReloadReport},
+    routes::{self, *},
+    toolchain::{Tool, Toolchain},
+    upstreams::{NetworkStatus, UpstreamHealth, UpstreamStatus},
+    usage::{Usage, UsageReport},
+};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Arch IDE server",
+        description = "Builds and deploys Arch programs and proxies Arch and Bitcoin RPC for the IDE. \
+                       Errors share one envelope, `ErrorEnvelope`, whose `code` is stable.\n\n\
+                       Within `/v1` changes are additive only: clients must ignore unknown fields and error \
+                       codes. The `Api-Version` request header (`1` or `legacy`) selects the response schema \
+                       and is echoed on every response. The same routes without the `/v1` prefix are \
+                       deprecated aliases that default to `legacy` and carry `Deprecation` and \
+                       `Link: rel=\"successor-version\"` headers."
+    ),
+    paths(
+        routes::health,
+        routes::ready,
+        routes::health_details,
+        routes::metrics_export,
+        openapi_json,
+        routes::build,
+        routes::build_status,
+        routes::deploy_program,
+        routes::deploy,
+        routes::rpc_proxy,
+        routes::rpc_health,
+        routes::bitcoin_rpc,
+        routes::localnet_start,
+        routes::localnet_stop,
+        routes::localnet_reset,
+        routes::localnet_status,
+        routes::bip322_verify,
+        routes::bip322_sign_test,
+        routes::decode,
+        routes::idl_encode,
+        routes::idl_decode,
+        routes::usage,
+        routes::admin_usage,
+        routes::list_builds,
+        routes::cancel_build,
+        routes::purge_artifacts,
+        routes::rerun_warmup,
+        routes::refresh_vendor,
+        routes::disk_usage,
+        routes::reload_config,
+    ),
+    components(schemas(
+        ErrorEnvelope,
+        ErrorBody,
+        ErrorCode,
+        FieldError,
+        BuildRequest,
+        BuildResponse,
+        BuildStatusResponse,
+        BuildStatus,
+        DeploymentInfo,
+        DeploymentStatus,
+        DeployRequest,
+        DeployResponse,
+        FeePayer,
+        BitcoinRpcRequest,
+        NetworkStatus,
+        UpstreamStatus,
+        UpstreamHealth,
+        LocalnetStatus,
+        LocalnetState,
+        ProcessStatus,
+        Encoding,
+        SignedPayload,
+        VerifyRequest,
+        VerifyResponse,
+        SignTestRequest,
+        SignTestResponse,
+        DecodeKind,
+        DecodeRequest,
+        Decoded,
+        MessageBreakdown,
+        MessageHeader,
+        AccountKeyBreakdown,
+        InstructionBreakdown,
+        TransactionBreakdown,
+        SignatureBreakdown,
+        BitcoinTransactionBreakdown,
+        BitcoinInputBreakdown,
+        BitcoinOutputBreakdown,
+        IdlEncodeRequest,
+        IdlEncodeResponse,
+        IdlDecodeRequest,
+        IdlDecodeResponse,
+        UsageReport,
+        Usage,
+        Quota,
+        BuildSummary,
+        PurgeRequest,
+        PurgeResponse,
+        DiskUsage,
+        ReloadReport,
+        Change,
+        Readiness,
+        HealthDetails,
+        Toolchain,
+        Tool,
+        Disk,
+        Builds,
+        ProfileWarmup,
+        WarmupState,
+        VendorMirror,
+        ArtifactStore,
+    )),
+    modifiers(&Security),
+    // Anonymous access is allowed unless the server requires an API key
+    security(("bearer" = []), ("api_key" = []), ()),
+    tags(
+        (name = "build", description = "Compile programs"),
+        (name = "deploy", description = "Download and deploy built programs"),
+        (name = "rpc", description = "Arch and Bitcoin JSON-RPC proxies"),
+        (name = "localnet", description = "Local validator and bitcoind"),
+        (name = "tools", description = "Signing and decoding helpers"),
+        (name = "idl", description = "Borsh encoding with a program's IDL"),
+        (name = "usage", description = "Per-principal usage and quotas"),
+        (name = "admin", description = "Operator endpoints; admin keys only"),
+        (name = "health", description = "Probes and monitoring"),
+    )
+)]
+pub struct ApiDoc;
+
+/// The API key schemes `middlewares::authenticate` accepts.
+struct Security;
+
+impl Modify for Security {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        );
+        components.add_security_scheme(
+            "api_key",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new(API_KEY_HEADER))),
+        );
+    }
+}
+
+/// This document.
+#[utoipa::path(
+    get,
+    path = "/openapi.json",
+    tag = "health",
+    security(()),
+    responses((status = 200, description = "OpenAPI 3 document", body = Object))
+)]
+pub async fn openapi_json() -> impl IntoResponse {
+    Json(ApiDoc::openapi())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeSet, env, fs, path::Path};
+
+    use serde_json::Value;
+
+    use super::*;
+
+    const METHODS: &[&str] = &["get", "post", "put", "patch", "delete"];
+
+    /// `(method, path)` of every operation in the spec.
+    fn documented() -> BTreeSet<(String, String)> {
+        let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        let paths = spec["paths"].as_object().unwrap();
+        paths
+            .iter()
+            .flat_map(|(path, item)| {
+                let methods = item.as_object().unwrap().keys().filter(|key| METHODS.contains(&key.as_str()));
+                methods.map(move |method| (method.clone(), path.clone()))
+            })
+            .collect()
+    }
+
+    /// `(method, path)` of every route `routes::router` serves, with `:param`
+    /// written the OpenAPI way. CORS preflight handlers are not documented.
+    fn routed() -> BTreeSet<(String, String)> {
+        routes::route_table()
+            .into_iter()
+            .map(|(method, path)| (method.as_str().to_lowercase(), path))
+            .filter(|(method, _)| METHODS.contains(&method.as_str()))
+            .map(|(method, path)| {
+                let segments: Vec<_> = path
+                    .split('/')
+                    .map(|segment| match segment.strip_prefix(':') {
+                        Some(param) => format!("{{{param}}}"),
+                        None => segment.to_string(),
+                    })
+                    .collect();
+                (method, segments.join("/"))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn every_route_is_documented() {
+        let (documented, routed) = (documented(), routed());
+        let undocumented: Vec<_> = routed.difference(&documented).collect();
+        let unrouted: Vec<_> = documented.difference(&routed).collect();
+        assert!(undocumented.is_empty(), "routes missing from ApiDoc: {undocumented:?}");
+        assert!(unrouted.is_empty(), "ApiDoc operations with no route: {unrouted:?}");
+    }
+
+    /// Compares `generated` with the committed file at `path`, relative to
+    /// the workspace root, or rewrites the file when `UPDATE_OPENAPI` is set.
+    fn check_committed(path: &str, generated: &str) {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("..").join(path);
+        if env::var_os("UPDATE_OPENAPI").is_some() {
+            fs::write(&path, generated).unwrap();
+            return;
+        }
+        let committed = fs::read_to_string(&path).unwrap_or_default();
+        assert!(
+            committed == generated,
+            "{} differs from the API in the code; regenerate it with `UPDATE_OPENAPI=1 cargo test openapi`",
+            path.display()
+        );
+    }
+
+    #[test]
+    fn committed_openapi_json_is_up_to_date() {
+        check_committed("rust-server/openapi.json", &(ApiDoc::openapi().to_pretty_json().unwrap() + "\n"));
+    }
+
+    #[test]
+    fn committed_typescript_schema_is_up_to_date() {
+        let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        check_committed("frontend/src/api/schema.ts", &typescript(&spec));
+    }
+
+    /// TypeScript types for `spec`, laid out like `openapi-typescript` output:
+    /// `paths` maps each path and method to an entry of `operations`, which
+    /// refer to `components["schemas"]`.
+    fn typescript(spec: &Value) -> String {
+        let mut out = String::from(
+            "/**\n * Types of the build server's API, generated from rust-server/openapi.json.\n \
+             * Do not edit; regenerate with `UPDATE_OPENAPI=1 cargo test openapi` in rust-server.\n */\n\n",
+        );
+
+        out += "export interface paths {\n";
+        for (path, item) in spec["paths"].as_object().unwrap() {
+            out += &format!("    {}: {{\n", quote(path));
+            for (method, operation) in item.as_object().unwrap() {
+                if METHODS.contains(&method.as_str()) {
+                    let id = operation["operationId"].as_str().unwrap();
+                    out += &format!("        {method}: operations[{}];\n", quote(id));
+                }
+            }
+            out += "    };\n";
+        }
+        out += "}\n\n";
+
+        out += "export interface components {\n    schemas: {\n";
+        for (name, schema) in spec["components"]["schemas"].as_object().unwrap() {
+            out += &doc(schema, 2);
+            out += &format!("        {}: {};\n", key(name), ty(schema, 2));
+        }
+        out += "    };\n}\n\n";
+
+        out += "export interface operations {\n";
+        let operations = spec["paths"].as_object().unwrap().values().flat_map(|item| {
+            let methods = item.as_object().unwrap().iter();
+            methods.filter(|(method, _)| METHODS.contains(&method.as_str())).map(|(_, operation)| operation)
+        });
+        for operation in operations {
+            out += &format!("    {}: {{\n", key(operation["operationId"].as_str().unwrap()));
+            out += &parameters(operation);
+            if let Some(body) = operation.get("requestBody") {
+                let optional = if body["required"] == true { "" } else { "?" };
+                out += &format!("        requestBody{optional}: {{\n");
+                out += &content(&body["content"], 3);
+                out += "        };\n";
+            }
+            out += "        responses: {\n";
+            for (status, response) in operation["responses"].as_object().unwrap() {
+                out += &doc(response, 3);
+                out += &format!("            {status}: {{\n");
+                match response.get("content") {
+                    Some(body) => out += &content(body, 4),
+                    None => out += "                content?: never;\n",
+                }
+                out += "            };\n";
+            }
+            out += "        };\n    };\n";
+        }
+        out += "}\n";
+        out
+    }
+
+    /// The `parameters` of an operation, grouped by where they go.
+    fn parameters(operation: &Value) -> String {
+        let parameters = operation["parameters"].as_array().cloned().unwrap_or_default();
+        let mut out = String::from("        parameters: {\n");
+        for location in ["query", "path"] {
+            let group: Vec<_> = parameters.iter().filter(|p| p["in"] == location).collect();
+            if group.is_empty() {
+                out += &format!("            {location}?: never;\n");
+                continue;
+            }
+            let optional = if group.iter().any(|p| p["required"] == true) { "" } else { "?" };
+            out += &format!("            {location}{optional}: {{\n");
+            for parameter in group {
+                out += &doc(parameter, 4);
+                let optional = if parameter["required"] == true { "" } else { "?" };
+                let name = key(parameter["name"].as_str().unwrap());
+                out += &format!("                {name}{optional}: {};\n", ty(&parameter["schema"], 4));
+            }
+            out += "            };\n";
+        }
+        out + "        };\n"
+    }
+
+    /// `content: { "<media type>": T }` of a request or response body.
+    fn content(content: &Value, depth: usize) -> String {
+        let indent = "    ".repeat(depth);
+        let mut out = format!("{indent}content: {{\n");
+        for (media_type, body) in content.as_object().unwrap() {
+            out += &format!("{indent}    {}: {};\n", quote(media_type), ty(&body["schema"], depth + 1));
+        }
+        out + &format!("{indent}}};\n")
+    }
+
+    /// The TypeScript type of a schema; `depth` is the indentation of the
+    /// line it starts on.
+    fn ty(schema: &Value, depth: usize) -> String {
+        let ty = if let Some(reference) = schema["$ref"].as_str() {
+            let name = reference.trim_start_matches("#/components/schemas/");
+            format!("components[\"schemas\"][{}]", quote(name))
+        } else if let Some(variants) = schema["enum"].as_array() {
+            let variants: Vec<_> = variants.iter().map(|v| v.to_string()).collect();
+            variants.join(" | ")
+        } else if let Some(parts) = schema["allOf"].as_array() {
+            combine(parts, " & ", depth)
+        } else if let Some(variants) = schema["oneOf"].as_array() {
+            combine(variants, " | ", depth)
+        } else {
+            match schema["type"].as_str() {
+                Some("string") => "string".to_string(),
+                Some("integer" | "number") => "number".to_string(),
+                Some("boolean") => "boolean".to_string(),
+                Some("array") => format!("{}[]", group(ty(&schema["items"], depth))),
+                Some("object") => object(schema, depth),
+                _ => "unknown".to_string(),
+            }
+        };
+        if schema["nullable"] == true {
+            format!("{} | null", group(ty))
+        } else {
+            ty
+        }
+    }
+
+    fn combine(schemas: &[Value], separator: &str, depth: usize) -> String {
+        let types: Vec<_> = schemas.iter().map(|schema| group(ty(schema, depth))).collect();
+        types.join(separator)
+    }
+
+    fn object(schema: &Value, depth: usize) -> String {
+        let indent = "    ".repeat(depth);
+        let required: Vec<_> = schema["required"].as_array().into_iter().flatten().filter_map(Value::as_str).collect();
+        let mut out = String::from("{\n");
+        for (name, property) in schema["properties"].as_object().into_iter().flatten() {
+            let optional = if required.contains(&name.as_str()) { "" } else { "?" };
+            out += &doc(property, depth + 1);
+            out += &format!("{indent}    {}{optional}: {};\n", key(name), ty(property, depth + 1));
+        }
+        if let Some(values) = schema.get("additionalProperties").filter(|v| v.is_object()) {
+            out += &format!("{indent}    [key: string]: {};\n", ty(values, depth + 1));
+        }
+        if out == "{\n" {
+            return "Record<string, unknown>".to_string();
+        }
+        out + &format!("{indent}}}")
+    }
+
+    /// Parenthesizes unions and intersections so they can be nested.
+    fn group(ty: String) -> String {
+        let mut nesting = 0;
+        let mut compound = false;
+        for (i, c) in ty.char_indices() {
+            match c {
+                '{' | '[' | '(' => nesting += 1,
+                '}' | ']' | ')' => nesting -= 1,
+                '|' | '&' if nesting == 0 && ty[..i].ends_with(' ') => compound = true,
+                _ => {}
+            }
+        }
+        if compound {
+            format!("({ty})")
+        } else {
+            ty
+        }
+    }
+
+    /// A JSDoc comment with the schema's description, if it has one.
+    fn doc(schema: &Value, depth: usize) -> String {
+        let Some(description) = schema["description"].as_str().filter(|d| !d.is_empty()) else {
+            return String::new();
+        };
+        let indent = "    ".repeat(depth);
+        let mut out = format!("{indent}/**\n");
+        for (i, line) in description.replace("*/", "*\\/").lines().enumerate() {
+            let tag = if i == 0 { "@description " } else { "" };
+            out += format!("{indent} * {tag}{line}").trim_end();
+            out += "\n";
+        }
+        out + &format!("{indent} */\n")
+    }
+
+    fn quote(s: &str) -> String {
+        Value::from(s).to_string()
+    }
+
+    /// An object key, quoted unless it is an identifier.
+    fn key(name: &str) -> String {
+        let identifier = name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            && !name.starts_with(|c: char| c.is_ascii_digit());
+        if identifier {
+            name.to_string()
+        } else {
+            quote(name)
+        }
+    }
+}
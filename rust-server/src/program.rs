@@ -1,682 +1,985 @@
-use std::{fs, path::Path, process::Command, sync::OnceLock, env};
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, OnceLock},
+    time::{Duration, Instant, SystemTime},
+};
 use anyhow::anyhow;
 use regex::Regex;
-use tokio::sync::OnceCell;
-use cloud_storage::Client;
-use tracing::info;
+use tokio::sync::{OnceCell, OwnedRwLockWriteGuard, OwnedSemaphorePermit, Semaphore};
+use cloud_storage::{Client, ListRequest};
+use futures_util::TryStreamExt;
+use serde::Serialize;
+use uuid::Uuid;
+use tracing::{debug, error, info, warn};
+use utoipa::ToSchema;
 use tokio::process::Command as TokioCommand;
 use tokio::io::{BufReader, AsyncBufReadExt};
 use std::process::Stdio;
 use axum::http::{header, HeaderValue};
 use axum::response::IntoResponse;
 
-const PROGRAMS_DIR: &str = "programs";
-const MAX_FILE_AMOUNT: usize = 64;
-const MAX_PATH_LENGTH: usize = 128;
+use crate::{
+    config::{BuildConfig, StorageConfig},
+    error::{Error, ErrorCode},
+    sdk::{SdkProfile, PROFILES},
+    toolchain::Toolchain,
+};
 
-fn use_gcs() -> bool {
-    std::env::var("USE_GCS").is_ok()
-}
+/// Holds one warmup project per SDK profile.
+const WARMUP_ID: &str = "warmup-cache";
+/// Fingerprint of the inputs of a profile's last successful warmup.
+const FINGERPRINT_FILE: &str = ".fingerprint";
+/// The vendored registry mirror: `crates/` from `cargo vendor`, the cargo
+/// `config.toml` pointing at it and one project per SDK profile holding the
+/// lockfile it was vendored from.
+const VENDOR_DIR: &str = ".vendor";
+/// Rust sources of the program and of its tests, benches and examples.
+const SOURCE_PATHS: &str = r"^/(?:src|tests|benches|examples)/[\w/-]+\.rs$";
+/// Data files a program can `include_str!` or ship alongside its sources: at
+/// the root or under the source directories and `/data/`, never hidden. `{}`
+/// stands for the allowed extensions.
+const DATA_PATHS: &str = r"^/(?:(?:src|tests|benches|examples|data)/(?:[\w-]+/)*)?[\w-][\w.-]*\.(?:{})$";
+const DATA_EXTENSIONS: &[&str] = &["json", "md", "txt", "csv", "yaml", "yml"];
+/// Marker in a build directory (and next to its GCS artifact) naming the owning principal.
+const OWNER_FILE: &str = ".owner";
 
 static INIT: OnceCell<()> = OnceCell::const_new();
-static GCS_CLIENT: OnceLock<Client> = OnceLock::new();
 
-fn get_gcs_bucket() -> String {
-    env::var("GCS_BUCKET").unwrap_or_else(|_| "arch-ide-build-artifacts".to_string())
+/// Where builds live and where their artifacts are stored, from the `build`
+/// and `storage` sections of `Config`. Shared through app state.
+#[derive(Clone)]
+pub struct Programs {
+    dir: PathBuf,
+    max_file_amount: usize,
+    max_path_length: usize,
+    max_file_bytes: usize,
+    max_files_total_bytes: usize,
+    /// Artifacts are mirrored to this GCS bucket when set
+    gcs_bucket: Option<String>,
+    /// Build against the vendored mirror with `--offline --locked`
+    offline: bool,
+    toolchain: Arc<Toolchain>,
+    warmup: Arc<Mutex<Vec<ProfileWarmup>>>,
+    vendor_refresh: Arc<tokio::sync::Mutex<()>>,
+    /// One permit per build allowed to run at once (`build.capacity`)
+    slots: Arc<Semaphore>,
+    /// Held shared by builds and warmups compiling into `programs/target`,
+    /// and exclusively while pruning it
+    target_lock: Arc<tokio::sync::RwLock<()>>,
 }
 
-fn find_solana_rustc_path() -> Option<String> {
-    // Probe common Solana cache locations for the bundled rustc used by cargo-build-sbf
-    let cache_root = Path::new("/root/.cache/solana");
-    if let Ok(entries) = fs::read_dir(cache_root) {
-        let mut candidates: Vec<std::path::PathBuf> = Vec::new();
-        for entry in entries.flatten() {
-            let p = entry.path().join("platform-tools/rust/bin/rustc");
-            if p.exists() {
-                candidates.push(p);
-            }
-        }
-        // Prefer lexicographically last (usually highest vXX directory)
-        candidates.sort();
-        if let Some(p) = candidates.last() {
-            return Some(p.to_string_lossy().to_string());
-        }
-    }
-    None
+/// The vendored registry mirror offline builds use.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct VendorMirror {
+    /// Crates in the mirror; 0 when it was never refreshed
+    pub crates: usize,
+    pub refreshed_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
-const CARGO_TOML_TEMPLATE: &str = r#"[package]
-name = "{}"
-version = "0.1.0"
-edition = "2021"
+/// Warmup of one SDK profile.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ProfileWarmup {
+    pub profile: &'static str,
+    /// Of the profile's manifest, warmup source and toolchain; a warmup built
+    /// under another fingerprint is redone
+    pub fingerprint: String,
+    pub state: WarmupState,
+}
 
-[lib]
-crate-type = ["cdylib"]
+/// Progress of a profile's background warmup build.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum WarmupState {
+    NotStarted,
+    /// Waiting for the warmups of other profiles to finish
+    Queued,
+    /// `crates_compiled` counts the dependencies compiled so far
+    Running { started_at: chrono::DateTime<chrono::Utc>, crates_compiled: usize },
+    /// `duration_secs` is absent when an earlier run's build was reused
+    Ready { duration_secs: Option<f64> },
+    Failed { error: String },
+}
 
-[dependencies]
-arch_program = "0.5.15"
-apl-associated-token-account = { version = "0.5.15", features = ["no-entrypoint"] }
-apl-token = { version = "0.5.15", features = ["no-entrypoint"] }
-apl-token-metadata = { version = "0.5.15", features = ["no-entrypoint"] }
+impl WarmupState {
+    fn is_active(&self) -> bool {
+        matches!(self, WarmupState::Queued | WarmupState::Running { .. })
+    }
+}
 
-# Satellite framework (pre-compiled in parent Cargo.toml)
-satellite-lang = "0.31.5"
-satellite-apl = "0.31.4"
+static GCS_CLIENT: OnceLock<Client> = OnceLock::new();
 
-# Core serialization/encoding
-borsh = "^1.5.3"
-base64 = { version = "=0.22.1", default-features = false, features = ["alloc"] }
-hex = { version = "=0.4.3", default-features = false }
-sha256 = { version = "=1.5.0", default-features = false }
+fn gcs_client() -> &'static Client {
+    GCS_CLIENT.get_or_init(|| {
+        info!("Initializing GCS client");
+        Client::default()
+    })
+}
+
+impl Programs {
+    pub fn new(build: &BuildConfig, storage: &StorageConfig, toolchain: Toolchain) -> Self {
+        Self {
+            dir: PathBuf::from(&build.programs_dir),
+            max_file_amount: build.max_file_amount,
+            max_path_length: build.max_path_length,
+            max_file_bytes: build.max_file_bytes,
+            max_files_total_bytes: build.max_files_total_bytes,
+            gcs_bucket: storage.gcs_bucket.clone(),
+            offline: build.offline,
+            warmup: Arc::new(Mutex::new(
+                build
+                    .warmup_profiles
+                    .iter()
+                    .filter_map(|name| SdkProfile::find(name))
+                    .map(|profile| ProfileWarmup {
+                        profile: profile.name,
+                        fingerprint: profile.fingerprint(&[
+                            toolchain.cargo_build_sbf.version.as_deref(),
+                            toolchain.platform_tools.version.as_deref(),
+                        ]),
+                        state: WarmupState::NotStarted,
+                    })
+                    .collect(),
+            )),
+            toolchain: Arc::new(toolchain),
+            vendor_refresh: Arc::new(tokio::sync::Mutex::new(())),
+            slots: Arc::new(Semaphore::new(build.capacity)),
+            target_lock: Arc::new(tokio::sync::RwLock::new(())),
+        }
+    }
+
+    /// Waits for a free build slot; the build may run while the permit is held.
+    pub async fn build_slot(&self) -> OwnedSemaphorePermit {
+        self.slots.clone().acquire_owned().await.expect("the build slots are never closed")
+    }
 
-# Error handling
-thiserror = "^1.0.57"
+    /// Exclusive use of the shared target dir, or None while a build or
+    /// warmup is compiling into it.
+    pub fn try_lock_target(&self) -> Option<OwnedRwLockWriteGuard<()>> {
+        self.target_lock.clone().try_write_owned().ok()
+    }
 
-# Serialization
-serde = { version = "^1.0.216", features = ["derive"], default-features = false }
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
 
-# Memory casting utilities
-bytemuck = { version = "^1.20.0", features = ["derive"] }
+    pub fn toolchain(&self) -> &Toolchain {
+        &self.toolchain
+    }
 
-[profile.release]
-overflow-checks = true
-incremental = true
-codegen-units = 256
-opt-level = 1
-lto = false
-debug = false
+    /// Where the build tracker is persisted across restarts.
+    pub fn tracker_state_file(&self) -> PathBuf {
+        self.dir.join(".builds.json")
+    }
 
-[profile.release.build-override]
-opt-level = 1
-incremental = true
-codegen-units = 256
-"#;
+    pub fn warmup_state(&self) -> Vec<ProfileWarmup> {
+        self.warmup.lock().unwrap().clone()
+    }
 
-pub async fn init() -> anyhow::Result<()> {
-    INIT.get_or_try_init(|| async {
-        let programs_dir = Path::new(PROGRAMS_DIR);
-        if !programs_dir.exists() {
-            fs::create_dir_all(programs_dir)?;
+    fn set_warmup_state(&self, profile: &str, state: WarmupState) {
+        let mut warmups = self.warmup.lock().unwrap();
+        if let Some(warmup) = warmups.iter_mut().find(|warmup| warmup.profile == profile) {
+            warmup.state = state;
         }
+    }
 
-        let cargo_toml = r#"[package]
-name = "archpg"
-version = "0.1.0"
-edition = "2021"
+    fn warmup_dir(&self, profile: &str) -> PathBuf {
+        self.dir.join(WARMUP_ID).join(profile)
+    }
 
-[lib]
-crate-type = ["cdylib", "lib"]
-path = "default/src/lib.rs"
+    pub async fn init(&self) -> anyhow::Result<()> {
+        INIT.get_or_try_init(|| async {
+            let programs_dir = self.dir.as_path();
+            // Shared by every build: compiled artifacts and the crates registry
+            for dir in [programs_dir.join("target"), programs_dir.join(".cargo")] {
+                fs::create_dir_all(&dir)?;
+                // Make sure cache directories have proper permissions
+                let _ = TokioCommand::new("chmod").arg("-R").arg("777").arg(&dir).output().await;
+            }
 
-[profile.release]
-overflow-checks = true
-incremental = true
+            Ok::<(), anyhow::Error>(())
+        }).await?;
 
-[dependencies]
-arch_program = "0.5.15"
-apl-associated-token-account = { version = "0.5.15", features = ["no-entrypoint"] }
-apl-token = { version = "0.5.15", features = ["no-entrypoint"] }
-apl-token-metadata = { version = "0.5.15", features = ["no-entrypoint"] }
+        Ok(())
+    }
 
-# Satellite framework (published crate)
-satellite-lang = "0.31.5"
-satellite-apl = "0.31.4"
+    /// Warms up the build cache by pre-compiling the dependencies of each
+    /// configured SDK profile, one after another in the background. A profile
+    /// whose last warmup has the current fingerprint is skipped.
+    ///
+    /// With `force`, previous warmup builds are discarded and redone. Returns
+    /// false if a warmup is already running.
+    pub async fn warmup(&self, force: bool) -> anyhow::Result<bool> {
+        let mut queued = Vec::new();
+        {
+            let mut warmups = self.warmup.lock().unwrap();
+            if warmups.iter().any(|warmup| warmup.state.is_active()) {
+                return Ok(false);
+            }
+            for warmup in warmups.iter_mut() {
+                let dir = self.warmup_dir(warmup.profile);
+                let recorded = fs::read_to_string(dir.join(FINGERPRINT_FILE)).unwrap_or_default();
+                if !force && recorded == warmup.fingerprint && dir.join("target/deploy/warmup.so").exists() {
+                    info!("✅ Build cache already warm for SDK profile {}", warmup.profile);
+                    warmup.state = WarmupState::Ready { duration_secs: None };
+                } else {
+                    warmup.state = WarmupState::Queued;
+                    queued.push(SdkProfile::find(warmup.profile).expect("warmups are of known profiles"));
+                }
+            }
+        }
+        if queued.is_empty() {
+            return Ok(true);
+        }
 
-# Core serialization/encoding
-borsh = { version = "1.5.1", features = ["derive"] }
+        info!("🔥 Warming up build cache by pre-compiling dependencies...");
+        // Warmups before SDK profiles built into a target directory of their own
+        let _ = fs::remove_dir_all(self.dir.join(WARMUP_ID).join("target"));
+        let prepared = self.toolchain.ensure_complete().and_then(|()| {
+            queued.iter().try_for_each(|profile| {
+                let dir = self.warmup_dir(profile.name);
+                prepare_warmup(&dir, profile)?;
+                if self.offline {
+                    self.use_vendored_sources(&dir)?;
+                }
+                Ok(())
+            })
+        });
+        if let Err(e) = prepared {
+            for profile in &queued {
+                self.set_warmup_state(profile.name, WarmupState::Failed { error: e.to_string() });
+            }
+            return Err(e);
+        }
 
-# Utilities
-base64 = { version = "0.22.1", default-features = false, features = ["alloc"] }
-hex = { version = "0.4.3", default-features = false }
-sha256 = { version = "1.5.0", default-features = false }
+        // Run the builds in the background (don't block server startup)
+        let programs = self.clone();
+        tokio::spawn(async move {
+            for profile in queued {
+                info!("🔨 Starting background warmup build of SDK profile {}...", profile.name);
+                let state = match programs.run_warmup(profile).await {
+                    Ok(duration_secs) => {
+                        info!("✅ SDK profile {} warmed up successfully in {:.1}s", profile.name, duration_secs);
+                        WarmupState::Ready { duration_secs: Some(duration_secs) }
+                    }
+                    Err(e) => {
+                        info!("⚠️  Warmup build of SDK profile {} failed: {}", profile.name, e);
+                        WarmupState::Failed { error: e.to_string() }
+                    }
+                };
+                programs.set_warmup_state(profile.name, state);
+            }
+        });
 
-# Error handling
-thiserror = "*"
+        info!("✅ Warmup build started in background (server ready to accept requests)");
+        Ok(true)
+    }
 
-# Logging
-log = "0.4.17"
+    /// Builds a prepared warmup project into the shared target directory,
+    /// counting the crates compiled as it goes, and records its fingerprint.
+    async fn run_warmup(&self, profile: &SdkProfile) -> anyhow::Result<f64> {
+        let start = std::time::Instant::now();
+        let started_at = chrono::Utc::now();
+        self.set_warmup_state(profile.name, WarmupState::Running { started_at, crates_compiled: 0 });
+
+        let dir = self.warmup_dir(profile.name).canonicalize()?;
+        let _target = self.target_lock.read().await;
+        let mut child = self
+            .sbf_command()?
+            .kill_on_drop(true)
+            .arg("--manifest-path")
+            .arg(dir.join("Cargo.toml"))
+            .arg("--sbf-out-dir")
+            .arg(dir.join("target/deploy"))
+            .arg("--")
+            .args(self.cargo_args())
+            .current_dir(&dir)
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        if let Some(stderr) = child.stderr.take() {
+            let mut lines = BufReader::new(stderr).lines();
+            let mut crates_compiled = 0;
+            while let Ok(Some(line)) = lines.next_line().await {
+                if line.trim_start().starts_with("Compiling ") {
+                    crates_compiled += 1;
+                    self.set_warmup_state(profile.name, WarmupState::Running { started_at, crates_compiled });
+                }
+            }
+        }
 
-# Serialization
-serde = { version = "1.0.136", features = ["derive"], default-features = false }
+        let status = child.wait().await?;
+        if !status.success() {
+            return Err(anyhow!("exited with {}", status));
+        }
+        let fingerprint = self
+            .warmup_state()
+            .into_iter()
+            .find(|warmup| warmup.profile == profile.name)
+            .map(|warmup| warmup.fingerprint)
+            .unwrap_or_default();
+        fs::write(dir.join(FINGERPRINT_FILE), fingerprint)?;
+        Ok(start.elapsed().as_secs_f64())
+    }
 
-# Memory casting utilities
-bytemuck = { version = "^1.20.0", features = ["derive"] }
+    /// `cargo-build-sbf` with the shared target directory and `CARGO_HOME`,
+    /// so builds and warmups reuse each other's compiled dependencies.
+    fn sbf_command(&self) -> anyhow::Result<TokioCommand> {
+        // Set up shared target directory for compiled artifacts
+        let programs_dir = self.dir.as_path();
+        let target_dir = programs_dir.join("target");
+        fs::create_dir_all(&target_dir)?;
+
+        // CRITICAL: Set up shared CARGO_HOME for caching downloaded crates and registry
+        // This prevents re-downloading dependencies for every build!
+        let cargo_home = programs_dir.join(".cargo");
+        fs::create_dir_all(&cargo_home)?;
+
+        let shared_target = target_dir.canonicalize().unwrap_or_else(|e| {
+            warn!("Failed to canonicalize target path: {}", e);
+            target_dir.clone()
+        });
+        let shared_cargo_home = cargo_home.canonicalize().unwrap_or_else(|e| {
+            warn!("Failed to canonicalize cargo home path: {}", e);
+            cargo_home.clone()
+        });
+        debug!("Using shared target directory {:?} and CARGO_HOME {:?}", shared_target, shared_cargo_home);
+
+        let mut command = TokioCommand::new(self.toolchain.cargo_build_sbf());
+        command
+            .env("CARGO_TARGET_DIR", &shared_target)
+            .env("CARGO_HOME", &shared_cargo_home) // Cache downloaded crates and registry!
+            .env("CARGO_BUILD_INCREMENTAL", "true")
+            .env("CARGO_PROFILE_RELEASE_INCREMENTAL", "true")
+            .env("CARGO_PROFILE_RELEASE_CODEGEN_UNITS", "256")
+            .env("RUST_LOG", "debug")
+            .env("RUST_BACKTRACE", "1")
+            .env("CARGO_PROFILE_RELEASE_BUILD_OVERRIDE_DEBUG", "false");
+        Ok(command)
+    }
 
-# Testing
-[dev-dependencies]
-proptest = "1.5.0""#;
-
-        let manifest_path = programs_dir.join("Cargo.toml");
-        if !manifest_path.exists() {
-            fs::write(manifest_path, cargo_toml)?;
+    /// Arguments `cargo-build-sbf` passes on to cargo.
+    fn cargo_args(&self) -> Vec<&'static str> {
+        let mut args = vec!["--locked"];
+        if self.toolchain.needs_lockfile_bump() {
+            args.push("-Znext-lockfile-bump");
         }
+        args
+    }
 
-        Ok::<(), anyhow::Error>(())
-    }).await?;
+    fn vendor_dir(&self) -> PathBuf {
+        self.dir.join(VENDOR_DIR)
+    }
 
-    Ok(())
-}
+    /// Points cargo in `dir` at the vendored mirror, offline.
+    fn use_vendored_sources(&self, dir: &Path) -> anyhow::Result<()> {
+        self.ensure_vendored_mirror()?;
+        fs::create_dir_all(dir.join(".cargo"))?;
+        fs::copy(self.vendor_dir().join("config.toml"), dir.join(".cargo/config.toml"))?;
+        Ok(())
+    }
 
-/// Warms up the build cache by pre-compiling dependencies
-/// This significantly speeds up the first user build
-pub async fn warmup() -> anyhow::Result<()> {
-    info!("🔥 Warming up build cache by pre-compiling dependencies...");
+    /// Fails in offline mode when the mirror is missing or was vendored from
+    /// other lockfiles than the SDK profiles' current ones.
+    pub fn ensure_vendored_mirror(&self) -> anyhow::Result<()> {
+        if !self.offline {
+            return Ok(());
+        }
+        let vendor_dir = self.vendor_dir();
+        let stale = !vendor_dir.join("config.toml").exists()
+            || PROFILES.iter().any(|profile| {
+                fs::read_to_string(vendor_dir.join(profile.name).join("Cargo.lock")).ok().as_deref()
+                    != Some(profile.lockfile)
+            });
+        if stale {
+            return Err(anyhow!(
+                "Vendored dependency mirror missing or out of date; refresh it with POST /v1/admin/vendor/refresh"
+            ));
+        }
+        Ok(())
+    }
 
-    let warmup_id = "warmup-cache";
-    let warmup_dir = Path::new(PROGRAMS_DIR).join(warmup_id);
+    /// The mirror offline builds use; `None` unless building offline.
+    pub fn vendored_mirror(&self) -> Option<VendorMirror> {
+        self.offline.then(|| self.mirror_status())
+    }
 
-    // Check if warmup build already exists (cached from previous run)
-    let binary_path = warmup_dir.join("target/deploy/warmup.so");
-    if binary_path.exists() {
-        info!("✅ Build cache already warm (found existing warmup build)");
-        return Ok(());
+    fn mirror_status(&self) -> VendorMirror {
+        let vendor_dir = self.vendor_dir();
+        let crates = fs::read_dir(vendor_dir.join("crates"))
+            .map(|entries| entries.flatten().filter(|entry| entry.path().is_dir()).count())
+            .unwrap_or(0);
+        let refreshed_at = fs::metadata(vendor_dir.join("config.toml"))
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .map(chrono::DateTime::from);
+        VendorMirror { crates, refreshed_at }
     }
 
-    // Create warmup project directory structure
-    fs::create_dir_all(&warmup_dir)?;
-    let src_dir = warmup_dir.join("src");
-    fs::create_dir_all(&src_dir)?;
+    /// Vendors the packages of every SDK profile's lockfile with `cargo
+    /// vendor`, replacing the mirror once that succeeded.
+    /// Needs the network; builds running during the swap may fail. Returns
+    /// `None` if a refresh is already running.
+    pub async fn refresh_vendor(&self) -> anyhow::Result<Option<VendorMirror>> {
+        let Ok(_guard) = self.vendor_refresh.try_lock() else {
+            return Ok(None);
+        };
+        info!("Refreshing the vendored dependency mirror...");
+        let start = std::time::Instant::now();
 
-    // Create minimal Cargo.toml with all dependencies
-    let cargo_toml = CARGO_TOML_TEMPLATE.replace("{}", "warmup");
-    fs::write(warmup_dir.join("Cargo.toml"), cargo_toml)?;
+        let vendor_dir = self.vendor_dir();
+        let mut manifests = Vec::new();
+        for profile in PROFILES {
+            let dir = vendor_dir.join(profile.name);
+            fs::create_dir_all(dir.join("src"))?;
+            fs::write(dir.join("Cargo.toml"), profile.manifest("vendor"))?;
+            fs::write(dir.join("src/lib.rs"), "")?;
+            fs::write(dir.join("Cargo.lock"), profile.lockfile)?;
+            manifests.push(dir.join("Cargo.toml"));
+        }
 
-    // Create minimal lib.rs that uses the dependencies
-    let lib_rs = r#"use arch_program::{
-    account::AccountInfo,
-    entrypoint,
-    program_error::ProgramError,
-    pubkey::Pubkey,
-};
-use borsh::{BorshDeserialize, BorshSerialize};
+        let cargo_home = self.dir.join(".cargo");
+        fs::create_dir_all(&cargo_home)?;
+        let next = vendor_dir.join("crates.next");
+        let _ = fs::remove_dir_all(&next);
+        let mut command = TokioCommand::new(self.toolchain.cargo());
+        command.args(["vendor", "--locked", "--versioned-dirs", "--manifest-path"]).arg(&manifests[0]);
+        for manifest in &manifests[1..] {
+            command.arg("--sync").arg(manifest);
+        }
+        let output = command
+            .arg(&next)
+            .env("CARGO_HOME", cargo_home.canonicalize()?)
+            .current_dir(&vendor_dir)
+            .kill_on_drop(true)
+            .output()
+            .await?;
+        if !output.status.success() {
+            return Err(anyhow!("cargo vendor failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+        }
 
-entrypoint!(process_instruction);
+        let crates = vendor_dir.join("crates");
+        let old = vendor_dir.join("crates.old");
+        let _ = fs::remove_dir_all(&old);
+        if crates.exists() {
+            fs::rename(&crates, &old)?;
+        }
+        fs::rename(&next, &crates)?;
+        let _ = fs::remove_dir_all(&old);
+        fs::write(vendor_dir.join("config.toml"), vendored_sources_config(&crates.canonicalize()?))?;
 
-pub fn process_instruction(
-    _program_id: &Pubkey,
-    _accounts: &[AccountInfo],
-    _instruction_data: &[u8],
-) -> Result<(), ProgramError> {
-    Ok(())
-}
-"#;
-    fs::write(src_dir.join("lib.rs"), lib_rs)?;
+        let mirror = self.mirror_status();
+        info!("Vendored {} crates in {:.1}s", mirror.crates, start.elapsed().as_secs_f64());
+        Ok(Some(mirror))
+    }
 
-    // Run the build in the background (don't block server startup)
-    let warmup_dir_clone = warmup_dir.clone();
-    tokio::spawn(async move {
-        info!("🔨 Starting background warmup build...");
-        let start = std::time::Instant::now();
+    /// Checks the file count, every path and the file sizes, reporting each
+    /// bad file as a field detail so the IDE can point at it.
+    pub fn validate_files(&self, files: &Files) -> Result<(), Error> {
+        if files.len() > self.max_file_amount {
+            return Err(Error::coded(
+                ErrorCode::TooManyFiles,
+                format!("Exceeded maximum file amount({})", self.max_file_amount),
+            )
+            .with_detail("files", format!("{} files, at most {} allowed", files.len(), self.max_file_amount)));
+        }
 
-        let result = TokioCommand::new("cargo")
-            .args(&[
-                "build-sbf",
-                "--manifest-path",
-                &warmup_dir_clone.join("Cargo.toml").to_string_lossy(),
-            ])
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .status()
-            .await;
-
-        match result {
-            Ok(status) if status.success() => {
-                let elapsed = start.elapsed();
-                info!("✅ Build cache warmed up successfully in {:.1}s", elapsed.as_secs_f64());
-                info!("   Next user build will be much faster!");
-            }
-            Ok(status) => {
-                info!("⚠️  Warmup build exited with status: {}", status);
+        static ALLOWED_REGEXES: OnceLock<[Regex; 2]> = OnceLock::new();
+        let allowed_regexes = ALLOWED_REGEXES.get_or_init(|| {
+            [
+                Regex::new(SOURCE_PATHS).unwrap(),
+                Regex::new(&DATA_PATHS.replace("{}", &DATA_EXTENSIONS.join("|"))).unwrap(),
+            ]
+        });
+        let mut error = Error::coded(ErrorCode::InvalidPath, "Invalid path");
+        let mut invalid = false;
+        for (i, [path, _]) in files.iter().enumerate() {
+            let reason = if path.len() > self.max_path_length {
+                Some(format!("longer than {} characters", self.max_path_length))
+            } else if !allowed_regexes.iter().any(|regex| regex.is_match(path)) || path.contains("..") || path.contains("//") {
+                Some(format!(
+                    "must be a .rs file under /src/, /tests/, /benches/ or /examples/, or a .{} file",
+                    DATA_EXTENSIONS.join(", .")
+                ))
+            } else {
+                None
+            };
+            if let Some(reason) = reason {
+                error = error.with_detail(format!("files[{i}][0]"), format!("{path}: {reason}"));
+                invalid = true;
             }
-            Err(e) => {
-                info!("⚠️  Warmup build failed: {}", e);
+        }
+        if invalid {
+            return Err(error);
+        }
+
+        let mut error = Error::coded(ErrorCode::PayloadTooLarge, "Files too large");
+        let mut too_large = false;
+        for (i, [path, content]) in files.iter().enumerate() {
+            if content.len() > self.max_file_bytes {
+                let reason = format!("{path}: {} bytes, at most {} allowed", content.len(), self.max_file_bytes);
+                error = error.with_detail(format!("files[{i}][1]"), reason);
+                too_large = true;
             }
         }
-    });
+        let total: usize = files.iter().map(|[_, content]| content.len()).sum();
+        if total > self.max_files_total_bytes {
+            let reason = format!("{total} bytes in total, at most {} allowed", self.max_files_total_bytes);
+            error = error.with_detail("files", reason);
+            too_large = true;
+        }
+        if too_large {
+            return Err(error);
+        }
+        Ok(())
+    }
+
+    pub async fn build(
+        &self,
+        uuid: &str,
+        program_name: &str,
+        files: &Files,
+    ) -> anyhow::Result<BuildOutput> {
+        info!("Starting build for program: {}", program_name);
+        self.validate_files(files)?;
+        self.toolchain.ensure_complete()?;
+
+        // Get or create program directory using UUID
+        let program_path = self.dir.as_path().join(uuid);
+        debug!("Program directory: {:?}", program_path);
+
+        // Ensure the program directory and its subdirectories exist
+        fs::create_dir_all(&program_path)?;
+        fs::create_dir_all(program_path.join("src"))?;
+        fs::create_dir_all(program_path.join("target/deploy"))?;
+
+        // Write source files
+        debug!("Writing source files...");
+        for [path, content] in files {
+            let relative_path = path.trim_start_matches('/');
+            let file_path = program_path.join(relative_path);
+            debug!("Writing file: {:?}", file_path);
+
+            let parent = file_path.parent().expect("Should have parent");
+            fs::create_dir_all(parent)?;
+            fs::write(&file_path, content)?;
+        }
 
-    info!("✅ Warmup build started in background (server ready to accept requests)");
-    Ok(())
-}
+        // Create program-specific Cargo.toml with sanitized name
+        debug!("Creating Cargo.toml...");
+        let safe_program_name = program_name.replace(|c: char| !c.is_alphanumeric(), "_");
+        let profile = SdkProfile::default_profile();
+        let cargo_toml = profile.manifest(&safe_program_name);
+        let manifest_path = program_path.join("Cargo.toml");
 
-pub type Files = Vec<[String; 2]>;
+        // Debug output for Cargo.toml creation
+        debug!("Writing Cargo.toml to: {:?}", manifest_path);
+        debug!("Cargo.toml contents:\n{}", cargo_toml);
 
-pub async fn build(
-    uuid: &str,
-    program_name: &str,
-    files: &Files,
-) -> anyhow::Result<(String, String)> {
-    println!("Starting build for program: {}", program_name);
-
-    // Check file count
-    if files.len() > MAX_FILE_AMOUNT {
-        return Err(anyhow!("Exceeded maximum file amount({MAX_FILE_AMOUNT})"));
-    }
-
-    // Check file paths
-    static ALLOWED_REGEX: OnceLock<Regex> = OnceLock::new();
-    let allowed_regex = ALLOWED_REGEX.get_or_init(|| Regex::new(r"^/src/[\w/-]+\.rs$").unwrap());
-    let is_valid = files.iter().all(|[path, _]| {
-        allowed_regex.is_match(path)
-            && path.len() <= MAX_PATH_LENGTH
-            && !path.contains("..")
-            && !path.contains("//")
-    });
-    if !is_valid {
-        return Err(anyhow!("Invalid path"));
-    }
-
-    // Get or create program directory using UUID
-    let program_path = Path::new(PROGRAMS_DIR).join(uuid);
-    println!("Program directory: {:?}", program_path);
-
-    // Ensure the program directory and its subdirectories exist
-    fs::create_dir_all(&program_path)?;
-    fs::create_dir_all(program_path.join("src"))?;
-    fs::create_dir_all(program_path.join("target/deploy"))?;
-
-    // Write source files
-    println!("Writing source files...");
-    for [path, content] in files {
-        let relative_path = path.trim_start_matches('/');
-        let file_path = program_path.join(relative_path);
-        println!("Writing file: {:?}", file_path);
-
-        let parent = file_path.parent().expect("Should have parent");
-        fs::create_dir_all(parent)?;
-        fs::write(&file_path, content)?;
-    }
-
-    // Create program-specific Cargo.toml with sanitized name
-    println!("Creating Cargo.toml...");
-    let safe_program_name = program_name.replace(|c: char| !c.is_alphanumeric(), "_");
-    let cargo_toml = CARGO_TOML_TEMPLATE.replace("{}", &safe_program_name);
-    let manifest_path = program_path.join("Cargo.toml");
-
-    // Debug output for Cargo.toml creation
-    println!("Writing Cargo.toml to: {:?}", manifest_path);
-    println!("Cargo.toml contents:\n{}", cargo_toml);
-
-    // Write Cargo.toml and verify it exists
-    fs::write(&manifest_path, &cargo_toml)?;
-    if !manifest_path.exists() {
-        return Err(anyhow!("Failed to create Cargo.toml file"));
-    }
-
-    // Set up shared target directory for compiled artifacts
-    println!("Setting up shared target directory...");
-    let programs_dir = Path::new(PROGRAMS_DIR);
-    let target_dir = programs_dir.join("target");
-    fs::create_dir_all(&target_dir)?;
-
-    // CRITICAL: Set up shared CARGO_HOME for caching downloaded crates and registry
-    // This prevents re-downloading dependencies for every build!
-    let cargo_home = programs_dir.join(".cargo");
-    fs::create_dir_all(&cargo_home)?;
-    println!("Using shared CARGO_HOME: {:?}", cargo_home);
-
-    // Make sure cache directories have proper permissions
-    let _ = Command::new("chmod")
-        .arg("-R")
-        .arg("777")
-        .arg(&target_dir)
-        .output();
-    let _ = Command::new("chmod")
-        .arg("-R")
-        .arg("777")
-        .arg(&cargo_home)
-        .output();
-
-    let shared_target = match target_dir.canonicalize() {
-        Ok(path) => path,
-        Err(e) => {
-            println!("Warning: Failed to canonicalize target path: {}", e);
-            target_dir.clone()
+        // Write Cargo.toml and verify it exists
+        fs::write(&manifest_path, &cargo_toml)?;
+        if !manifest_path.exists() {
+            return Err(anyhow!("Failed to create Cargo.toml file"));
         }
-    };
-    println!("Using shared target directory: {:?}", shared_target);
 
-    let shared_cargo_home = match cargo_home.canonicalize() {
-        Ok(path) => path,
-        Err(e) => {
-            println!("Warning: Failed to canonicalize cargo home path: {}", e);
-            cargo_home.clone()
+        // Left over from offline builds of the same project
+        if !self.offline {
+            let _ = fs::remove_dir_all(program_path.join(".cargo"));
         }
-    };
-    let shared_cargo_home_str = shared_cargo_home.to_str()
-        .ok_or_else(|| anyhow!("Failed to convert cargo home path to string"))?
-        .to_string();
-
-    // Clean up any existing Cargo.lock
-    let lock_file = program_path.join("Cargo.lock");
-    if lock_file.exists() {
-        println!("Found existing Cargo.lock file.");
-        // Instead of removing it, try to modify it to fix the bytemuck_derive version
-        let lock_content = fs::read_to_string(&lock_file)?;
-
-        // If the lock file contains bytemuck_derive with version 1.9.2, replace it with 1.5.0
-        let modified_content = lock_content.replace(
-            "name = \"bytemuck_derive\"\nversion = \"1.9.2\"",
-            "name = \"bytemuck_derive\"\nversion = \"1.5.0\""
-        );
 
-        if modified_content != lock_content {
-            println!("Updating bytemuck_derive version in Cargo.lock...");
-            fs::write(&lock_file, modified_content)?;
-        } else {
-            println!("No bytemuck_derive 1.9.2 found in Cargo.lock, removing the file...");
-            fs::remove_file(&lock_file)?;
+        // Lock to the profile's curated lockfile; offline builds also use the vendored mirror
+        fs::write(program_path.join("Cargo.lock"), profile.lockfile)?;
+        if self.offline {
+            debug!("Using the vendored mirror of SDK profile {}", profile.name);
+            self.use_vendored_sources(&program_path)?;
         }
-    } else {
-        println!("No existing Cargo.lock file found.");
-    }
 
-    // Check if cargo is installed
-    println!("Checking if cargo is installed...");
-    let cargo_path = Command::new("which").arg("cargo").output();
+        // Create string bindings with absolute paths
+        let manifest_path_str = program_path
+            .canonicalize()
+            .unwrap_or(program_path.clone())
+            .join("Cargo.toml")
+            .to_str()
+            .expect("Manifest path should be UTF-8")
+            .to_string();
+
+        let deploy_dir_str = program_path
+            .canonicalize()
+            .unwrap_or(program_path.clone())
+            .join("target/deploy")
+            .to_str()
+            .expect("Deploy directory path should be UTF-8")
+            .to_string();
+
+        // Verify paths exist
+        debug!("Verifying paths exist:");
+        debug!("Manifest path exists: {}", Path::new(&manifest_path_str).exists());
+        debug!("Deploy dir exists: {}", Path::new(&deploy_dir_str).exists());
+
+        // Print absolute paths for debugging
+        debug!("Using absolute paths:");
+        debug!("Manifest path: {}", manifest_path_str);
+        debug!("Deploy dir: {}", deploy_dir_str);
+
+        // Build the args vector with absolute paths
+        let mut build_args = vec![
+            "--manifest-path",
+            &manifest_path_str,
+            "--sbf-out-dir",
+            &deploy_dir_str,
+        ];
+
+        let cargo_args = self.cargo_args();
+        build_args.push("--");
+        build_args.extend(cargo_args);
+
+        debug!("Executing build command with args: {:?}", build_args);
+        debug!("Current working directory: {:?}", std::env::current_dir()?);
+
+        // Killed if the build task is cancelled
+        let _target = self.target_lock.read().await;
+        let mut child = self
+            .sbf_command()?
+            .kill_on_drop(true)
+            .args(&build_args)
+            .current_dir(&program_path)  // Keep this to maintain relative path resolution
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let compile_started = Instant::now();
+        let pid = child.id();
+
+        // CRITICAL: Read stdout and stderr in PARALLEL to avoid deadlock
+        // If we read sequentially, the child process can hang if one buffer fills up
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+
+        let stdout_handle = tokio::spawn(async move {
+            let mut lines = String::new();
+            if let Some(stdout) = stdout {
+                let mut reader = BufReader::new(stdout).lines();
+                while let Ok(Some(line)) = reader.next_line().await {
+                    debug!("stdout: {}", line);
+                    lines.push_str(&line);
+                    lines.push('\n');
+                }
+            }
+            lines
+        });
 
-    if let Ok(output) = cargo_path {
-        if output.status.success() {
-            println!("Cargo is installed at: {}", String::from_utf8_lossy(&output.stdout));
-        } else {
-            println!("Cargo is not installed. Attempting to use PATH environment variable.");
-            // Try to find cargo in PATH
-            if let Ok(path) = env::var("PATH") {
-                println!("PATH: {}", path);
+        let stderr_handle = tokio::spawn(async move {
+            let mut lines = String::new();
+            if let Some(stderr) = stderr {
+                let mut reader = BufReader::new(stderr).lines();
+                while let Ok(Some(line)) = reader.next_line().await {
+                    debug!("stderr: {}", line);
+                    lines.push_str(&line);
+                    lines.push('\n');
+                }
             }
+            lines
+        });
 
-            // Try to install cargo if not found
-            println!("Attempting to install Rust and Cargo...");
-            let install_result = Command::new("sh")
-                .arg("-c")
-                .arg("curl --proto '=https' --tlsv1.2 -sSf https://sh.rustup.rs | sh -s -- -y")
-                .output();
-
-            match install_result {
-                Ok(output) => {
-                    println!("Rust installation output: {}", String::from_utf8_lossy(&output.stdout));
-                    println!("Rust installation error: {}", String::from_utf8_lossy(&output.stderr));
-                },
-                Err(e) => println!("Failed to install Rust: {}", e)
+        // Wait for both streams to complete in parallel
+        let (stdout_result, stderr_result) = tokio::join!(stdout_handle, stderr_handle);
+        let stdout_lines = stdout_result.unwrap_or_default();
+        let stderr_lines = stderr_result.unwrap_or_default();
+
+        // Read the CPU time before the child is reaped; the wall time of the
+        // compile stands in where it cannot be read
+        let cpu_time = match pid {
+            Some(pid) => tokio::task::spawn_blocking(move || exited_cpu_time(pid)).await.ok().flatten(),
+            None => None,
+        };
+        let cpu_time = cpu_time.unwrap_or_else(|| compile_started.elapsed());
+
+        // Wait for the command to complete
+        let status = child.wait().await?;
+        let build_succeeded = stdout_lines.contains("Finished release") || stderr_lines.contains("Finished release");
+
+        // Instead of returning error, we return the stderr output along with the status
+        if !status.success() && !build_succeeded {
+            // Return the stderr output even on failure
+            return Ok(BuildOutput { stderr: stderr_lines, program_name: safe_program_name, cpu_time });
+        }
+
+        info!("Build command executed successfully.");
+
+        // Check if binary was created using safe program name
+        let binary_path = program_path
+            .join("target/deploy")
+            .join(format!("{}.so", safe_program_name));
+        debug!("Checking for binary at: {:?}", binary_path);
+
+        // After successful build, upload to GCS
+        if binary_path.exists() {
+            info!("Binary file created successfully");
+            if let Some(bucket) = self.gcs_bucket.clone() {
+                let binary_data = fs::read(&binary_path)?;
+                let uuid = uuid.to_string();
+                let safe_program_name = safe_program_name.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = upload_to_gcs(&bucket, &uuid, &safe_program_name, &binary_data).await {
+                        error!("Failed to upload binary to GCS: {}", e);
+                    }
+                });
             }
+        } else {
+            warn!("Binary file not found at expected location");
         }
-    } else {
-        println!("Failed to check if cargo is installed: {}", cargo_path.err().unwrap());
+
+        Ok(BuildOutput { stderr: stderr_lines, program_name: safe_program_name, cpu_time })
     }
 
-    // Check Cargo version to determine if we need the lockfile bump flag
-    println!("Checking Cargo version...");
-    let cargo_version_output = Command::new("cargo")
-        .arg("--version")
-        .output();
+    /// Records `owner` as the principal a build belongs to, so ownership
+    /// survives restarts of the in-memory build tracker.
+    pub fn set_owner(&self, uuid: &str, owner: &str) -> std::io::Result<()> {
+        let program_path = self.dir.as_path().join(uuid);
+        fs::create_dir_all(&program_path)?;
+        fs::write(program_path.join(OWNER_FILE), owner)?;
 
-    let needs_lockfile_bump = match cargo_version_output {
-        Ok(output) => {
-            if output.status.success() {
-                let cargo_version = String::from_utf8_lossy(&output.stdout);
-                println!("Detected Cargo version: {}", cargo_version);
-                cargo_version.contains("1.75")
-            } else {
-                println!("Failed to get Cargo version: {}", String::from_utf8_lossy(&output.stderr));
-                false
-            }
-        },
-        Err(e) => {
-            println!("Error checking Cargo version: {}", e);
-            false
+        if let Some(bucket) = self.gcs_bucket.clone() {
+            let uuid = uuid.to_string();
+            let owner = owner.to_string();
+            tokio::spawn(async move {
+                if let Err(e) = upload_owner_to_gcs(&bucket, &uuid, &owner).await {
+                    error!("Failed to upload build owner to GCS: {}", e);
+                }
+            });
         }
-    };
+        Ok(())
+    }
 
-    // Create string bindings with absolute paths
-    let manifest_path_str = program_path
-        .canonicalize()
-        .unwrap_or(program_path.clone())
-        .join("Cargo.toml")
-        .to_str()
-        .expect("Manifest path should be UTF-8")
-        .to_string();
-
-    let deploy_dir_str = program_path
-        .canonicalize()
-        .unwrap_or(program_path.clone())
-        .join("target/deploy")
-        .to_str()
-        .expect("Deploy directory path should be UTF-8")
-        .to_string();
-
-    let shared_target_str = shared_target
-        .canonicalize()
-        .unwrap_or(shared_target.clone())
-        .to_str()
-        .expect("Shared target directory path should be UTF-8")
-        .to_string();
-
-    // Verify paths exist
-    println!("Verifying paths exist:");
-    println!("Manifest path exists: {}", Path::new(&manifest_path_str).exists());
-    println!("Deploy dir exists: {}", Path::new(&deploy_dir_str).exists());
-    println!("Shared target exists: {}", Path::new(&shared_target_str).exists());
-
-    // Pre-build diagnostic: find who depends on getrandom
-    println!("Running 'cargo tree -i getrandom' to diagnose dependency source...");
-    let tree_diag_output = Command::new("cargo")
-        .args(["tree", "-i", "getrandom"]) // show inverse deps of getrandom
-        .current_dir(&program_path)
-        .output();
-
-    let mut getrandom_diag = String::new();
-    match tree_diag_output {
-        Ok(output) => {
-            let out = String::from_utf8_lossy(&output.stdout);
-            let err = String::from_utf8_lossy(&output.stderr);
-            println!("cargo tree (stdout):\n{}", out);
-            if !err.is_empty() { println!("cargo tree (stderr):\n{}", err); }
-            getrandom_diag.push_str("\n--- cargo tree -i getrandom ---\n");
-            getrandom_diag.push_str(&out);
-            if !err.is_empty() {
-                getrandom_diag.push_str("\n[stderr from cargo tree]\n");
-                getrandom_diag.push_str(&err);
-            }
-        },
-        Err(e) => {
-            let msg = format!("Failed to run cargo tree: {}", e);
-            println!("{}", msg);
-            getrandom_diag.push_str("\n--- cargo tree -i getrandom failed ---\n");
-            getrandom_diag.push_str(&msg);
-        }
-    }
-
-    // Update bytemuck to latest compatible (align with apl-associated-token-account)
-    println!("Running cargo update for bytemuck to >=1.20.0...");
-    let update_status = Command::new("cargo")
-        .args(["update", "-p", "bytemuck"]) // allow resolver to pick >=1.20
-        .current_dir(&program_path)
-        .output();
-
-    match update_status {
-        Ok(output) => {
-            println!("Cargo update output: {}", String::from_utf8_lossy(&output.stdout));
-            if !output.status.success() {
-                println!("Cargo update stderr: {}", String::from_utf8_lossy(&output.stderr));
-                println!("Warning: cargo update failed, but continuing with build anyway");
-            }
-        },
-        Err(e) => println!("Failed to run cargo update: {}", e),
+    /// The principal that owns a build, or `None` for unknown builds and ones
+    /// made before builds were scoped.
+    pub async fn owner(&self, uuid: &str) -> Option<String> {
+        if let Some(owner) = self.local_owner(uuid) {
+            return Some(owner);
+        }
+        let bucket = self.gcs_bucket.as_deref()?;
+        let object_name = format!("binaries/{}/{}", uuid, OWNER_FILE);
+        gcs_client()
+            .object()
+            .download(bucket, &object_name)
+            .await
+            .ok()
+            .and_then(|data| String::from_utf8(data).ok())
     }
 
-    // Check the Solana rust version
-    println!("Checking Solana rust version...");
-    let rustc_path = find_solana_rustc_path().unwrap_or_else(|| "rustc".to_string());
-    let solana_rust_version = Command::new(&rustc_path)
-        .arg("--version")
-        .output();
+    /// The owner recorded in a build's local directory.
+    pub fn local_owner(&self, uuid: &str) -> Option<String> {
+        fs::read_to_string(self.dir.as_path().join(uuid).join(OWNER_FILE)).ok()
+    }
 
-    match solana_rust_version {
-        Ok(output) => {
-            if output.status.success() {
-                println!("Solana rust version: {}", String::from_utf8_lossy(&output.stdout));
-            } else {
-                println!(
-                    "Failed to get Solana rust version using '{}': {}",
-                    rustc_path,
-                    String::from_utf8_lossy(&output.stderr)
-                );
+    /// Build project directories under `programs/` with the time they were last built.
+    pub fn build_dirs(&self) -> io::Result<Vec<(String, SystemTime)>> {
+        let mut dirs = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let metadata = entry.metadata()?;
+            if metadata.is_dir() && Uuid::try_parse(&name).is_ok() {
+                // The owner marker is rewritten on every build, the directory itself is not
+                let built = fs::metadata(entry.path().join(OWNER_FILE))
+                    .and_then(|owner| owner.modified())
+                    .or_else(|_| metadata.modified())?;
+                dirs.push((name, built));
             }
-        },
-        Err(e) => println!("Error checking Solana rust version: {}", e),
+        }
+        Ok(dirs)
     }
 
-    // Print absolute paths for debugging
-    println!("Using absolute paths:");
-    println!("Manifest path: {}", manifest_path_str);
-    println!("Deploy dir: {}", deploy_dir_str);
-    println!("Shared target: {}", shared_target_str);
-
-    // Build the args vector with absolute paths
-    let mut build_args = vec![
-        "build-sbf",
-        "--manifest-path",
-        &manifest_path_str,
-        "--sbf-out-dir",
-        &deploy_dir_str,
-    ];
+    /// Builds stored in the GCS bucket that have no local project directory,
+    /// e.g. because the container was replaced. Empty without a bucket.
+    pub async fn stored_only_builds(&self) -> anyhow::Result<Vec<StoredBuild>> {
+        let Some(bucket) = &self.gcs_bucket else {
+            return Ok(Vec::new());
+        };
+        let request = ListRequest { prefix: Some("binaries/".to_string()), ..Default::default() };
+        let pages: Vec<_> = gcs_client().object().list(bucket, request).await?.try_collect().await?;
+
+        let mut builds: HashMap<String, StoredBuild> = HashMap::new();
+        for object in pages.into_iter().flat_map(|page| page.items) {
+            let Some(uuid) = object.name.split('/').nth(1) else { continue };
+            if Uuid::try_parse(uuid).is_err() || self.dir.join(uuid).exists() {
+                continue;
+            }
+            let build = builds.entry(uuid.to_string()).or_insert_with(|| StoredBuild {
+                uuid: uuid.to_string(),
+                owner: None,
+                built: SystemTime::UNIX_EPOCH,
+                bytes: 0,
+            });
+            build.bytes += object.size;
+            // Every build rewrites the owner marker and uploads the binary
+            build.built = build.built.max(SystemTime::from(object.updated));
+        }
+        for build in builds.values_mut() {
+            build.owner = self.owner(&build.uuid).await;
+        }
+        Ok(builds.into_values().collect())
+    }
 
-    if needs_lockfile_bump {
-        println!("Adding lockfile bump flag to build args.");
-        build_args.extend(["--", "-Znext-lockfile-bump"]);
+    /// Deletes a build's project directory and, when using GCS, its stored artifacts.
+    pub async fn purge(&self, uuid: &str) -> anyhow::Result<()> {
+        let program_path = self.dir.as_path().join(uuid);
+        if program_path.exists() {
+            tokio::task::spawn_blocking(move || fs::remove_dir_all(program_path)).await??;
+        }
+        if let Some(bucket) = &self.gcs_bucket {
+            delete_from_gcs(bucket, uuid).await?;
+        }
+        Ok(())
     }
 
-    println!("Executing build command with args: {:?}", build_args);
-    println!("Current working directory: {:?}", std::env::current_dir()?);
+    /// Checks that built binaries can be stored: the GCS bucket when `USE_GCS` is
+    /// set, otherwise the local `programs/` directory.
+    pub async fn artifact_store(&self) -> ArtifactStore {
+        let (kind, result) = if let Some(bucket) = &self.gcs_bucket {
+            let request = ListRequest { prefix: Some("binaries/".to_string()), max_results: Some(1), ..Default::default() };
+            let result = match gcs_client().object().list(bucket, request).await {
+                Ok(pages) => Box::pin(pages).try_next().await.map(|_| ()).map_err(|e| e.to_string()),
+                Err(e) => Err(e.to_string()),
+            };
+            ("gcs", result)
+        } else {
+            let result = match fs::metadata(&self.dir) {
+                Ok(metadata) if metadata.permissions().readonly() => Err(format!("{} is read-only", self.dir.display())),
+                Ok(_) => Ok(()),
+                Err(e) => Err(e.to_string()),
+            };
+            ("local", result)
+        };
+        ArtifactStore { kind, reachable: result.is_ok(), error: result.err() }
+    }
 
-    // Make sure the cargo-build-sbf command is in PATH
-    let cargo_build_sbf_path = Command::new("which")
-        .arg("cargo-build-sbf")
-        .output();
+    /// Free space on the filesystem holding `programs/`, from `df`.
+    pub async fn available_disk_bytes(&self) -> Option<u64> {
+        let output = TokioCommand::new("df").arg("-Pk").arg(&self.dir).output().await.ok()?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        // Second line: filesystem, 1024-blocks, used, available, capacity, mount point
+        let available_kb: u64 = stdout.lines().nth(1)?.split_whitespace().nth(3)?.parse().ok()?;
+        Some(available_kb * 1024)
+    }
 
-    if let Ok(output) = cargo_build_sbf_path {
-        if !output.status.success() {
-            println!("cargo-build-sbf not found in PATH. This may cause build failures.");
-        } else {
-            println!("cargo-build-sbf found at: {}", String::from_utf8_lossy(&output.stdout));
-        }
-    }
-
-    let mut child = TokioCommand::new("cargo-build-sbf")
-        .args(&build_args)
-        .env("CARGO_TARGET_DIR", &shared_target_str)
-        .env("CARGO_HOME", &shared_cargo_home_str)  // Cache downloaded crates and registry!
-        .env("CARGO_BUILD_INCREMENTAL", "true")
-        .env("CARGO_PROFILE_RELEASE_INCREMENTAL", "true")
-        .env("CARGO_PROFILE_RELEASE_CODEGEN_UNITS", "256")
-        .env("RUST_LOG", "debug")
-        .env("RUST_BACKTRACE", "1")
-        .env("CARGO_PROFILE_RELEASE_BUILD_OVERRIDE_DEBUG", "false")
-        .env("CARGO_DEP_BYTEMUCK_DERIVE_VERSION", "1.5.0")
-        .current_dir(&program_path)  // Keep this to maintain relative path resolution
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()?;
-
-    let mut stdout_lines = String::new();
-    let mut stderr_lines = String::new();
-
-    // CRITICAL: Read stdout and stderr in PARALLEL to avoid deadlock
-    // If we read sequentially, the child process can hang if one buffer fills up
-    let stdout = child.stdout.take();
-    let stderr = child.stderr.take();
-
-    let stdout_handle = tokio::spawn(async move {
-        let mut lines = String::new();
-        if let Some(stdout) = stdout {
-            let mut reader = BufReader::new(stdout).lines();
-            while let Ok(Some(line)) = reader.next_line().await {
-                println!("stdout: {}", line);
-                lines.push_str(&line);
-                lines.push('\n');
-            }
-        }
-        lines
-    });
-
-    let stderr_handle = tokio::spawn(async move {
-        let mut lines = String::new();
-        if let Some(stderr) = stderr {
-            let mut reader = BufReader::new(stderr).lines();
-            while let Ok(Some(line)) = reader.next_line().await {
-                println!("stderr: {}", line);
-                lines.push_str(&line);
-                lines.push('\n');
-            }
-        }
-        lines
-    });
+    /// Walks `programs/`; slow on a large cache, so call it off the async runtime.
+    pub fn disk_usage(&self) -> io::Result<DiskUsage> {
+        let programs_dir = self.dir.as_path();
+        Ok(DiskUsage {
+            programs_bytes: dir_size(programs_dir),
+            target_bytes: dir_size(&programs_dir.join("target")),
+            cargo_home_bytes: dir_size(&programs_dir.join(".cargo")),
+            build_dirs: self.build_dirs()?.len(),
+        })
+    }
 
-    // Wait for both streams to complete in parallel
-    let (stdout_result, stderr_result) = tokio::join!(stdout_handle, stderr_handle);
-    stdout_lines = stdout_result.unwrap_or_default();
-    stderr_lines = stderr_result.unwrap_or_default();
+    pub fn build_dir_size(&self, uuid: &str) -> u64 {
+        dir_size(&self.dir.as_path().join(uuid))
+    }
 
-    // Wait for the command to complete
-    let status = child.wait().await?;
-    let build_succeeded = stdout_lines.contains("Finished release") || stderr_lines.contains("Finished release");
+    /// Shrinks the shared `programs/target` cache to `max_bytes` by dropping
+    /// incremental compilation state, or the whole cache if that is not enough.
+    /// Only call it holding the guard of `try_lock_target`. Returns the bytes freed.
+    pub fn prune_target(&self, max_bytes: u64) -> io::Result<u64> {
+        let target_dir = self.dir.as_path().join("target");
+        let before = dir_size(&target_dir);
+        if before <= max_bytes {
+            return Ok(0);
+        }
 
-    // Instead of returning error, we return the stderr output along with the status
-    if !status.success() && !build_succeeded {
-        // Include pre-build diagnostics to help identify the source of getrandom
-        if !getrandom_diag.is_empty() {
-            stderr_lines.push_str(&getrandom_diag);
-            stderr_lines.push('\n');
+        for dir in incremental_dirs(&target_dir) {
+            fs::remove_dir_all(dir)?;
         }
-        // Return the stderr output even on failure
-        return Ok((stderr_lines, safe_program_name));
+        if dir_size(&target_dir) > max_bytes {
+            warn!("Shared target dir still over budget without incremental state, clearing it");
+            fs::remove_dir_all(&target_dir)?;
+            fs::create_dir_all(&target_dir)?;
+        }
+        Ok(before.saturating_sub(dir_size(&target_dir)))
     }
 
-    println!("Build command executed successfully.");
-
-    // Check if binary was created using safe program name
-    let binary_path = program_path
-        .join("target/deploy")
-        .join(format!("{}.so", safe_program_name));
-    println!("Checking for binary at: {:?}", binary_path);
+    /// Size of a build's local binary, if the build produced one.
+    pub fn binary_size(&self, uuid: &str, program_name: &str) -> Option<u64> {
+        let safe_program_name = program_name.replace(|c: char| !c.is_alphanumeric(), "_");
+        let binary_path = self.dir.as_path()
+            .join(uuid)
+            .join("target/deploy")
+            .join(format!("{}.so", safe_program_name));
+        fs::metadata(binary_path).ok().map(|metadata| metadata.len())
+    }
 
-    // After successful build, upload to GCS
-    if binary_path.exists() {
-        println!("Binary file created successfully");
-        if use_gcs() {
-            let binary_data = fs::read(&binary_path)?;
-            let uuid = uuid.to_string();
-            let safe_program_name = safe_program_name.clone();
-            let binary_data = binary_data.to_vec();
-            tokio::spawn(async move {
-                if let Err(e) = upload_to_gcs(&uuid, &safe_program_name, &binary_data).await {
-                    eprintln!("Failed to upload binary to GCS: {}", e);
-                }
+    pub async fn get_binary(&self, uuid: &str, program_name: &str) -> std::io::Result<Vec<u8>> {
+        let safe_program_name = program_name.replace(|c: char| !c.is_alphanumeric(), "_");
+        let binary_filename = format!("{}.so", safe_program_name);
+
+        // First try to get from the exact UUID path
+        let program_path = self.dir.as_path()
+            .join(uuid)
+            .join("target/deploy")
+            .join(&binary_filename);
+
+        if program_path.exists() {
+            debug!("Reading binary from exact UUID path: {:?}", program_path);
+            return fs::read(program_path).map_err(|e| {
+                warn!("Failed to read local binary: {}", e);
+                e
             });
         }
-    } else {
-        println!("Warning: Binary file not found at expected location");
-    }
 
-    Ok((stderr_lines, safe_program_name))
+        // If not found locally, try to get from GCS
+        let Some(bucket) = &self.gcs_bucket else {
+            return Err(io::Error::new(io::ErrorKind::NotFound, format!("{} not found", binary_filename)));
+        };
+        debug!("Binary not found locally, attempting to fetch from GCS");
+        download_from_gcs(bucket, uuid, &safe_program_name)
+            .await
+            .map_err(|e| {
+                warn!("Failed to download binary from GCS: {}", e);
+                std::io::Error::other(e.to_string())
+            })
+    }
 }
 
-async fn upload_to_gcs(uuid: &str, program_name: &str, binary_data: &[u8]) -> anyhow::Result<()> {
-    if !use_gcs() {
-        return Ok(());
+/// Writes the warmup project of `profile`, discarding the binary and
+/// fingerprint of a previous run.
+fn prepare_warmup(warmup_dir: &Path, profile: &SdkProfile) -> io::Result<()> {
+    for stale in [warmup_dir.join("target/deploy/warmup.so"), warmup_dir.join(FINGERPRINT_FILE)] {
+        if stale.exists() {
+            fs::remove_file(stale)?;
+        }
     }
 
+    let src_dir = warmup_dir.join("src");
+    fs::create_dir_all(&src_dir)?;
+    fs::write(warmup_dir.join("Cargo.toml"), profile.manifest("warmup"))?;
+    fs::write(warmup_dir.join("Cargo.lock"), profile.lockfile)?;
+    fs::write(src_dir.join("lib.rs"), profile.warmup_source())?;
+    Ok(())
+}
+
+/// Cargo config replacing crates.io with the vendored crates in `crates`.
+fn vendored_sources_config(crates: &Path) -> String {
+    format!(
+        "[source.crates-io]\nreplace-with = \"vendored-sources\"\n\n\
+         [source.vendored-sources]\ndirectory = {}\n\n\
+         [net]\noffline = true\n",
+        toml::Value::from(crates.to_string_lossy().into_owned())
+    )
+}
+
+pub type Files = Vec<[String; 2]>;
+
+async fn upload_to_gcs(bucket: &str, uuid: &str, program_name: &str, binary_data: &[u8]) -> anyhow::Result<()> {
     info!("Starting GCS upload for program {} with UUID {}", program_name, uuid);
-    let client = GCS_CLIENT.get_or_init(|| {
-        info!("Initializing GCS client");
-        Client::default()
-    });
+    let client = gcs_client();
 
-    let bucket = get_gcs_bucket();
     let object_name = format!("binaries/{}/{}.so", uuid, program_name);
     info!("Uploading to GCS bucket {} with path {}", bucket, object_name);
 
     client.object().create(
-        &bucket,
+        bucket,
         binary_data.to_vec(),
         &object_name,
         "application/octet-stream",
@@ -686,73 +989,147 @@ async fn upload_to_gcs(uuid: &str, program_name: &str, binary_data: &[u8]) -> an
     Ok(())
 }
 
-async fn download_from_gcs(uuid: &str, program_name: &str) -> anyhow::Result<Vec<u8>> {
-    let client = Client::default();
-
-    let bucket = get_gcs_bucket();
+async fn download_from_gcs(bucket: &str, uuid: &str, program_name: &str) -> anyhow::Result<Vec<u8>> {
+    let client = gcs_client();
 
     let object_name = format!("binaries/{}/{}.so", uuid, program_name);
-    println!("Attempting to download from GCS: bucket={}, object={}", bucket, object_name);
+    debug!("Attempting to download from GCS: bucket={}, object={}", bucket, object_name);
 
-    match client.object().download(&bucket, &object_name).await {
+    match client.object().download(bucket, &object_name).await {
         Ok(data) => {
-            println!("Successfully downloaded binary from GCS, size: {} bytes", data.len());
+            info!("Successfully downloaded binary from GCS, size: {} bytes", data.len());
             Ok(data)
         },
         Err(e) => {
-            println!("Failed to download from GCS: {}", e);
+            warn!("Failed to download from GCS: {}", e);
             Err(anyhow::anyhow!("Failed to download from GCS: {}", e))
         }
     }
 }
 
-pub async fn get_binary(uuid: &str, program_name: &str) -> std::io::Result<Vec<u8>> {
-    let safe_program_name = program_name.replace(|c: char| !c.is_alphanumeric(), "_");
-    let binary_filename = format!("{}.so", safe_program_name);
-
-    // First try to get from the exact UUID path
-    let program_path = Path::new(PROGRAMS_DIR)
-        .join(uuid)
-        .join("target/deploy")
-        .join(&binary_filename);
-
-    if program_path.exists() {
-        println!("Reading binary from exact UUID path: {:?}", program_path);
-        return fs::read(program_path).map_err(|e| {
-            println!("Failed to read local binary: {}", e);
-            e
-        });
-    }
+async fn upload_owner_to_gcs(bucket: &str, uuid: &str, owner: &str) -> anyhow::Result<()> {
+    let object_name = format!("binaries/{}/{}", uuid, OWNER_FILE);
+    gcs_client()
+        .object()
+        .create(bucket, owner.as_bytes().to_vec(), &object_name, "text/plain")
+        .await?;
+    Ok(())
+}
 
-    // If not found, try to find the binary in any project directory
-    println!("Binary not found at exact UUID path, searching in all project directories");
-    let programs_dir = Path::new(PROGRAMS_DIR);
-
-    if let Ok(entries) = fs::read_dir(programs_dir) {
-        for entry in entries.flatten() {
-            if let Ok(file_type) = entry.file_type() {
-                if file_type.is_dir() {
-                    let potential_path = entry.path().join("target/deploy").join(&binary_filename);
-                    if potential_path.exists() {
-                        println!("Found binary in alternative location: {:?}", potential_path);
-                        return fs::read(potential_path).map_err(|e| {
-                            println!("Failed to read local binary: {}", e);
-                            e
-                        });
-                    }
-                }
-            }
+/// A build hit the shared dependency cache when cargo compiled nothing but the program itself.
+pub fn dependencies_cached(output: &str) -> bool {
+    output.lines().filter(|line| line.trim_start().starts_with("Compiling ")).count() <= 1
+}
+
+/// Waits for the child `pid` to exit without reaping it, then reads the CPU
+/// time it and the descendants it waited for used. Blocks, so run it off the
+/// runtime, and before the child is reaped.
+#[cfg(target_os = "linux")]
+fn exited_cpu_time(pid: u32) -> Option<Duration> {
+    loop {
+        // SAFETY: `info` is plain data for waitid to fill in; WNOWAIT leaves
+        // the child for `Child::wait` to reap
+        let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+        let waited = unsafe { libc::waitid(libc::P_PID, pid, &mut info, libc::WEXITED | libc::WNOWAIT) };
+        if waited == 0 {
+            break;
+        }
+        if io::Error::last_os_error().kind() != io::ErrorKind::Interrupted {
+            return None;
         }
     }
 
-    // If still not found locally, try to get from GCS
-    println!("Binary not found locally, attempting to fetch from GCS");
-    download_from_gcs(uuid, &safe_program_name)
-        .await
-        .map_err(|e| {
-            println!("Failed to download binary from GCS: {}", e);
-            std::io::Error::new(std::io::ErrorKind::Other, e.to_string())
+    // Fields after the parenthesized command name start with the state, field
+    // 3; utime, stime, cutime and cstime are fields 14 to 17, in clock ticks
+    let stat = fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+    let fields: Vec<_> = stat.rsplit_once(')')?.1.split_whitespace().collect();
+    let ticks = fields.get(11..15)?.iter().map(|field| field.parse::<u64>().ok()).sum::<Option<u64>>()?;
+    // SAFETY: sysconf only reads a configuration value
+    let ticks_per_sec = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+    (ticks_per_sec > 0).then(|| Duration::from_secs_f64(ticks as f64 / ticks_per_sec as f64))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn exited_cpu_time(_pid: u32) -> Option<Duration> {
+    None
+}
+
+async fn delete_from_gcs(bucket: &str, uuid: &str) -> anyhow::Result<()> {
+    let client = gcs_client();
+    let request = ListRequest { prefix: Some(format!("binaries/{}/", uuid)), ..Default::default() };
+    let pages: Vec<_> = client.object().list(bucket, request).await?.try_collect().await?;
+    for object in pages.into_iter().flat_map(|page| page.items) {
+        client.object().delete(bucket, &object.name).await?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ArtifactStore {
+    /// `gcs` or `local`
+    pub kind: &'static str,
+    pub reachable: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// What `Programs::build` produced; a failed compile is still `Ok`, with its errors in `stderr`.
+pub struct BuildOutput {
+    pub stderr: String,
+    /// Sanitized program name the binary is stored under
+    pub program_name: String,
+    /// CPU time of the compile, including every process it spawned
+    pub cpu_time: Duration,
+}
+
+/// A build known only from its artifacts in the GCS bucket.
+pub struct StoredBuild {
+    pub uuid: String,
+    pub owner: Option<String>,
+    /// When its newest object was written
+    pub built: SystemTime,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DiskUsage {
+    /// Everything under `programs/`, including the two entries below
+    pub programs_bytes: u64,
+    /// Shared incremental build cache, `programs/target`
+    pub target_bytes: u64,
+    /// Shared crate registry and sources, `programs/.cargo`
+    pub cargo_home_bytes: u64,
+    pub build_dirs: usize,
+}
+
+fn incremental_dirs(path: &Path) -> Vec<std::path::PathBuf> {
+    let Ok(entries) = fs::read_dir(path) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|file_type| file_type.is_dir()))
+        .flat_map(|entry| match entry.file_name() == "incremental" {
+            true => vec![entry.path()],
+            false => incremental_dirs(&entry.path()),
+        })
+        .collect()
+}
+
+/// Total size of the files under `path`. Symlinks are not followed and
+/// unreadable entries are skipped.
+fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => dir_size(&entry.path()),
+            Ok(_) => entry.metadata().map(|metadata| metadata.len()).unwrap_or(0),
+            Err(_) => 0,
         })
+        .sum()
 }
 
 // Instead, create a wrapper type for binary data
@@ -778,4 +1155,52 @@ impl IntoResponse for BinaryData {
         );
         response
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{BuildConfig, StorageConfig};
+
+    fn validate(files: &[(&str, usize)]) -> Result<(), Error> {
+        let build = BuildConfig { max_file_bytes: 100, max_files_total_bytes: 150, ..BuildConfig::default() };
+        let programs = Programs::new(&build, &StorageConfig::default(), Toolchain::default());
+        let files: Files = files.iter().map(|(path, size)| [path.to_string(), "x".repeat(*size)]).collect();
+        programs.validate_files(&files)
+    }
+
+    #[test]
+    fn accepts_sources_and_data_files_within_limits() {
+        let accepted =
+            ["/src/lib.rs", "/tests/deposit.rs", "/examples/mint.rs", "/README.md", "/src/idl.json", "/data/fixtures/a.csv"];
+        for path in accepted {
+            assert!(validate(&[(path, 10)]).is_ok(), "{path} rejected");
+        }
+
+        let rejected =
+            ["/Cargo.toml", "/Cargo.lock", "/.cargo/config.toml", "/src/../x.rs", "/target/x.json", "/src/.hidden.md", "/build.rs"];
+        for path in rejected {
+            assert_eq!(validate(&[(path, 10)]).unwrap_err().code(), ErrorCode::InvalidPath, "{path} accepted");
+        }
+
+        assert_eq!(validate(&[("/src/lib.rs", 101)]).unwrap_err().code(), ErrorCode::PayloadTooLarge);
+        assert_eq!(validate(&[("/src/lib.rs", 100), ("/README.md", 51)]).unwrap_err().code(), ErrorCode::PayloadTooLarge);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn cpu_time_counts_grandchildren_but_not_waiting() {
+        // A grandchild spins, then the child sleeps, which costs no CPU time
+        let script = "sh -c 'end=$(($(date +%s) + 2)); while [ $(date +%s) -lt $end ]; do :; done'; sleep 1";
+        let mut child = TokioCommand::new("sh").arg("-c").arg(script).spawn().unwrap();
+        let pid = child.id().unwrap();
+        let started = Instant::now();
+
+        let cpu_time = tokio::task::spawn_blocking(move || exited_cpu_time(pid)).await.unwrap().unwrap();
+        let elapsed = started.elapsed();
+        // The child is left for `wait` to reap
+        assert!(child.wait().await.unwrap().success());
+        assert!(cpu_time >= Duration::from_millis(200), "{cpu_time:?}");
+        assert!(cpu_time + Duration::from_millis(500) < elapsed, "{cpu_time:?} of {elapsed:?}");
+    }
+}
@@ -0,0 +1,208 @@
+//! Hot reload of the runtime-tunable parts of `Config`: RPC upstreams and
+//! method allowlists, Bitcoin RPC networks, quotas and a few request-time
+//! knobs. Everything else is fixed at startup; changes to it are reported
+//! and ignored until the next restart.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::Mutex;
+use tracing::{error, info, warn};
+use utoipa::ToSchema;
+
+use crate::{
+    config::{Config, ConfigError},
+    state::{AppState, RpcRules},
+};
+
+/// Top-level `Config` fields a reload applies.
+pub const RUNTIME_FIELDS: &[&str] = &[
+    "rpc_allowed_methods",
+    "rpc_upstreams",
+    "rpc_default_network",
+    "rpc_max_block_lag",
+    "rpc_max_stall_secs",
+    "rpc_faucet_networks",
+    "bitcoin_rpc",
+    "deploy_chunk_retries",
+    "deploy_callback_hosts",
+    "trust_forwarded_for",
+    "quota_anonymous",
+    "quota_authenticated",
+];
+
+/// Reloads triggered by the watcher and the admin route must not interleave.
+static RELOAD: Mutex<()> = Mutex::const_new(());
+
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct Change {
+    /// Dotted path of the setting, e.g. `rpc_upstreams.testnet`
+    pub field: String,
+    pub old: Option<Value>,
+    pub new: Option<Value>,
+}
+
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct ReloadReport {
+    pub applied: Vec<Change>,
+    /// Changes to settings only read at startup
+    pub requires_restart: Vec<Change>,
+}
+
+/// Loads the config again and swaps the runtime-tunable parts into `state`.
+/// An invalid config is rejected as a whole and the running one kept.
+pub async fn reload(state: &AppState) -> Result<ReloadReport, ConfigError> {
+    let _guard = RELOAD.lock().await;
+    let new = Config::load(state.config.path())?;
+    let current = state.config.current();
+
+    let mut report = ReloadReport::default();
+    for change in diff(&current, &new) {
+        let top = change.field.split('.').next().unwrap_or_default();
+        if RUNTIME_FIELDS.contains(&top) {
+            report.applied.push(change);
+        } else {
+            report.requires_restart.push(change);
+        }
+    }
+
+    let effective = current.with_runtime_settings(&new);
+    state.rpc.set_rules(RpcRules::from_config(&effective));
+    state
+        .rpc
+        .upstreams
+        .reconfigure(
+            &effective.rpc_upstreams,
+            effective.rpc_default_network.clone(),
+            effective.rpc_max_block_lag,
+            Duration::from_secs(effective.rpc_max_stall_secs),
+        )
+        .await;
+    state.usage.set_quotas(effective.quota_anonymous.clone(), effective.quota_authenticated.clone());
+    state.config.replace(effective);
+
+    for change in &report.applied {
+        info!(field = %change.field, old = %show(&change.old), new = %show(&change.new), "Config setting reloaded");
+    }
+    for change in &report.requires_restart {
+        warn!(field = %change.field, "Config setting changed but only takes effect after a restart");
+    }
+    Ok(report)
+}
+
+impl Config {
+    /// `self` with the fields in [`RUNTIME_FIELDS`] taken from `other`.
+    pub fn with_runtime_settings(&self, other: &Config) -> Config {
+        Config {
+            rpc_allowed_methods: other.rpc_allowed_methods.clone(),
+            rpc_upstreams: other.rpc_upstreams.clone(),
+            rpc_default_network: other.rpc_default_network.clone(),
+            rpc_max_block_lag: other.rpc_max_block_lag,
+            rpc_max_stall_secs: other.rpc_max_stall_secs,
+            rpc_faucet_networks: other.rpc_faucet_networks.clone(),
+            bitcoin_rpc: other.bitcoin_rpc.clone(),
+            deploy_chunk_retries: other.deploy_chunk_retries,
+            deploy_callback_hosts: other.deploy_callback_hosts.clone(),
+            trust_forwarded_for: other.trust_forwarded_for,
+            quota_anonymous: other.quota_anonymous.clone(),
+            quota_authenticated: other.quota_authenticated.clone(),
+            ..self.clone()
+        }
+    }
+}
+
+/// Settings that differ between `old` and `new`, compared leaf by leaf.
+/// Secrets serialize redacted, so a changed password shows up as no change.
+pub fn diff(old: &Config, new: &Config) -> Vec<Change> {
+    let (old, new) = (flatten(old), flatten(new));
+    let mut fields: Vec<&String> = old.keys().chain(new.keys()).collect();
+    fields.sort();
+    fields.dedup();
+    fields
+        .into_iter()
+        .filter(|field| old.get(*field) != new.get(*field))
+        .map(|field| Change { field: field.clone(), old: old.get(field).cloned(), new: new.get(field).cloned() })
+        .collect()
+}
+
+fn show(value: &Option<Value>) -> String {
+    match value {
+        None | Some(Value::Null) => "unset".to_string(),
+        Some(value) => value.to_string(),
+    }
+}
+
+fn flatten(config: &Config) -> BTreeMap<String, Value> {
+    fn walk(prefix: String, value: Value, out: &mut BTreeMap<String, Value>) {
+        match value {
+            Value::Object(map) if !map.is_empty() => {
+                for (key, value) in map {
+                    let path = if prefix.is_empty() { key } else { format!("{prefix}.{key}") };
+                    walk(path, value, out);
+                }
+            }
+            value => {
+                out.insert(prefix, value);
+            }
+        }
+    }
+    let mut out = BTreeMap::new();
+    walk(String::new(), serde_json::to_value(config).expect("config serializes to JSON"), &mut out);
+    out
+}
+
+/// Reloads whenever the config file's modification time changes, checking
+/// every `interval`.
+pub fn watch(state: AppState, path: PathBuf, interval: Duration) {
+    tokio::spawn(async move {
+        info!("Watching {} for config changes every {}s", path.display(), interval.as_secs());
+        let mut last_modified = modified(&path);
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            let modified = modified(&path);
+            if modified == last_modified {
+                continue;
+            }
+            last_modified = modified;
+            match reload(&state).await {
+                Ok(report) => info!(
+                    applied = report.applied.len(),
+                    requires_restart = report.requires_restart.len(),
+                    "Config file changed, reloaded"
+                ),
+                Err(e) => error!("Config file changed but was rejected, keeping the running config: {}", e),
+            }
+        }
+    });
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_reports_leaves_and_only_runtime_settings_are_taken() {
+        let old = Config::default();
+        let mut new = Config { port: 9000, ..Default::default() };
+        new.rpc_upstreams.insert("devnet".to_string(), vec!["http://127.0.0.1:9002".to_string()]);
+        new.quota_anonymous.builds_per_day = Some(5);
+
+        let fields: Vec<String> = diff(&old, &new).into_iter().map(|change| change.field).collect();
+        assert_eq!(fields, ["port", "quota_anonymous.builds_per_day", "rpc_upstreams.devnet"]);
+
+        let effective = old.with_runtime_settings(&new);
+        assert_eq!(effective.port, 8080);
+        assert_eq!(effective.quota_anonymous.builds_per_day, Some(5));
+        assert!(effective.rpc_upstreams.contains_key("devnet"));
+    }
+}
@@ -0,0 +1,273 @@
+use std::time::{Duration, SystemTime};
+
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{
+    auth::Principal,
+    build_tracker::{BuildStatus, BuildTracker},
+    error::{Error, Result},
+    extract::Json,
+    program::Programs,
+    reload,
+    state::AppState,
+};
+
+#[derive(Deserialize, IntoParams)]
+pub struct ListBuildsQuery {
+    /// `queued`, `building`, `success`, `failed`, ...
+    status: Option<String>,
+    owner: Option<String>,
+    /// Only builds started at or after this RFC 3339 time
+    since: Option<DateTime<Utc>>,
+    /// At most this many builds, 100 by default
+    limit: Option<usize>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BuildSummary {
+    uuid: String,
+    program_name: String,
+    owner: String,
+    status: BuildStatus,
+    started_at: DateTime<Utc>,
+    completed_at: Option<DateTime<Utc>>,
+    deploying: bool,
+}
+
+/// Builds known to this instance, newest first.
+#[utoipa::path(
+    get,
+    path = "/v1/admin/builds",
+    tag = "admin",
+    params(ListBuildsQuery),
+    responses(
+        (status = 200, description = "Matching builds", body = Vec<BuildSummary>),
+        (status = 403, description = "Not an admin", body = ErrorEnvelope),
+    )
+)]
+pub async fn list_builds(
+    State(tracker): State<BuildTracker>,
+    Extension(principal): Extension<Principal>,
+    Query(query): Query<ListBuildsQuery>,
+) -> Result<impl IntoResponse> {
+    principal.require_admin()?;
+
+    let mut builds = tracker.list_builds().await;
+    builds.retain(|info| {
+        query.status.as_deref().is_none_or(|status| format!("{:?}", info.status).eq_ignore_ascii_case(status))
+            && query.owner.as_deref().is_none_or(|owner| info.owner == owner)
+            && query.since.is_none_or(|since| info.started_at >= since)
+    });
+    builds.sort_by_key(|info| std::cmp::Reverse(info.started_at));
+    builds.truncate(query.limit.unwrap_or(100));
+
+    let builds: Vec<_> = builds
+        .into_iter()
+        .map(|info| BuildSummary {
+            deploying: info.deployment.is_some_and(|d| d.completed_at.is_none()),
+            uuid: info.uuid,
+            program_name: info.program_name,
+            owner: info.owner,
+            status: info.status,
+            started_at: info.started_at,
+            completed_at: info.completed_at,
+        })
+        .collect();
+    Ok(Json(builds))
+}
+
+/// Stop a running build and kill its toolchain process.
+#[utoipa::path(
+    post,
+    path = "/v1/admin/builds/{uuid}/cancel",
+    tag = "admin",
+    params(("uuid" = String, Path, description = "Build UUID")),
+    responses(
+        (status = 204, description = "Cancelled"),
+        (status = 403, description = "Not an admin", body = ErrorEnvelope),
+        (status = 404, description = "No running build with this UUID", body = ErrorEnvelope),
+    )
+)]
+pub async fn cancel_build(
+    State(tracker): State<BuildTracker>,
+    Extension(principal): Extension<Principal>,
+    Path(uuid): Path<String>,
+) -> Result<impl IntoResponse> {
+    principal.require_admin()?;
+
+    if !tracker.cancel_build(&uuid, &format!("Build cancelled by {}", principal.id)).await {
+        return Err(Error::NotFound(format!("Running build {}", uuid)));
+    }
+    info!("Build {} cancelled by {}", uuid, principal.id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct PurgeRequest {
+    uuid: Option<String>,
+    /// Purge every build whose project directory is older than this
+    older_than_hours: Option<u64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PurgeResponse {
+    purged: Vec<String>,
+    /// Builds that matched but could not be deleted
+    failed: Vec<String>,
+}
+
+/// Delete build directories, stored artifacts and tracker entries, by uuid or by age.
+/// Builds still building or deploying are never purged.
+#[utoipa::path(
+    post,
+    path = "/v1/admin/artifacts/purge",
+    tag = "admin",
+    request_body = PurgeRequest,
+    responses(
+        (status = 200, description = "What was purged", body = PurgeResponse),
+        (status = 400, description = "Neither or both of `uuid` and `older_than_hours`", body = ErrorEnvelope),
+        (status = 403, description = "Not an admin", body = ErrorEnvelope),
+    )
+)]
+pub async fn purge_artifacts(
+    State(tracker): State<BuildTracker>,
+    State(programs): State<Programs>,
+    Extension(principal): Extension<Principal>,
+    Json(payload): Json<PurgeRequest>,
+) -> Result<impl IntoResponse> {
+    principal.require_admin()?;
+
+    let uuids = match (payload.uuid, payload.older_than_hours) {
+        (Some(uuid), None) => {
+            uuid::Uuid::try_parse(&uuid).map_err(|_| Error::BadRequest("Invalid UUID".to_string()))?;
+            vec![uuid]
+        }
+        (None, Some(hours)) => {
+            let cutoff = SystemTime::now() - Duration::from_secs(hours * 3600);
+            programs.build_dirs()
+                .map_err(anyhow::Error::from)?
+                .into_iter()
+                .filter(|(_, modified)| *modified < cutoff)
+                .map(|(uuid, _)| uuid)
+                .collect()
+        }
+        _ => return Err(Error::BadRequest("Specify exactly one of 'uuid' or 'older_than_hours'".to_string())),
+    };
+
+    let mut response = PurgeResponse { purged: Vec::new(), failed: Vec::new() };
+    for uuid in uuids {
+        match tracker.purge_idle(&uuid, programs.purge(&uuid)).await {
+            Some(Ok(())) => response.purged.push(uuid),
+            Some(Err(e)) => {
+                warn!("Failed to purge build {}: {}", uuid, e);
+                response.failed.push(uuid);
+            }
+            None => response.failed.push(uuid),
+        }
+    }
+    info!("{} purged {} builds", principal.id, response.purged.len());
+    Ok(Json(response))
+}
+
+/// Discard the warmup builds and pre-compile the dependencies of every
+/// configured SDK profile again.
+#[utoipa::path(
+    post,
+    path = "/v1/admin/warmup",
+    tag = "admin",
+    responses(
+        (status = 202, description = "Warmup started; follow it in `/health/details`"),
+        (status = 403, description = "Not an admin", body = ErrorEnvelope),
+        (status = 409, description = "Warmup is already running", body = ErrorEnvelope),
+    )
+)]
+pub async fn rerun_warmup(
+    State(programs): State<Programs>,
+    Extension(principal): Extension<Principal>,
+) -> Result<impl IntoResponse> {
+    principal.require_admin()?;
+
+    if !programs.warmup(true).await? {
+        return Err(Error::Conflict("Warmup is already running".to_string()));
+    }
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// Vendor the packages of every SDK profile's lockfile into the mirror
+/// offline builds use. Needs the network; blocks until `cargo vendor` is done.
+#[utoipa::path(
+    post,
+    path = "/v1/admin/vendor/refresh",
+    tag = "admin",
+    responses(
+        (status = 200, description = "The refreshed mirror", body = VendorMirror),
+        (status = 403, description = "Not an admin", body = ErrorEnvelope),
+        (status = 409, description = "A refresh is already running", body = ErrorEnvelope),
+    )
+)]
+pub async fn refresh_vendor(
+    State(programs): State<Programs>,
+    Extension(principal): Extension<Principal>,
+) -> Result<impl IntoResponse> {
+    principal.require_admin()?;
+
+    let Some(mirror) = programs.refresh_vendor().await? else {
+        return Err(Error::Conflict("A vendor refresh is already running".to_string()));
+    };
+    info!("{} refreshed the vendored mirror", principal.id);
+    Ok(Json(mirror))
+}
+
+/// Disk used by build directories and the shared caches.
+#[utoipa::path(
+    get,
+    path = "/v1/admin/disk",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Disk usage", body = DiskUsage),
+        (status = 403, description = "Not an admin", body = ErrorEnvelope),
+    )
+)]
+pub async fn disk_usage(
+    State(programs): State<Programs>,
+    Extension(principal): Extension<Principal>,
+) -> Result<impl IntoResponse> {
+    principal.require_admin()?;
+
+    let usage = tokio::task::spawn_blocking(move || programs.disk_usage())
+        .await
+        .map_err(anyhow::Error::from)?
+        .map_err(anyhow::Error::from)?;
+    Ok(Json(usage))
+}
+
+/// Re-read the config file and environment and apply the runtime-tunable settings.
+/// An invalid config is rejected and the running one kept.
+#[utoipa::path(
+    post,
+    path = "/v1/admin/config/reload",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Settings applied and those needing a restart", body = ReloadReport),
+        (status = 400, description = "The new config is invalid", body = ErrorEnvelope),
+        (status = 403, description = "Not an admin", body = ErrorEnvelope),
+    )
+)]
+pub async fn reload_config(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+) -> Result<impl IntoResponse> {
+    principal.require_admin()?;
+
+    let report = reload::reload(&state).await.map_err(|e| Error::BadRequest(e.to_string()))?;
+    info!("{} reloaded the config, {} settings changed", principal.id, report.applied.len());
+    Ok(Json(report))
+}
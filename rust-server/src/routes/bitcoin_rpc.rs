@@ -0,0 +1,100 @@
+use std::time::Instant;
+
+use axum::{
+    extract::{Extension, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tracing::{error, info};
+use utoipa::ToSchema;
+
+use crate::{auth::Principal, error::{Error, ErrorCode, Result}, extract::Json, metrics::metrics, state::RpcState, usage::UsageTracker};
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct BitcoinRpcRequest {
+    network: String,
+    method: String,
+    #[serde(default)]
+    params: Vec<Value>,
+    /// Route the call to `/wallet/<name>` on the node
+    wallet: Option<String>,
+}
+
+/// Proxy a Bitcoin Core JSON-RPC call using the credentials configured for `network`
+#[utoipa::path(
+    post,
+    path = "/v1/bitcoin/rpc",
+    tag = "rpc",
+    request_body = BitcoinRpcRequest,
+    responses(
+        (status = 200, description = "The node's JSON-RPC response; node errors keep their status code", body = Object),
+        (status = 400, description = "Invalid request or wallet name", body = ErrorEnvelope),
+        (status = 403, description = "Method not on the allowlist", body = ErrorEnvelope),
+        (status = 404, description = "Unknown network", body = ErrorEnvelope),
+        (status = 429, description = "RPC quota exceeded", body = ErrorEnvelope),
+        (status = 502, description = "Node unreachable or answered garbage", body = ErrorEnvelope),
+        (status = 504, description = "Node timed out", body = ErrorEnvelope),
+    )
+)]
+pub async fn bitcoin_rpc(
+    State(rpc): State<RpcState>,
+    State(usage): State<UsageTracker>,
+    Extension(principal): Extension<Principal>,
+    Json(payload): Json<BitcoinRpcRequest>,
+) -> Result<impl IntoResponse> {
+    let rules = rpc.rules();
+    let network = rules
+        .bitcoin
+        .get(&payload.network)
+        .ok_or_else(|| {
+            Error::coded(ErrorCode::NetworkNotFound, format!("Bitcoin network '{}' not found", payload.network))
+        })?;
+
+    network.policy.check_method(&payload.method)?;
+    usage.rpc_calls(&principal, 1)?;
+
+    let url = match &payload.wallet {
+        Some(wallet) if wallet.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-') => {
+            format!("{}/wallet/{}", network.config.url.trim_end_matches('/'), wallet)
+        }
+        Some(_) => {
+            return Err(Error::BadRequest("Invalid wallet name".to_string())
+                .with_detail("wallet", "only letters, digits, '_' and '-' are allowed"))
+        }
+        None => network.config.url.clone(),
+    };
+
+    info!("Proxying Bitcoin RPC '{}' to network '{}'", payload.method, payload.network);
+
+    let start = Instant::now();
+    let result = rpc.client
+        .post(&url)
+        .basic_auth(&network.config.username, Some(&network.config.password.0))
+        .json(&json!({
+            "jsonrpc": "2.0",
+            "id": "arch",
+            "method": payload.method,
+            "params": payload.params,
+        }))
+        .send()
+        .await;
+    let ok = result.as_ref().is_ok_and(|response| !response.status().is_server_error());
+    metrics().rpc_attempt(&format!("bitcoin:{}", payload.network), 0, &payload.method, start.elapsed(), ok);
+
+    let response = result.map_err(|e| {
+        error!("Failed to send Bitcoin RPC request: {}", e);
+        Error::upstream("Failed to connect to Bitcoin node", e.is_timeout())
+    })?;
+
+    let status = response.status();
+    // bitcoind reports RPC errors as JSON with a 4xx/5xx status, so pass the body through
+    let body: Value = response.json().await.map_err(|e| {
+        error!("Failed to read Bitcoin RPC response (status {}): {}", status, e);
+        Error::BadGateway("Invalid response from Bitcoin node".to_string())
+    })?;
+
+    let status = StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+    Ok((status, Json(body)))
+}
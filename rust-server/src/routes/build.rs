@@ -1,46 +1,130 @@
-use anyhow::anyhow;
-use axum::{extract::{Json, Path, State}, response::IntoResponse, http::{StatusCode, HeaderMap, header}};
+use axum::{extract::{Extension, Path, State}, response::{IntoResponse, Response}, http::{StatusCode, HeaderMap, header}};
 use serde::{Deserialize, Serialize};
+use tracing::{error, info, info_span, Instrument};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
-use crate::{build_tracker::BuildTracker, error::Result, program::{self, Files}};
+use crate::{
+    auth::{Principal, ANONYMOUS},
+    compat::ApiVersion,
+    build_tracker::{BuildStatus, BuildTracker, DeploymentInfo},
+    error::{Error, ErrorCode, Result},
+    extract::Json,
+    program::{self, Files, Programs},
+    metrics::metrics,
+    usage::UsageTracker,
+};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct BuildRequest {
     program_name: String,
+    /// `[path, content]` pairs. Paths are `.rs` files under `/src/`, `/tests/`,
+    /// `/benches/` or `/examples/`, or `.json`, `.md`, `.txt`, `.csv` and `.yaml`
+    /// data files at the root or under those directories and `/data/`. Each
+    /// file and all files together are capped in size.
+    #[schema(value_type = Vec<Vec<String>>, example = json!([["/src/lib.rs", "use arch_program::..."]]))]
     files: Files,
+    /// Rebuild an existing build in place instead of starting a new one
     uuid: Option<String>,
 }
 
-#[derive(Serialize)]
-struct BuildResponse {
+#[derive(Serialize, ToSchema)]
+pub struct BuildResponse {
     uuid: String,
     program_name: String,
     status: String,
 }
 
-#[derive(Serialize)]
-struct BuildStatusResponse {
+#[derive(Serialize, ToSchema)]
+pub struct BuildStatusResponse {
     uuid: String,
     program_name: String,
+    /// `queued`, `building`, `success`, `failed` or `cancelled`
     status: String,
     stderr: Option<String>,
     started_at: String,
     completed_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deployment: Option<DeploymentInfo>,
 }
 
+/// Owner of a build, from the tracker or, after a restart, from its artifacts.
+/// Builds made before builds were scoped belong to the bare anonymous id,
+/// which only admins can reach.
+async fn build_owner(tracker: &BuildTracker, programs: &Programs, uuid: &str) -> Option<String> {
+    match tracker.get_build(uuid).await {
+        Some(info) => Some(info.owner),
+        None => programs.owner(uuid).await,
+    }
+}
+
+/// Builds and their artifacts are only visible to the principal that started them
+/// (and admins); anyone else gets the same answer as for a missing build.
+pub async fn authorize_build(
+    tracker: &BuildTracker,
+    programs: &Programs,
+    principal: &Principal,
+    uuid: &str,
+) -> Result<()> {
+    let not_found = || Error::coded(ErrorCode::BuildNotFound, format!("Build {} not found", uuid));
+    if Uuid::try_parse(uuid).is_err() {
+        return Err(not_found());
+    }
+    let owner = build_owner(tracker, programs, uuid).await;
+    if principal.can_access(owner.as_deref().unwrap_or(ANONYMOUS)) {
+        Ok(())
+    } else {
+        Err(not_found())
+    }
+}
+
+/// Start building a program in the background; poll `/build/status/:uuid` for the result.
+#[utoipa::path(
+    post,
+    path = "/v1/build",
+    tag = "build",
+    request_body = BuildRequest,
+    responses(
+        (status = 200, description = "Build started", body = BuildResponse),
+        (status = 400, description = "Invalid files or UUID", body = ErrorEnvelope),
+        (status = 413, description = "A file or all files together are too large", body = ErrorEnvelope),
+        (status = 404, description = "The UUID belongs to someone else's build", body = ErrorEnvelope),
+        (status = 409, description = "A build or deployment of this UUID is still running", body = ErrorEnvelope),
+        (status = 429, description = "Build quota exceeded", body = ErrorEnvelope),
+        (status = 503, description = "Server is shutting down", body = ErrorEnvelope),
+    )
+)]
 pub async fn build(
     State(tracker): State<BuildTracker>,
+    State(programs): State<Programs>,
+    State(usage): State<UsageTracker>,
+    Extension(principal): Extension<Principal>,
+    version: ApiVersion,
     Json(payload): Json<BuildRequest>,
 ) -> Result<impl IntoResponse> {
+    if tracker.is_draining() {
+        return Err(Error::coded(ErrorCode::ShuttingDown, "Server is shutting down, retry shortly"));
+    }
+
     let uuid = match payload.uuid {
         Some(uuid) => {
             Uuid::try_parse(&uuid)
                 .map(|_| uuid)
-                .map_err(|_| anyhow!("Invalid UUID"))?
+                .map_err(|_| Error::coded(ErrorCode::InvalidUuid, "Invalid UUID").with_detail("uuid", "must be a UUID"))?
         },
         None => Uuid::new_v4().to_string(),
     };
+    programs.validate_files(&payload.files)?;
+
+    // Rebuilding an existing UUID keeps its owner
+    let owner = match build_owner(&tracker, &programs, &uuid).await {
+        Some(owner) if !principal.can_access(&owner) => {
+            return Err(Error::coded(ErrorCode::BuildNotFound, format!("Build {} not found", uuid)));
+        }
+        Some(owner) => owner,
+        None => principal.usage_key(),
+    };
+    programs.set_owner(&uuid, &owner).map_err(anyhow::Error::from)?;
 
     let files = payload.files;
     let program_name = payload.program_name.clone();
@@ -48,58 +132,91 @@ pub async fn build(
     let tracker_clone = tracker.clone();
 
     // Start tracking the build
-    tracker.start_build(uuid.clone(), program_name.clone()).await;
+    if !tracker.start_build(uuid.clone(), program_name.clone(), owner).await {
+        return Err(Error::coded(ErrorCode::BuildInProgress, format!("Build {} is still running or being removed", uuid)));
+    }
+    // Charged last, so a build refused for any other reason costs nothing
+    if let Err(e) = usage.start_build(&principal) {
+        tracker.cancel_build(&uuid, "Build quota exceeded").await;
+        return Err(e);
+    }
 
-    // Spawn the build task in the background
-    tokio::spawn(async move {
-        println!("[BUILD] Starting background build task for UUID: {}", uuid_clone);
+    // Spawn the build task in the background; its span nests under the request's
+    let span = info_span!("build", build_uuid = %uuid, program = %program_name);
+    let task = tokio::spawn(async move {
+        let _slot = programs.build_slot().await;
+        if !tracker_clone.mark_building(&uuid_clone).await {
+            return;
+        }
+        info!("Starting background build task");
 
-        let result = program::build(&uuid_clone, &program_name, &files).await;
-        println!("[BUILD] Build function returned for UUID: {}", uuid_clone);
+        let started = std::time::Instant::now();
+        let result = programs.build(&uuid_clone, &program_name, &files).await;
 
-        match result {
-            Ok((stderr, final_program_name)) => {
-                println!("[BUILD] Build Ok for UUID: {}", uuid_clone);
-                println!("[BUILD] stderr length: {} bytes", stderr.len());
-                println!("[BUILD] stderr contains 'Finished': {}", stderr.contains("Finished"));
-                println!("[BUILD] stderr contains 'release': {}", stderr.contains("release"));
-                println!("[BUILD] stderr contains '`release`': {}", stderr.contains("`release`"));
-                println!("[BUILD] stderr contains 'error: could not compile': {}", stderr.contains("error: could not compile"));
+        // The build is charged to whoever ran it, even when rebuilding someone else's UUID as an admin,
+        // for the CPU time it used rather than the time it spent waiting for a slot or the target dir
+        let (cpu_time, artifact_bytes) = match &result {
+            Ok(output) => (output.cpu_time, programs.binary_size(&uuid_clone, &output.program_name).unwrap_or(0)),
+            Err(_) => (std::time::Duration::ZERO, 0),
+        };
+        usage.finish_build(&principal, cpu_time.as_secs(), artifact_bytes);
 
+        match result {
+            Ok(program::BuildOutput { stderr, program_name: final_program_name, .. }) => {
                 // Check if build actually succeeded by looking for compilation success indicators
                 let build_succeeded = stderr.contains("Finished") &&
                                      (stderr.contains("release") || stderr.contains("`release`")) &&
                                      !stderr.contains("error: could not compile");
 
-                println!("[BUILD] build_succeeded: {}", build_succeeded);
-                println!("[BUILD] Calling complete_build for UUID: {} with status: {}", uuid_clone, if build_succeeded { "Success" } else { "Failed" });
-
-                tracker_clone.complete_build(&uuid_clone, stderr, final_program_name, build_succeeded).await;
-
-                println!("[BUILD] complete_build finished for UUID: {}", uuid_clone);
+                info!(
+                    success = build_succeeded,
+                    elapsed_secs = started.elapsed().as_secs_f64(),
+                    cpu_secs = cpu_time.as_secs_f64(),
+                    artifact_bytes,
+                    "Build finished"
+                );
+                let cache_hit = program::dependencies_cached(&stderr);
+                if tracker_clone.complete_build(&uuid_clone, stderr, final_program_name, build_succeeded).await {
+                    metrics().build_finished(build_succeeded, started.elapsed(), Some(cache_hit), artifact_bytes);
+                }
             },
             Err(e) => {
-                println!("[BUILD] Build Err for UUID: {}, error: {}", uuid_clone, e);
+                error!("Build failed: {}", e);
                 let error_msg = format!("Build failed: {}", e);
-                tracker_clone.complete_build(&uuid_clone, error_msg, program_name, false).await;
-                println!("[BUILD] complete_build (error) finished for UUID: {}", uuid_clone);
+                if tracker_clone.complete_build(&uuid_clone, error_msg, program_name, false).await {
+                    metrics().build_finished(false, started.elapsed(), None, 0);
+                }
             }
         }
-
-        println!("[BUILD] Background task completed for UUID: {}", uuid_clone);
-    });
+    }.instrument(span));
+    tracker.set_task(&uuid, task.abort_handle()).await;
 
     Ok(Json(BuildResponse {
         uuid,
         program_name: payload.program_name,
-        status: "building".to_string(),
+        // Clients of the legacy schema only know `building`
+        status: if version == ApiVersion::Legacy { "building" } else { "queued" }.to_string(),
     }))
 }
 
+/// Status of a build. A missing build is a `BUILD_NOT_FOUND` error; the
+/// `legacy` schema answers with a `not_found` pseudo-status instead.
+#[utoipa::path(
+    get,
+    path = "/v1/build/status/{uuid}",
+    tag = "build",
+    params(("uuid" = String, Path, description = "Build UUID")),
+    responses(
+        (status = 200, description = "Build found", body = BuildStatusResponse),
+        (status = 404, description = "No such build", body = ErrorEnvelope),
+    )
+)]
 pub async fn build_status(
     State(tracker): State<BuildTracker>,
+    Extension(principal): Extension<Principal>,
+    version: ApiVersion,
     Path(uuid): Path<String>,
-) -> Result<impl IntoResponse> {
+) -> Result<Response> {
     // CRITICAL: Add no-cache headers to prevent CloudFront/browser caching
     // Build status must ALWAYS be fresh since it changes from "building" -> "success"/"failed"
     let mut headers = HeaderMap::new();
@@ -107,20 +224,26 @@ pub async fn build_status(
     headers.insert(header::PRAGMA, "no-cache".parse().unwrap());
     headers.insert(header::EXPIRES, "0".parse().unwrap());
 
-    match tracker.get_build(&uuid).await {
+    match tracker.get_build(&uuid).await.filter(|info| principal.can_access(&info.owner)) {
         Some(info) => Ok((
             StatusCode::OK,
             headers,
             Json(BuildStatusResponse {
                 uuid: info.uuid,
                 program_name: info.program_name,
-                status: format!("{:?}", info.status).to_lowercase(),
+                status: match info.status {
+                    // Clients of the legacy schema only know `building`
+                    BuildStatus::Queued if version == ApiVersion::Legacy => "building".to_string(),
+                    status => format!("{:?}", status).to_lowercase(),
+                },
                 stderr: info.stderr,
                 started_at: info.started_at.to_rfc3339(),
                 completed_at: info.completed_at.map(|dt| dt.to_rfc3339()),
+                deployment: info.deployment,
             }),
-        )),
-        None => Ok((
+        )
+            .into_response()),
+        None if version == ApiVersion::Legacy => Ok((
             StatusCode::NOT_FOUND,
             headers,
             Json(BuildStatusResponse {
@@ -130,20 +253,18 @@ pub async fn build_status(
                 stderr: Some("Build not found".to_string()),
                 started_at: chrono::Utc::now().to_rfc3339(),
                 completed_at: None,
+                deployment: None,
             }),
-        )),
+        )
+            .into_response()),
+        None => Ok((headers, Error::coded(ErrorCode::BuildNotFound, format!("Build {} not found", uuid))).into_response()),
     }
 }
 
-/// Handle OPTIONS preflight requests for build_status
+/// Handle OPTIONS requests for build_status; CORS headers come from the `cors` layer
 pub async fn build_status_options() -> impl IntoResponse {
     (
         StatusCode::OK,
-        [
-            ("Access-Control-Allow-Origin", "*"),
-            ("Access-Control-Allow-Methods", "GET, OPTIONS"),
-            ("Access-Control-Allow-Headers", "Content-Type, Accept, Cache-Control, Pragma"),
-            ("Access-Control-Max-Age", "3600"),
-        ],
+        [("Allow", "GET, OPTIONS")],
     )
 }
\ No newline at end of file
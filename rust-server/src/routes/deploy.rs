@@ -1,25 +1,53 @@
-use anyhow::anyhow;
-use axum::{extract::Path, response::IntoResponse};
-use tokio::io;
-use serde::Deserialize;
+use std::time::Duration;
 
-use crate::{error::Result, program::{self, BinaryData}};
+use axum::{extract::{Extension, Path, State}, http::StatusCode, response::IntoResponse};
+use bitcoin::{key::Keypair, secp256k1::Secp256k1};
+use serde::{Deserialize, Serialize};
+use tokio::io;
+use tracing::{info_span, Instrument};
+use utoipa::ToSchema;
 
-#[derive(Deserialize)]
-pub struct DeployParams {
-    uuid: String,
-    program_name: String,
-}
+use super::authorize_build;
+use crate::{
+    arch_rpc::ArchRpcClient,
+    auth::Principal,
+    build_tracker::{BuildStatus, BuildTracker, DeploymentStatus},
+    deployer::{Deployer, Signer},
+    error::{Error, ErrorCode, Result},
+    extract::Json,
+    localnet::LOCALNET_NETWORK,
+    program::{BinaryData, Programs},
+    state::AppState,
+};
 
-pub async fn deploy(Path((uuid, program_name)): Path<(String, String)>) -> Result<impl IntoResponse> {
+/// Download a built program's ELF binary.
+#[utoipa::path(
+    get,
+    path = "/v1/deploy/{uuid}/{program_name}",
+    tag = "deploy",
+    params(
+        ("uuid" = String, Path, description = "Build UUID"),
+        ("program_name" = String, Path, description = "Name of the built program"),
+    ),
+    responses(
+        (status = 200, description = "Program binary", body = Vec<u8>, content_type = "application/octet-stream"),
+        (status = 404, description = "No such build, or it has no binary", body = ErrorEnvelope),
+    )
+)]
+pub async fn deploy(
+    State(tracker): State<BuildTracker>,
+    State(programs): State<Programs>,
+    Extension(principal): Extension<Principal>,
+    Path((uuid, program_name)): Path<(String, String)>,
+) -> Result<impl IntoResponse> {
+    authorize_build(&tracker, &programs, &principal, &uuid).await?;
     tracing::info!("Attempting to deploy program with UUID: {} and name: {}", uuid, program_name);
-    let binary = program::get_binary(&uuid, &program_name)
+    let binary = programs.get_binary(&uuid, &program_name)
         .await
         .map_err(|e| match e.kind() {
-            io::ErrorKind::NotFound => anyhow!("Program is not built"),
-            _ => e.into(),
-        })
-        .map_err(|e: anyhow::Error| crate::error::Error::from(e))?;
+            io::ErrorKind::NotFound => Error::coded(ErrorCode::ProgramNotBuilt, "Program is not built"),
+            _ => anyhow::Error::from(e).into(),
+        })?;
 
     // Log the actual size of the binary
     tracing::info!("Program binary retrieved successfully, size: {} bytes", binary.len());
@@ -33,4 +61,169 @@ pub async fn deploy(Path((uuid, program_name)): Path<(String, String)>) -> Resul
 
     // Use our wrapper type instead of the raw response construction
     Ok(BinaryData(binary))
-}
\ No newline at end of file
+}
+#[derive(Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FeePayer {
+    /// The server-held localnet key; only valid for the `localnet` network
+    Localnet,
+    /// A remote signer that receives `{pubkey, message}` and returns `{signature}`;
+    /// its host must be listed in `deploy_callback_hosts`
+    Callback { pubkey: String, url: String },
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct DeployRequest {
+    program_name: String,
+    network: Option<String>,
+    /// Hex-encoded secret key of the program account
+    program_keypair: String,
+    fee_payer: FeePayer,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DeployResponse {
+    uuid: String,
+    network: String,
+    program_id: String,
+    status: String,
+}
+
+/// Deploy a successfully built program from the server; progress is reported
+/// in the `deployment` field of `/build/status/:uuid`.
+#[utoipa::path(
+    post,
+    path = "/v1/deploy/{uuid}",
+    tag = "deploy",
+    params(("uuid" = String, Path, description = "Build UUID")),
+    request_body = DeployRequest,
+    responses(
+        (status = 202, description = "Deployment started", body = DeployResponse),
+        (status = 400, description = "Invalid keypair or fee payer, or callback host not allowed", body = ErrorEnvelope),
+        (status = 404, description = "No such build, or it has no binary", body = ErrorEnvelope),
+        (status = 409, description = "Build not finished, or already deploying", body = ErrorEnvelope),
+        (status = 503, description = "Server is shutting down", body = ErrorEnvelope),
+    )
+)]
+pub async fn deploy_program(
+    State(state): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    Path(uuid): Path<String>,
+    Json(payload): Json<DeployRequest>,
+) -> Result<impl IntoResponse> {
+    if state.build_tracker.is_draining() {
+        return Err(Error::coded(ErrorCode::ShuttingDown, "Server is shutting down, retry shortly"));
+    }
+    authorize_build(&state.build_tracker, &state.programs, &principal, &uuid).await?;
+    let build = state
+        .build_tracker
+        .get_build(&uuid)
+        .await
+        .ok_or_else(|| Error::coded(ErrorCode::BuildNotFound, format!("Build {} not found", uuid)))?;
+    if !matches!(build.status, BuildStatus::Success) {
+        return Err(Error::coded(ErrorCode::BuildNotReady, "Build has not completed successfully"));
+    }
+    if build
+        .deployment
+        .as_ref()
+        .is_some_and(|d| matches!(d.status, DeploymentStatus::Running))
+    {
+        return Err(Error::coded(ErrorCode::DeploymentInProgress, "A deployment of this build is already running"));
+    }
+
+    let network = payload
+        .network
+        .unwrap_or_else(|| state.rpc.upstreams.default_network());
+
+    let config = state.config.current();
+    let secp = Secp256k1::new();
+    let program = Keypair::from_seckey_str(&secp, &payload.program_keypair)
+        .map(Signer::Keypair)
+        .map_err(|_| {
+            Error::coded(ErrorCode::InvalidKeypair, "Invalid program keypair")
+                .with_detail("program_keypair", "must be a hex-encoded secp256k1 secret key")
+        })?;
+
+    let authority = match payload.fee_payer {
+        FeePayer::Localnet if network == LOCALNET_NETWORK && state.localnet.is_enabled() => {
+            Signer::Keypair(state.localnet.fee_payer())
+        }
+        FeePayer::Localnet => {
+            return Err(Error::BadRequest("The localnet fee payer can only deploy to an enabled localnet".to_string())
+                .with_detail("fee_payer.type", "use 'callback' for this network"))
+        }
+        FeePayer::Callback { pubkey, url } => Signer::Callback {
+            pubkey: hex::decode(&pubkey)
+                .ok()
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or_else(|| {
+                    Error::BadRequest("Invalid fee payer pubkey".to_string())
+                        .with_detail("fee_payer.pubkey", "must be 32 hex-encoded bytes")
+                })?,
+            url: check_callback_url(&url, &config.deploy_callback_hosts)?,
+            // A redirect must not lead the signing requests past the host check
+            client: reqwest::Client::builder()
+                .redirect(reqwest::redirect::Policy::none())
+                .timeout(Duration::from_secs(config.rpc_request_timeout_secs))
+                .build()
+                .map_err(anyhow::Error::from)?,
+        },
+    };
+
+    let elf = state.programs.get_binary(&uuid, &payload.program_name)
+        .await
+        .map_err(|_| Error::coded(ErrorCode::ProgramNotBuilt, "Program is not built"))?;
+
+    let program_id = hex::encode(program.pubkey());
+    // Checked again under the tracker lock: a rebuild or another deployment
+    // may have started while the binary was fetched
+    state
+        .build_tracker
+        .start_deployment(&uuid, network.clone(), program_id.clone())
+        .await
+        .map_err(|code| {
+            let message = match code {
+                ErrorCode::DeploymentInProgress => "A deployment of this build is already running".to_string(),
+                ErrorCode::BuildNotReady => "Build has not completed successfully".to_string(),
+                _ => format!("Build {} not found", uuid),
+            };
+            Error::coded(code, message)
+        })?;
+
+    let rpc = ArchRpcClient::new(state.rpc.client.clone(), state.rpc.upstreams.clone(), network.clone());
+    let fund_authority = config.rpc_faucet_networks.contains(&network);
+    let deployer = Deployer::new(
+        rpc,
+        state.build_tracker.clone(),
+        uuid.clone(),
+        config.deploy_chunk_retries,
+        fund_authority,
+    );
+    let span = info_span!("deploy", build_uuid = %uuid);
+    tokio::spawn(deployer.run(elf, program, authority).instrument(span));
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(DeployResponse {
+            uuid,
+            network,
+            program_id,
+            status: "deploying".to_string(),
+        }),
+    ))
+}
+
+/// The server POSTs every chunk's signing request to the callback, so it may
+/// only point at an http(s) host the operator listed.
+fn check_callback_url(url: &str, allowed_hosts: &[String]) -> Result<String> {
+    let parsed = reqwest::Url::parse(url).map_err(|_| {
+        Error::BadRequest("Invalid fee payer callback URL".to_string()).with_detail("fee_payer.url", "must be an absolute URL")
+    })?;
+    let allowed = matches!(parsed.scheme(), "http" | "https")
+        && parsed.host_str().is_some_and(|host| allowed_hosts.iter().any(|allowed| allowed.eq_ignore_ascii_case(host)));
+    if !allowed {
+        return Err(Error::BadRequest("Fee payer callback host is not allowed".to_string())
+            .with_detail("fee_payer.url", "host must be listed in deploy_callback_hosts"));
+    }
+    Ok(url.to_string())
+}
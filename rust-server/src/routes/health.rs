@@ -0,0 +1,146 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use axum::{
+    extract::{Json, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::{
+    build_tracker::BuildTracker,
+    program::{ArtifactStore, ProfileWarmup, Programs, VendorMirror, WarmupState},
+    state::AppState,
+    toolchain::Toolchain,
+    upstreams::NetworkStatus,
+};
+
+/// Liveness probe; stays `OK` while draining so the process is not killed
+/// before its builds finish. Use `/ready` to stop routing traffic to it.
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "health",
+    security(()),
+    responses((status = 200, description = "`OK`", body = String, content_type = "text/plain"))
+)]
+pub async fn health() -> impl IntoResponse {
+    (StatusCode::OK, "OK")
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct Readiness {
+    ready: bool,
+    reasons: Vec<String>,
+}
+
+/// Whether this instance should receive builds: not draining, toolchain
+/// installed and the warmup build no longer competing for CPU.
+#[utoipa::path(
+    get,
+    path = "/ready",
+    tag = "health",
+    security(()),
+    responses(
+        (status = 200, description = "Ready for builds", body = Readiness),
+        (status = 503, description = "Not ready; `reasons` says why", body = Readiness),
+    )
+)]
+pub async fn ready(State(tracker): State<BuildTracker>, State(programs): State<Programs>) -> impl IntoResponse {
+    let mut reasons = Vec::new();
+    if tracker.is_draining() {
+        reasons.push("draining".to_string());
+    }
+    for tool in programs.toolchain().missing() {
+        reasons.push(format!("{} not installed", tool));
+    }
+    if programs.ensure_vendored_mirror().is_err() {
+        reasons.push("vendored mirror missing".to_string());
+    }
+    for warmup in programs.warmup_state() {
+        match warmup.state {
+            WarmupState::NotStarted => reasons.push(format!("warmup of {} not started", warmup.profile)),
+            WarmupState::Queued | WarmupState::Running { .. } => {
+                reasons.push(format!("warmup of {} running", warmup.profile))
+            }
+            WarmupState::Ready { .. } | WarmupState::Failed { .. } => {}
+        }
+    }
+
+    let status = if reasons.is_empty() { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (status, Json(Readiness { ready: reasons.is_empty(), reasons }))
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct Disk {
+    #[schema(value_type = String)]
+    path: PathBuf,
+    available_bytes: Option<u64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct Builds {
+    active: usize,
+    capacity: usize,
+    /// `active / capacity`; above 1 means builds are queued
+    saturation: f64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct HealthDetails {
+    /// `ok`, `degraded` or `draining`
+    status: &'static str,
+    toolchain: Toolchain,
+    /// One entry per configured SDK profile
+    warmup: Vec<ProfileWarmup>,
+    /// Only reported when building offline
+    vendored_mirror: Option<VendorMirror>,
+    disk: Disk,
+    builds: Builds,
+    artifact_store: ArtifactStore,
+    rpc_upstreams: HashMap<String, NetworkStatus>,
+}
+
+/// Everything `/ready` looks at and more, for dashboards and debugging.
+#[utoipa::path(
+    get,
+    path = "/health/details",
+    tag = "health",
+    responses((status = 200, description = "Detailed health", body = HealthDetails))
+)]
+pub async fn health_details(State(state): State<AppState>) -> impl IntoResponse {
+    let (artifact_store, available_bytes, rpc_upstreams) = tokio::join!(
+        state.programs.artifact_store(),
+        state.programs.available_disk_bytes(),
+        state.rpc.upstreams.status(),
+    );
+
+    let active = state.build_tracker.active_count().await;
+    let capacity = state.config.current().build.capacity.max(1);
+    let warmup = state.programs.warmup_state();
+    let toolchain = state.programs.toolchain().clone();
+
+    let status = if state.build_tracker.is_draining() {
+        "draining"
+    } else if !toolchain.missing().is_empty()
+        || !artifact_store.reachable
+        || state.programs.ensure_vendored_mirror().is_err()
+        || warmup.iter().any(|warmup| matches!(warmup.state, WarmupState::Failed { .. }))
+    {
+        "degraded"
+    } else {
+        "ok"
+    };
+
+    Json(HealthDetails {
+        status,
+        toolchain,
+        warmup,
+        vendored_mirror: state.programs.vendored_mirror(),
+        disk: Disk { path: state.programs.dir().to_path_buf(), available_bytes },
+        builds: Builds { active, capacity, saturation: active as f64 / capacity as f64 },
+        artifact_store,
+        rpc_upstreams,
+    })
+}
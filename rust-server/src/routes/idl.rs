@@ -0,0 +1,117 @@
+use axum::response::IntoResponse;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::ToSchema;
+
+use super::tools::{decode_bytes, Encoding};
+use crate::{
+    error::{Error, Result},
+    extract::Json,
+    idl::{ArchIdl, Codec},
+};
+
+fn default_discriminator() -> bool {
+    true
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct IdlEncodeRequest {
+    #[schema(value_type = Object)]
+    idl: ArchIdl,
+    instruction: String,
+    /// Object keyed by arg name, or an array in declaration order
+    #[serde(default)]
+    args: Value,
+    /// Prefix the data with the instruction's `u8` index
+    #[serde(default = "default_discriminator")]
+    discriminator: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct IdlEncodeResponse {
+    instruction: String,
+    hex: String,
+    base64: String,
+    length: usize,
+}
+
+/// Borsh-encode instruction data from JSON args using the program's IDL.
+#[utoipa::path(
+    post,
+    path = "/v1/idl/encode",
+    tag = "idl",
+    request_body = IdlEncodeRequest,
+    responses(
+        (status = 200, description = "Encoded instruction data", body = IdlEncodeResponse),
+        (status = 400, description = "Unknown instruction or args that do not fit the IDL", body = ErrorEnvelope),
+    )
+)]
+pub async fn idl_encode(Json(payload): Json<IdlEncodeRequest>) -> Result<impl IntoResponse> {
+    let data = Codec::new(&payload.idl)
+        .encode_instruction(&payload.instruction, &payload.args, payload.discriminator)
+        .map_err(|e| Error::BadRequest(e.to_string()))?;
+
+    Ok(Json(IdlEncodeResponse {
+        instruction: payload.instruction,
+        hex: hex::encode(&data),
+        base64: base64::engine::general_purpose::STANDARD.encode(&data),
+        length: data.len(),
+    }))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct IdlDecodeRequest {
+    #[schema(value_type = Object)]
+    idl: ArchIdl,
+    data: String,
+    encoding: Option<Encoding>,
+    /// Account type to decode `data` as
+    account: Option<String>,
+    /// Instruction to decode `data` as; picked by its index byte when only `discriminator` is set
+    instruction: Option<String>,
+    #[serde(default = "default_discriminator")]
+    discriminator: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct IdlDecodeResponse {
+    /// `account` or `instruction`
+    kind: &'static str,
+    name: String,
+    value: Value,
+    /// Bytes left after the decoded value, e.g. unused space in an account
+    remaining_bytes: usize,
+}
+
+/// Decode account or instruction data into JSON using the program's IDL.
+#[utoipa::path(
+    post,
+    path = "/v1/idl/decode",
+    tag = "idl",
+    request_body = IdlDecodeRequest,
+    responses(
+        (status = 200, description = "Decoded value", body = IdlDecodeResponse),
+        (status = 400, description = "Data that does not fit the IDL", body = ErrorEnvelope),
+    )
+)]
+pub async fn idl_decode(Json(payload): Json<IdlDecodeRequest>) -> Result<impl IntoResponse> {
+    let data = decode_bytes("data", &payload.data, payload.encoding)?;
+    let codec = Codec::new(&payload.idl);
+
+    let response = match &payload.account {
+        Some(account) => {
+            let (value, remaining_bytes) =
+                codec.decode_account(account, &data).map_err(|e| Error::BadRequest(e.to_string()))?;
+            IdlDecodeResponse { kind: "account", name: account.clone(), value, remaining_bytes }
+        }
+        None => {
+            let (name, value, remaining_bytes) = codec
+                .decode_instruction(payload.instruction.as_deref(), &data, payload.discriminator)
+                .map_err(|e| Error::BadRequest(e.to_string()))?;
+            IdlDecodeResponse { kind: "instruction", name: name.to_string(), value, remaining_bytes }
+        }
+    };
+
+    Ok(Json(response))
+}
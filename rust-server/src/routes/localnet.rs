@@ -0,0 +1,82 @@
+use axum::{extract::{Extension, Json, State}, response::IntoResponse};
+
+use crate::{auth::Principal, error::{Error, Result}, localnet::Localnet};
+
+fn ensure_enabled(localnet: &Localnet) -> Result<()> {
+    if localnet.is_enabled() {
+        Ok(())
+    } else {
+        Err(Error::NotFound("Localnet is disabled".to_string()))
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/localnet/start",
+    tag = "localnet",
+    responses(
+        (status = 200, description = "Status after starting", body = LocalnetStatus),
+        (status = 403, description = "Not an admin", body = ErrorEnvelope),
+        (status = 404, description = "Localnet is disabled", body = ErrorEnvelope),
+    )
+)]
+pub async fn localnet_start(
+    State(localnet): State<Localnet>,
+    Extension(principal): Extension<Principal>,
+) -> Result<impl IntoResponse> {
+    principal.require_admin()?;
+    ensure_enabled(&localnet)?;
+    Ok(Json(localnet.start().await?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/localnet/stop",
+    tag = "localnet",
+    responses(
+        (status = 200, description = "Status after stopping", body = LocalnetStatus),
+        (status = 403, description = "Not an admin", body = ErrorEnvelope),
+        (status = 404, description = "Localnet is disabled", body = ErrorEnvelope),
+    )
+)]
+pub async fn localnet_stop(
+    State(localnet): State<Localnet>,
+    Extension(principal): Extension<Principal>,
+) -> Result<impl IntoResponse> {
+    principal.require_admin()?;
+    ensure_enabled(&localnet)?;
+    Ok(Json(localnet.stop().await?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/localnet/reset",
+    tag = "localnet",
+    responses(
+        (status = 200, description = "Status after restarting from a clean ledger", body = LocalnetStatus),
+        (status = 403, description = "Not an admin", body = ErrorEnvelope),
+        (status = 404, description = "Localnet is disabled", body = ErrorEnvelope),
+    )
+)]
+pub async fn localnet_reset(
+    State(localnet): State<Localnet>,
+    Extension(principal): Extension<Principal>,
+) -> Result<impl IntoResponse> {
+    principal.require_admin()?;
+    ensure_enabled(&localnet)?;
+    Ok(Json(localnet.reset().await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/localnet/status",
+    tag = "localnet",
+    responses(
+        (status = 200, description = "Current status", body = LocalnetStatus),
+        (status = 404, description = "Localnet is disabled", body = ErrorEnvelope),
+    )
+)]
+pub async fn localnet_status(State(localnet): State<Localnet>) -> Result<impl IntoResponse> {
+    ensure_enabled(&localnet)?;
+    Ok(Json(localnet.status().await?))
+}
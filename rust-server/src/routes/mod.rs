@@ -1,13 +1,168 @@
+mod admin;
+mod bitcoin_rpc;
 mod build;
 mod deploy;
+mod health;
+mod idl;
+mod localnet;
 mod rpc_proxy;
+mod tools;
+mod usage;
 
+pub use admin::*;
+pub use bitcoin_rpc::*;
 pub use build::*;
 pub use deploy::*;
+pub use health::*;
+pub use idl::*;
+pub use localnet::*;
 pub use rpc_proxy::*;
+pub use tools::*;
+pub use usage::*;
 
-use axum::response::IntoResponse;
+use axum::{
+    extract::State,
+    handler::Handler,
+    http::Method,
+    middleware,
+    response::IntoResponse,
+    routing::{on, MethodFilter},
+    Router,
+};
 
-pub async fn health() -> impl IntoResponse {
-    "OK"
-}
\ No newline at end of file
+use crate::{
+    build_tracker::BuildTracker,
+    compat::{self, V1_PREFIX},
+    error, openapi,
+    state::AppState,
+};
+
+/// Every route of the server. Each one needs a `#[utoipa::path]` annotation
+/// and an entry in `openapi::ApiDoc`; the tests there check both stay in step.
+/// Probes and monitoring are unversioned; see `compat` for the rest.
+pub fn router() -> Router<AppState> {
+    routes().router
+}
+
+/// `(method, path)` of every route `router` serves, with axum's `:param`
+/// syntax. The unversioned aliases of the `/v1` routes are left out.
+#[cfg(test)]
+pub fn route_table() -> Vec<(Method, String)> {
+    routes().table
+}
+
+fn routes() -> Routes {
+    let api = Routes::new()
+        .nest(V1_PREFIX, v1())
+        // Deprecated unversioned aliases of the v1 routes
+        .merge_unlisted(v1().router)
+        .map(|router| router.layer(middleware::from_fn(compat::negotiate)));
+
+    Routes::new()
+        .route(Method::GET, "/health", health)
+        .route(Method::GET, "/health/details", health_details)
+        .route(Method::GET, "/ready", ready)
+        .route(Method::GET, "/metrics", metrics_export)
+        .route(Method::GET, "/openapi.json", openapi::openapi_json)
+        .merge(api)
+        .map(|router| router.fallback(error::not_found))
+}
+
+fn v1() -> Routes {
+    Routes::new()
+        .route(Method::POST, "/build", build)
+        .route(Method::GET, "/build/status/:uuid", build_status)
+        .route(Method::OPTIONS, "/build/status/:uuid", build_status_options)
+        .route(Method::POST, "/deploy/:uuid", deploy_program)
+        .route(Method::GET, "/deploy/:uuid/:program_name", deploy)
+        .route(Method::POST, "/rpc", rpc_proxy)
+        .route(Method::OPTIONS, "/rpc", rpc_proxy_options)
+        .route(Method::GET, "/rpc/health", rpc_health)
+        .route(Method::POST, "/bitcoin/rpc", bitcoin_rpc)
+        .route(Method::POST, "/localnet/start", localnet_start)
+        .route(Method::POST, "/localnet/stop", localnet_stop)
+        .route(Method::POST, "/localnet/reset", localnet_reset)
+        .route(Method::GET, "/localnet/status", localnet_status)
+        .route(Method::POST, "/tools/bip322/verify", bip322_verify)
+        .route(Method::POST, "/tools/bip322/sign-test", bip322_sign_test)
+        .route(Method::POST, "/tools/decode", decode)
+        .route(Method::POST, "/idl/encode", idl_encode)
+        .route(Method::POST, "/idl/decode", idl_decode)
+        .route(Method::GET, "/usage", usage)
+        .route(Method::GET, "/admin/usage", admin_usage)
+        .route(Method::GET, "/admin/builds", list_builds)
+        .route(Method::POST, "/admin/builds/:uuid/cancel", cancel_build)
+        .route(Method::POST, "/admin/artifacts/purge", purge_artifacts)
+        .route(Method::POST, "/admin/warmup", rerun_warmup)
+        .route(Method::POST, "/admin/vendor/refresh", refresh_vendor)
+        .route(Method::GET, "/admin/disk", disk_usage)
+        .route(Method::POST, "/admin/config/reload", reload_config)
+}
+
+/// A `Router` being built, with the `(method, path)` of each route added to
+/// it, so the documentation can be checked against what is actually served.
+struct Routes {
+    router: Router<AppState>,
+    table: Vec<(Method, String)>,
+}
+
+impl Routes {
+    fn new() -> Self {
+        Self { router: Router::new(), table: Vec::new() }
+    }
+
+    /// Routes `method` requests to `path` to `handler`. Further methods on
+    /// the same path are added to its existing `MethodRouter`.
+    fn route<H, T>(mut self, method: Method, path: &str, handler: H) -> Self
+    where
+        H: Handler<T, AppState>,
+        T: 'static,
+    {
+        let filter = MethodFilter::try_from(method.clone()).expect("method axum can route");
+        self.router = self.router.route(path, on(filter, handler));
+        self.table.push((method, path.to_string()));
+        self
+    }
+
+    fn nest(mut self, prefix: &str, routes: Routes) -> Self {
+        self.router = self.router.nest(prefix, routes.router);
+        let nested = routes.table.into_iter().map(|(method, path)| (method, format!("{prefix}{path}")));
+        self.table.extend(nested);
+        self
+    }
+
+    fn merge(mut self, routes: Routes) -> Self {
+        self.router = self.router.merge(routes.router);
+        self.table.extend(routes.table);
+        self
+    }
+
+    /// Merges routes that duplicate listed ones, like the unversioned aliases.
+    fn merge_unlisted(mut self, router: Router<AppState>) -> Self {
+        self.router = self.router.merge(router);
+        self
+    }
+
+    /// Applies a layer or fallback to every route added so far.
+    fn map(mut self, f: impl FnOnce(Router<AppState>) -> Router<AppState>) -> Self {
+        self.router = f(self.router);
+        self
+    }
+}
+
+/// Prometheus text exposition of `crate::metrics`
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "health",
+    responses((status = 200, description = "OpenMetrics text", body = String, content_type = "application/openmetrics-text"))
+)]
+pub async fn metrics_export(State(tracker): State<BuildTracker>) -> impl IntoResponse {
+    let metrics = crate::metrics::metrics();
+    // Read from the tracker rather than counted, so no transition can skew it
+    metrics.build_queue_depth.set(tracker.running_count().await as i64);
+    (
+        [("Content-Type", "application/openmetrics-text; version=1.0.0; charset=utf-8")],
+        metrics.encode(),
+    )
+}
@@ -1,69 +1,66 @@
 use axum::{
-    extract::Query,
+    extract::{Extension, Json, Query, State},
     http::{StatusCode, HeaderMap},
     response::IntoResponse,
 };
-use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde::Deserialize;
 use tracing::{error, info};
+use utoipa::IntoParams;
 
-#[derive(Debug, Deserialize)]
-pub struct RpcProxyQuery {
-    target: Option<String>,
-}
+use crate::{auth::Principal, error::{Error, Result}, state::RpcState, usage::UsageTracker};
 
-#[derive(Debug, Serialize, Deserialize)]
-struct JsonRpcRequest {
-    jsonrpc: String,
-    id: Value,
-    method: String,
-    params: Vec<Value>,
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct RpcProxyQuery {
+    /// Named network to send to; the configured default when omitted
+    network: Option<String>,
 }
 
 /// Proxy endpoint for RPC requests to avoid CORS issues
+#[utoipa::path(
+    post,
+    path = "/v1/rpc",
+    tag = "rpc",
+    params(RpcProxyQuery),
+    request_body(content = Object, description = "JSON-RPC 2.0 request or batch"),
+    responses(
+        (status = 200, description = "The upstream's JSON-RPC response, with its status code", body = Object),
+        (status = 400, description = "Malformed JSON-RPC", body = ErrorEnvelope),
+        (status = 403, description = "Method not on the allowlist", body = ErrorEnvelope),
+        (status = 404, description = "Unknown network", body = ErrorEnvelope),
+        (status = 429, description = "RPC quota exceeded", body = ErrorEnvelope),
+        (status = 502, description = "No upstream answered", body = ErrorEnvelope),
+        (status = 504, description = "Upstream timed out", body = ErrorEnvelope),
+    )
+)]
 pub async fn rpc_proxy(
+    State(rpc): State<RpcState>,
+    State(usage): State<UsageTracker>,
+    Extension(principal): Extension<Principal>,
     Query(query): Query<RpcProxyQuery>,
     _headers: HeaderMap,
     body: String,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
-    // Default RPC URL - can be overridden via query param
-    let default_rpc_url = std::env::var("RPC_URL")
-        .unwrap_or_else(|_| "https://rpc-beta.test.arch.network".to_string());
+) -> Result<impl IntoResponse> {
+    // Parse and validate the request against the method allowlist
+    let rules = rpc.rules();
+    let rpc_requests = rules.arch_policy.check_body(&body).map_err(|e| {
+        error!("Rejected RPC request: {}", e);
+        e
+    })?;
 
-    let target_url = query.target.as_deref().unwrap_or(&default_rpc_url);
-
-    info!("Proxying RPC request to: {}", target_url);
-
-    // Parse and validate the request
-    let rpc_request: JsonRpcRequest = serde_json::from_str(&body)
-        .map_err(|e| {
-            error!("Failed to parse RPC request: {}", e);
-            (StatusCode::BAD_REQUEST, format!("Invalid JSON-RPC request: {}", e))
-        })?;
+    // Each call in a batch counts against the quota
+    usage.rpc_calls(&principal, rpc_requests.len() as u64)?;
 
-    info!("RPC method: {}", rpc_request.method);
+    for rpc_request in &rpc_requests {
+        info!("RPC method: {}", rpc_request.method);
+    }
 
-    // Create HTTP client
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(120))
-        .build()
-        .map_err(|e| {
-            error!("Failed to create HTTP client: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create client: {}", e))
-        })?;
+    let method = match rpc_requests.as_slice() {
+        [request] => rules.arch_policy.method_label(&request.method),
+        _ => "batch",
+    };
 
-    // Forward the request to the target RPC server
-    let response = client
-        .post(target_url)
-        .header("Content-Type", "application/json")
-        .header("Accept", "application/json")
-        .body(body)
-        .send()
-        .await
-        .map_err(|e| {
-            error!("Failed to send RPC request: {}", e);
-            (StatusCode::BAD_GATEWAY, format!("Failed to connect to RPC server: {}", e))
-        })?;
+    let network = query.network.unwrap_or_else(|| rpc.upstreams.default_network());
+    let response = rpc.upstreams.send(&rpc.client, &network, method, body).await?;
 
     let status = response.status();
     let response_body = response
@@ -71,7 +68,7 @@ pub async fn rpc_proxy(
         .await
         .map_err(|e| {
             error!("Failed to read RPC response: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to read response: {}", e))
+            Error::Internal(format!("Failed to read response: {}", e))
         })?;
 
     info!("RPC response status: {}", status);
@@ -80,28 +77,22 @@ pub async fn rpc_proxy(
     let axum_status = StatusCode::from_u16(status.as_u16())
         .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
 
-    // Return the response with CORS headers
-    Ok((
-        axum_status,
-        [
-            ("Content-Type", "application/json"),
-            ("Access-Control-Allow-Origin", "*"),
-            ("Access-Control-Allow-Methods", "POST, OPTIONS"),
-            ("Access-Control-Allow-Headers", "Content-Type, Accept"),
-        ],
-        response_body,
-    ))
+    // CORS headers are added by the `cors` layer
+    Ok((axum_status, [("Content-Type", "application/json")], response_body))
+}
+
+/// Summarise the health of every upstream of every named network
+#[utoipa::path(
+    get,
+    path = "/v1/rpc/health",
+    tag = "rpc",
+    responses((status = 200, description = "Upstreams by network name", body = HashMap<String, NetworkStatus>))
+)]
+pub async fn rpc_health(State(rpc): State<RpcState>) -> impl IntoResponse {
+    Json(rpc.upstreams.status().await)
 }
 
-/// Handle OPTIONS preflight requests
+/// Handle OPTIONS requests; CORS headers come from the `cors` layer
 pub async fn rpc_proxy_options() -> impl IntoResponse {
-    (
-        StatusCode::OK,
-        [
-            ("Access-Control-Allow-Origin", "*"),
-            ("Access-Control-Allow-Methods", "POST, OPTIONS"),
-            ("Access-Control-Allow-Headers", "Content-Type, Accept"),
-            ("Access-Control-Max-Age", "3600"),
-        ],
-    )
+    (StatusCode::OK, [("Allow", "POST, OPTIONS")])
 }
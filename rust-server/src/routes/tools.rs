@@ -0,0 +1,428 @@
+use axum::response::IntoResponse;
+use base64::Engine;
+use bitcoin::{key::Keypair, secp256k1::Secp256k1, Address, Network, Transaction};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::{
+    arch::{self, ArchMessage, MessageHeader, RuntimeTransaction},
+    bip322::{self, VerifyError},
+    error::{Error, Result},
+    extract::Json,
+};
+
+/// An `ArchMessage` either as hex of its wire serialization or in its JSON form.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum MessageInput {
+    Serialized(String),
+    Decoded(ArchMessage),
+}
+
+/// What gets signed: an `ArchMessage`, hashed the way the validator does, or
+/// an already computed 64-character message hash.
+#[derive(Deserialize, ToSchema)]
+pub struct SignedPayload {
+    /// Hex of the serialized message, or the message as JSON
+    #[schema(value_type = Option<Object>)]
+    message: Option<MessageInput>,
+    message_hash: Option<String>,
+}
+
+struct ResolvedPayload {
+    serialized_message: Option<String>,
+    message_hash: Vec<u8>,
+}
+
+impl SignedPayload {
+    fn resolve(self) -> Result<ResolvedPayload> {
+        match (self.message, self.message_hash) {
+            (Some(message), None) => {
+                let message = match message {
+                    MessageInput::Serialized(hex) => {
+                        let bytes = decode_hex("message", &hex)?;
+                        ArchMessage::deserialize(&bytes)
+                            .map_err(|e| Error::BadRequest(format!("Invalid message: {}", e)))?
+                    }
+                    MessageInput::Decoded(message) => message,
+                };
+                Ok(ResolvedPayload {
+                    serialized_message: Some(hex::encode(message.serialize())),
+                    message_hash: message.hash(),
+                })
+            }
+            (None, Some(hash)) if hash.len() == 64 && hash.bytes().all(|b| b.is_ascii_hexdigit()) => {
+                Ok(ResolvedPayload { serialized_message: None, message_hash: hash.into_bytes() })
+            }
+            (None, Some(_)) => Err(Error::BadRequest("message_hash must be 64 hex characters".to_string())),
+            _ => Err(Error::BadRequest("Provide exactly one of message or message_hash".to_string())),
+        }
+    }
+}
+
+fn decode_hex(field: &str, value: &str) -> Result<Vec<u8>> {
+    hex::decode(value.trim_start_matches("0x"))
+        .map_err(|_| Error::BadRequest(format!("{} must be hex-encoded", field)))
+}
+
+#[derive(Clone, Copy, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Encoding {
+    Hex,
+    Base64,
+}
+
+/// Decodes binary input given as hex or base64, detecting which when `encoding` is not set.
+pub fn decode_bytes(field: &str, value: &str, encoding: Option<Encoding>) -> Result<Vec<u8>> {
+    let value = value.trim();
+    let encoding = encoding.unwrap_or_else(|| {
+        let hex = value.trim_start_matches("0x");
+        if hex.len().is_multiple_of(2) && hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            Encoding::Hex
+        } else {
+            Encoding::Base64
+        }
+    });
+    match encoding {
+        Encoding::Hex => decode_hex(field, value),
+        Encoding::Base64 => base64::engine::general_purpose::STANDARD
+            .decode(value)
+            .map_err(|_| Error::BadRequest(format!("{} must be hex or base64-encoded", field))),
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct VerifyRequest {
+    /// Untweaked x-only public key, hex
+    pubkey: String,
+    /// 64-byte signature, or 65 bytes with a trailing sighash type, hex
+    signature: String,
+    #[serde(flatten)]
+    payload: SignedPayload,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct VerifyResponse {
+    valid: bool,
+    sighash_type: Option<String>,
+    serialized_message: Option<String>,
+    /// The signed bytes, i.e. the 64 UTF-8 hex characters of the message hash
+    message_hash: String,
+    error: Option<String>,
+}
+
+/// Check a BIP-322 signature against the reference implementation used by the server's deployer.
+#[utoipa::path(
+    post,
+    path = "/v1/tools/bip322/verify",
+    tag = "tools",
+    request_body = VerifyRequest,
+    responses(
+        (status = 200, description = "Verification result; a wrong signature is `valid: false`, not an error", body = VerifyResponse),
+        (status = 400, description = "Malformed key, signature or message", body = ErrorEnvelope),
+    )
+)]
+pub async fn bip322_verify(Json(payload): Json<VerifyRequest>) -> Result<impl IntoResponse> {
+    let pubkey = decode_hex("pubkey", &payload.pubkey)?;
+    let signature = decode_hex("signature", &payload.signature)?;
+    let resolved = payload.payload.resolve()?;
+
+    let (valid, sighash_type, error) = match bip322::verify_message(&pubkey, &resolved.message_hash, &signature) {
+        Ok(sighash_type) => (true, Some(sighash_type.to_string()), None),
+        Err(e @ VerifyError::Mismatch) => (false, None, Some(e.to_string())),
+        Err(e) => return Err(Error::BadRequest(e.to_string())),
+    };
+
+    Ok(Json(VerifyResponse {
+        valid,
+        sighash_type,
+        serialized_message: resolved.serialized_message,
+        message_hash: String::from_utf8(resolved.message_hash).expect("message hash is hex"),
+        error,
+    }))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct SignTestRequest {
+    /// Hex secret key; a throwaway key is generated when omitted
+    private_key: Option<String>,
+    #[serde(flatten)]
+    payload: SignedPayload,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SignTestResponse {
+    private_key: String,
+    pubkey: String,
+    serialized_message: Option<String>,
+    message_hash: String,
+    signature: String,
+}
+
+/// Produce a reference signature to compare a client signer against. Only
+/// meant for test keys: the private key is sent to and echoed by the server.
+#[utoipa::path(
+    post,
+    path = "/v1/tools/bip322/sign-test",
+    tag = "tools",
+    request_body = SignTestRequest,
+    responses(
+        (status = 200, description = "Signature and the key that made it", body = SignTestResponse),
+        (status = 400, description = "Malformed key or message", body = ErrorEnvelope),
+    )
+)]
+pub async fn bip322_sign_test(Json(payload): Json<SignTestRequest>) -> Result<impl IntoResponse> {
+    let secp = Secp256k1::new();
+    let keypair = match &payload.private_key {
+        Some(key) => Keypair::from_seckey_str(&secp, key.trim_start_matches("0x"))
+            .map_err(|_| Error::BadRequest("Invalid private key".to_string()))?,
+        None => Keypair::new(&secp, &mut bitcoin::secp256k1::rand::thread_rng()),
+    };
+    let resolved = payload.payload.resolve()?;
+    let signature = bip322::sign_message(&keypair, &resolved.message_hash);
+
+    Ok(Json(SignTestResponse {
+        private_key: hex::encode(keypair.secret_bytes()),
+        pubkey: hex::encode(keypair.x_only_public_key().0.serialize()),
+        serialized_message: resolved.serialized_message,
+        message_hash: String::from_utf8(resolved.message_hash).expect("message hash is hex"),
+        signature: hex::encode(signature),
+    }))
+}
+
+#[derive(Clone, Copy, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DecodeKind {
+    ArchMessage,
+    RuntimeTransaction,
+    BitcoinTransaction,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct DecodeRequest {
+    data: String,
+    /// Detected from the content when omitted
+    encoding: Option<Encoding>,
+    /// Each kind is tried in turn when omitted
+    kind: Option<DecodeKind>,
+    /// Bitcoin network used to render output addresses
+    network: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Decoded {
+    ArchMessage(MessageBreakdown),
+    RuntimeTransaction(TransactionBreakdown),
+    BitcoinTransaction(BitcoinTransactionBreakdown),
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct MessageBreakdown {
+    header: MessageHeader,
+    account_keys: Vec<AccountKeyBreakdown>,
+    recent_blockhash: String,
+    instructions: Vec<InstructionBreakdown>,
+    message_hash: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AccountKeyBreakdown {
+    pubkey: String,
+    signer: bool,
+    writable: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct InstructionBreakdown {
+    program_id: Option<String>,
+    program_id_index: u8,
+    program_name: Option<&'static str>,
+    instruction_name: Option<&'static str>,
+    accounts: Vec<u8>,
+    data: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TransactionBreakdown {
+    version: u32,
+    txid: String,
+    signatures: Vec<SignatureBreakdown>,
+    message: MessageBreakdown,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SignatureBreakdown {
+    signer: Option<String>,
+    signature: String,
+    /// Whether the signature is a valid BIP-322 signature of the message by `signer`
+    valid: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BitcoinTransactionBreakdown {
+    txid: String,
+    wtxid: String,
+    version: i32,
+    lock_time: u32,
+    size: usize,
+    vsize: usize,
+    weight: u64,
+    inputs: Vec<BitcoinInputBreakdown>,
+    outputs: Vec<BitcoinOutputBreakdown>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BitcoinInputBreakdown {
+    previous_output: String,
+    script_sig: String,
+    sequence: u32,
+    witness: Vec<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BitcoinOutputBreakdown {
+    value: u64,
+    script_pubkey: String,
+    address: Option<String>,
+}
+
+fn message_breakdown(message: &ArchMessage) -> MessageBreakdown {
+    MessageBreakdown {
+        header: message.header,
+        account_keys: message
+            .account_keys
+            .iter()
+            .enumerate()
+            .map(|(index, key)| AccountKeyBreakdown {
+                pubkey: hex::encode(key),
+                signer: message.is_signer(index),
+                writable: message.is_writable(index),
+            })
+            .collect(),
+        recent_blockhash: hex::encode(message.recent_blockhash),
+        instructions: message
+            .instructions
+            .iter()
+            .map(|ix| {
+                let program_id = message.account_keys.get(ix.program_id_index as usize);
+                InstructionBreakdown {
+                    program_id: program_id.map(hex::encode),
+                    program_id_index: ix.program_id_index,
+                    program_name: program_id.and_then(arch::program_name),
+                    instruction_name: program_id.and_then(|id| arch::instruction_name(id, &ix.data)),
+                    accounts: ix.accounts.clone(),
+                    data: hex::encode(&ix.data),
+                }
+            })
+            .collect(),
+        message_hash: String::from_utf8(message.hash()).expect("message hash is hex"),
+    }
+}
+
+fn transaction_breakdown(tx: &RuntimeTransaction) -> TransactionBreakdown {
+    let message_hash = tx.message.hash();
+    TransactionBreakdown {
+        version: tx.version,
+        txid: tx.txid(),
+        signatures: tx
+            .signatures
+            .iter()
+            .enumerate()
+            .map(|(index, signature)| {
+                let signer = tx.message.signers().get(index);
+                SignatureBreakdown {
+                    signer: signer.map(hex::encode),
+                    signature: hex::encode(signature),
+                    valid: signer.is_some_and(|key| bip322::verify_message(key, &message_hash, signature).is_ok()),
+                }
+            })
+            .collect(),
+        message: message_breakdown(&tx.message),
+    }
+}
+
+fn bitcoin_transaction_breakdown(tx: &Transaction, network: Option<Network>) -> BitcoinTransactionBreakdown {
+    BitcoinTransactionBreakdown {
+        txid: tx.compute_txid().to_string(),
+        wtxid: tx.compute_wtxid().to_string(),
+        version: tx.version.0,
+        lock_time: tx.lock_time.to_consensus_u32(),
+        size: tx.total_size(),
+        vsize: tx.vsize(),
+        weight: tx.weight().to_wu(),
+        inputs: tx
+            .input
+            .iter()
+            .map(|input| BitcoinInputBreakdown {
+                previous_output: input.previous_output.to_string(),
+                script_sig: hex::encode(input.script_sig.as_bytes()),
+                sequence: input.sequence.0,
+                witness: input.witness.iter().map(hex::encode).collect(),
+            })
+            .collect(),
+        outputs: tx
+            .output
+            .iter()
+            .map(|output| BitcoinOutputBreakdown {
+                value: output.value.to_sat(),
+                script_pubkey: hex::encode(output.script_pubkey.as_bytes()),
+                address: network
+                    .and_then(|network| Address::from_script(&output.script_pubkey, network).ok())
+                    .map(|address| address.to_string()),
+            })
+            .collect(),
+    }
+}
+
+fn decode_as(kind: DecodeKind, bytes: &[u8], network: Option<Network>) -> std::result::Result<Decoded, String> {
+    match kind {
+        DecodeKind::ArchMessage => ArchMessage::deserialize(bytes)
+            .map(|message| Decoded::ArchMessage(message_breakdown(&message)))
+            .map_err(|e| e.to_string()),
+        DecodeKind::RuntimeTransaction => RuntimeTransaction::deserialize(bytes)
+            .map(|tx| Decoded::RuntimeTransaction(transaction_breakdown(&tx)))
+            .map_err(|e| e.to_string()),
+        DecodeKind::BitcoinTransaction => bitcoin::consensus::deserialize::<Transaction>(bytes)
+            .map(|tx| Decoded::BitcoinTransaction(bitcoin_transaction_breakdown(&tx, network)))
+            .map_err(|e| e.to_string()),
+    }
+}
+
+/// Break down a serialized Arch message, Arch runtime transaction or Bitcoin transaction.
+#[utoipa::path(
+    post,
+    path = "/v1/tools/decode",
+    tag = "tools",
+    request_body = DecodeRequest,
+    responses(
+        (status = 200, description = "The decoded data, tagged by `kind`", body = Decoded),
+        (status = 400, description = "The data decodes as none of the kinds tried", body = ErrorEnvelope),
+    )
+)]
+pub async fn decode(Json(payload): Json<DecodeRequest>) -> Result<impl IntoResponse> {
+    let bytes = decode_bytes("data", &payload.data, payload.encoding)?;
+
+    let network = payload
+        .network
+        .map(|network| {
+            network
+                .parse::<Network>()
+                .map_err(|_| Error::BadRequest(format!("Unknown Bitcoin network '{}'", network)))
+        })
+        .transpose()?;
+
+    let kinds = match payload.kind {
+        Some(kind) => vec![kind],
+        None => vec![DecodeKind::ArchMessage, DecodeKind::RuntimeTransaction, DecodeKind::BitcoinTransaction],
+    };
+
+    let mut errors = Vec::new();
+    for kind in kinds {
+        match decode_as(kind, &bytes, network) {
+            Ok(decoded) => return Ok(Json(decoded)),
+            Err(e) => errors.push(e),
+        }
+    }
+    Err(Error::BadRequest(format!("Could not decode data: {}", errors.join("; "))))
+}
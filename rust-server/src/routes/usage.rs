@@ -0,0 +1,39 @@
+use axum::{
+    extract::{Extension, Json, State},
+    response::IntoResponse,
+};
+
+use crate::{
+    auth::Principal,
+    error::Result,
+    usage::UsageTracker,
+};
+
+/// Today's and lifetime usage of the caller, with the limits that apply to it.
+#[utoipa::path(
+    get,
+    path = "/v1/usage",
+    tag = "usage",
+    responses((status = 200, description = "The caller's usage", body = UsageReport))
+)]
+pub async fn usage(State(usage): State<UsageTracker>, Extension(principal): Extension<Principal>) -> impl IntoResponse {
+    Json(usage.report(&principal))
+}
+
+/// Usage of every principal since startup. Admin only.
+#[utoipa::path(
+    get,
+    path = "/v1/admin/usage",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Usage by principal", body = Vec<UsageReport>),
+        (status = 403, description = "Not an admin", body = ErrorEnvelope),
+    )
+)]
+pub async fn admin_usage(
+    State(usage): State<UsageTracker>,
+    Extension(principal): Extension<Principal>,
+) -> Result<impl IntoResponse> {
+    principal.require_admin()?;
+    Ok(Json(usage.summary()))
+}
@@ -0,0 +1,113 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::{Error, ErrorCode, Result};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JsonRpcRequest {
+    pub jsonrpc: String,
+    pub id: Value,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+/// Method allowlist applied to every JSON-RPC call the server forwards upstream.
+/// `None` lets any well-formed request through.
+#[derive(Debug, Clone, Default)]
+pub struct RpcPolicy {
+    allowed_methods: Option<HashSet<String>>,
+}
+
+impl RpcPolicy {
+    pub fn allow_all() -> Self {
+        Self::default()
+    }
+
+    pub fn allowlist<I, S>(methods: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            allowed_methods: Some(methods.into_iter().map(Into::into).collect()),
+        }
+    }
+
+    pub fn check_method(&self, method: &str) -> Result<()> {
+        match &self.allowed_methods {
+            Some(allowed) if !allowed.contains(method) => Err(Error::coded(
+                ErrorCode::MethodNotAllowed,
+                format!("RPC method '{method}' is not allowed"),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// `method` as a metrics label. Without an allowlist any string reaches
+    /// this point, so unknown methods are folded into one label.
+    pub fn method_label<'a>(&self, method: &'a str) -> &'a str {
+        match &self.allowed_methods {
+            Some(_) => method,
+            None => "other",
+        }
+    }
+
+    /// Parses a raw JSON-RPC body (single request or batch) and checks every
+    /// method against the allowlist.
+    pub fn check_body(&self, body: &str) -> Result<Vec<JsonRpcRequest>> {
+        let value: Value = serde_json::from_str(body)
+            .map_err(|e| Error::coded(ErrorCode::InvalidJsonRpc, format!("Invalid JSON-RPC request: {e}")))?;
+
+        let requests = match value {
+            Value::Array(batch) if batch.is_empty() => {
+                return Err(Error::coded(ErrorCode::InvalidJsonRpc, "Empty JSON-RPC batch"))
+            }
+            Value::Array(batch) => batch
+                .into_iter()
+                .map(serde_json::from_value)
+                .collect::<std::result::Result<Vec<JsonRpcRequest>, _>>(),
+            single => serde_json::from_value(single).map(|req| vec![req]),
+        }
+        .map_err(|e| Error::coded(ErrorCode::InvalidJsonRpc, format!("Invalid JSON-RPC request: {e}")))?;
+
+        for request in &requests {
+            self.check_method(&request.method)?;
+        }
+
+        Ok(requests)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allowlist_rejects_unknown_methods() {
+        let policy = RpcPolicy::allowlist(["getblockcount"]);
+        assert!(policy.check_method("getblockcount").is_ok());
+        assert_eq!(policy.check_method("stop").unwrap_err().code(), ErrorCode::MethodNotAllowed);
+    }
+
+    #[test]
+    fn batch_is_checked_per_method() {
+        let policy = RpcPolicy::allowlist(["get_block_count"]);
+        let body = r#"[
+            {"jsonrpc":"2.0","id":1,"method":"get_block_count","params":[]},
+            {"jsonrpc":"2.0","id":2,"method":"send_transaction","params":[]}
+        ]"#;
+        assert_eq!(policy.check_body(body).unwrap_err().code(), ErrorCode::MethodNotAllowed);
+    }
+
+    #[test]
+    fn allow_all_still_requires_valid_json_rpc() {
+        let policy = RpcPolicy::allow_all();
+        assert!(policy
+            .check_body(r#"{"jsonrpc":"2.0","id":1,"method":"get_block_count"}"#)
+            .is_ok());
+        assert_eq!(policy.check_body("{}").unwrap_err().code(), ErrorCode::InvalidJsonRpc);
+    }
+}
@@ -0,0 +1,228 @@
+//! SDK profiles: the dependency sets user programs are compiled against.
+//! Each profile is a `Cargo.toml` template; builds get it with their library
+//! name filled in and the warmup pre-compiles every dependency it lists.
+//!
+//! Each profile also has a curated `Cargo.lock`, committed as
+//! `sdk/<profile>/Cargo.lock` next to `Cargo.toml` and compiled in, that
+//! every build is locked to. Regenerate the lockfiles with
+//! `cargo run -- --update-lockfiles`, check them with `--verify-lockfiles`.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Context};
+use bitcoin::hashes::{sha256, Hash as _};
+use tokio::process::Command;
+
+/// Profile builds use.
+pub const DEFAULT_PROFILE: &str = "arch-0.5";
+
+/// Every known profile; `build.warmup_profiles` names a subset.
+pub const PROFILES: &[SdkProfile] = &[SdkProfile {
+    name: DEFAULT_PROFILE,
+    manifest: ARCH_0_5_MANIFEST,
+    lockfile: include_str!("../sdk/arch-0.5/Cargo.lock"),
+    // Newer bytemuck_derive releases need a newer rustc than platform-tools
+    // ships, and newer bytemuck releases need a newer bytemuck_derive
+    pins: &[("bytemuck", "1.20.0"), ("bytemuck_derive", "1.5.0")],
+}];
+
+#[derive(Debug)]
+pub struct SdkProfile {
+    pub name: &'static str,
+    /// `Cargo.toml` with `{}` in place of the library name. The package name
+    /// is the same for every build, so one lockfile fits them all.
+    manifest: &'static str,
+    pub lockfile: &'static str,
+    /// `(package, version)` held back by `--update-lockfiles`
+    pins: &'static [(&'static str, &'static str)],
+}
+
+impl SdkProfile {
+    pub fn find(name: &str) -> Option<&'static SdkProfile> {
+        PROFILES.iter().find(|profile| profile.name == name)
+    }
+
+    pub fn default_profile() -> &'static SdkProfile {
+        Self::find(DEFAULT_PROFILE).expect("default profile is listed")
+    }
+
+    /// The manifest of a program whose binary is `<lib>.so`.
+    pub fn manifest(&self, lib: &str) -> String {
+        self.manifest.replace("{}", lib)
+    }
+
+    /// Library names of the `[dependencies]`, as they are written in Rust code.
+    pub fn dependencies(&self) -> Vec<String> {
+        let manifest: toml::Table = toml::from_str(&self.manifest("profile")).expect("profile manifests are valid TOML");
+        manifest
+            .get("dependencies")
+            .and_then(toml::Value::as_table)
+            .map(|dependencies| dependencies.keys().map(|name| name.replace('-', "_")).collect())
+            .unwrap_or_default()
+    }
+
+    /// A program that links every dependency, so building it compiles all of
+    /// them into the shared target directory.
+    pub fn warmup_source(&self) -> String {
+        let mut source = format!(
+            "//! Generated to pre-compile the dependencies of the `{}` SDK profile.\n\n",
+            self.name
+        );
+        for dependency in self.dependencies() {
+            source.push_str(&format!("use {dependency} as _;\n"));
+        }
+        source.push_str(WARMUP_ENTRYPOINT);
+        source
+    }
+
+    /// Hex SHA-256 of what a warmup build depends on: the manifest, the
+    /// lockfile, the warmup source and the toolchain versions. A warmup whose
+    /// recorded fingerprint differs is stale.
+    pub fn fingerprint(&self, toolchain_versions: &[Option<&str>]) -> String {
+        let mut input = self.manifest("warmup");
+        input.push_str(self.lockfile);
+        input.push_str(&self.warmup_source());
+        for version in toolchain_versions {
+            input.push('\n');
+            input.push_str(version.unwrap_or_default());
+        }
+        hex::encode(sha256::Hash::hash(input.as_bytes()).to_byte_array())
+    }
+
+    /// Resolves the manifest afresh, applies the pins and writes the result
+    /// over the committed lockfile. Rebuild the server to pick it up.
+    pub async fn update_lockfile(&self, cargo: &Path) -> anyhow::Result<PathBuf> {
+        let project = self.scratch_project()?;
+        cargo_in(cargo, &project, &["generate-lockfile"]).await?;
+        for (package, version) in self.pins {
+            cargo_in(cargo, &project, &["update", "--package", package, "--precise", version]).await?;
+        }
+
+        let committed = Path::new(env!("CARGO_MANIFEST_DIR")).join("sdk").join(self.name).join("Cargo.lock");
+        fs::create_dir_all(committed.parent().expect("lockfile has a parent"))?;
+        fs::copy(project.join("Cargo.lock"), &committed)?;
+        let _ = fs::remove_dir_all(&project);
+        Ok(committed)
+    }
+
+    /// Checks that the compiled-in lockfile satisfies the manifest without
+    /// changes and that every locked package can be fetched.
+    pub async fn verify_lockfile(&self, cargo: &Path) -> anyhow::Result<()> {
+        let project = self.scratch_project()?;
+        fs::write(project.join("Cargo.lock"), self.lockfile)?;
+        let result = cargo_in(cargo, &project, &["fetch", "--locked"]).await;
+        let _ = fs::remove_dir_all(&project);
+        result
+    }
+
+    /// An empty library with the profile's manifest in a fresh temporary directory.
+    fn scratch_project(&self) -> anyhow::Result<PathBuf> {
+        let project = env::temp_dir().join(format!("arch-sdk-{}-{}", self.name, std::process::id()));
+        let _ = fs::remove_dir_all(&project);
+        fs::create_dir_all(project.join("src"))?;
+        fs::write(project.join("Cargo.toml"), self.manifest("program"))?;
+        fs::write(project.join("src/lib.rs"), "")?;
+        Ok(project)
+    }
+}
+
+async fn cargo_in(cargo: &Path, project: &Path, args: &[&str]) -> anyhow::Result<()> {
+    let output = Command::new(cargo)
+        .args(args)
+        .current_dir(project)
+        .output()
+        .await
+        .with_context(|| format!("failed to run {}", cargo.display()))?;
+    if !output.status.success() {
+        return Err(anyhow!("cargo {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(())
+}
+
+const WARMUP_ENTRYPOINT: &str = r#"
+use arch_program::{account::AccountInfo, entrypoint, program_error::ProgramError, pubkey::Pubkey};
+
+entrypoint!(process_instruction);
+
+pub fn process_instruction(
+    _program_id: &Pubkey,
+    _accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+) -> Result<(), ProgramError> {
+    Ok(())
+}
+"#;
+
+const ARCH_0_5_MANIFEST: &str = r#"[package]
+name = "program"
+version = "0.1.0"
+edition = "2021"
+
+[lib]
+name = "{}"
+crate-type = ["cdylib"]
+
+[dependencies]
+arch_program = "0.5.15"
+apl-associated-token-account = { version = "0.5.15", features = ["no-entrypoint"] }
+apl-token = { version = "0.5.15", features = ["no-entrypoint"] }
+apl-token-metadata = { version = "0.5.15", features = ["no-entrypoint"] }
+
+# Satellite framework
+satellite-lang = "0.31.5"
+satellite-apl = "0.31.4"
+
+# Core serialization/encoding
+borsh = "^1.5.3"
+base64 = { version = "=0.22.1", default-features = false, features = ["alloc"] }
+hex = { version = "=0.4.3", default-features = false }
+sha256 = { version = "=1.5.0", default-features = false }
+
+# Error handling
+thiserror = "^1.0.57"
+
+# Serialization
+serde = { version = "^1.0.216", features = ["derive"], default-features = false }
+
+# Memory casting utilities
+bytemuck = { version = "^1.20.0", features = ["derive"] }
+
+[profile.release]
+overflow-checks = true
+incremental = true
+codegen-units = 256
+opt-level = 1
+lto = false
+debug = false
+
+[profile.release.build-override]
+opt-level = 1
+incremental = true
+codegen-units = 256
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lockfile_holds_pins_and_warmup_uses_every_dependency() {
+        let profile = SdkProfile::default_profile();
+        let source = profile.warmup_source();
+        for dependency in ["arch_program", "apl_token", "apl_associated_token_account", "satellite_lang", "bytemuck"] {
+            assert!(source.contains(&format!("use {dependency} as _;")), "{dependency} not used:\n{source}");
+        }
+
+        for (package, version) in profile.pins {
+            let locked = format!("name = \"{package}\"\nversion = \"{version}\"\n");
+            assert!(profile.lockfile.contains(&locked), "{package} is not locked to {version}");
+        }
+
+        let fingerprint = profile.fingerprint(&[Some("solana-cargo-build-sbf 2.2.20")]);
+        assert_eq!(fingerprint, profile.fingerprint(&[Some("solana-cargo-build-sbf 2.2.20")]));
+        assert_ne!(fingerprint, profile.fingerprint(&[Some("solana-cargo-build-sbf 2.3.0")]));
+    }
+}
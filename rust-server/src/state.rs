@@ -0,0 +1,152 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use axum::extract::FromRef;
+
+use crate::{
+    auth::Auth,
+    build_tracker::BuildTracker,
+    config::{BitcoinRpcConfig, Config, SharedConfig},
+    localnet::Localnet,
+    program::Programs,
+    rpc_policy::RpcPolicy,
+    upstreams::UpstreamPool,
+    usage::UsageTracker,
+};
+
+#[derive(Clone)]
+pub struct AppState {
+    pub config: SharedConfig,
+    pub auth: Auth,
+    pub build_tracker: BuildTracker,
+    pub rpc: RpcState,
+    pub localnet: Localnet,
+    pub usage: UsageTracker,
+    pub programs: Programs,
+}
+
+/// Everything the RPC proxy routes need: upstream credentials and the
+/// policy applied before a request leaves the server.
+#[derive(Clone)]
+pub struct RpcState {
+    pub client: reqwest::Client,
+    pub upstreams: UpstreamPool,
+    rules: Arc<RwLock<Arc<RpcRules>>>,
+}
+
+/// The parts of `RpcState` replaced as a whole when the config is reloaded.
+pub struct RpcRules {
+    pub arch_policy: RpcPolicy,
+    pub bitcoin: HashMap<String, BitcoinNetwork>,
+}
+
+pub struct BitcoinNetwork {
+    pub config: BitcoinRpcConfig,
+    pub policy: RpcPolicy,
+}
+
+impl RpcRules {
+    pub fn from_config(config: &Config) -> Self {
+        let arch_policy = match &config.rpc_allowed_methods {
+            Some(methods) => RpcPolicy::allowlist(methods.iter().cloned()),
+            None => RpcPolicy::allow_all(),
+        };
+
+        let bitcoin = config
+            .bitcoin_rpc
+            .iter()
+            .map(|(name, config)| {
+                let network = BitcoinNetwork {
+                    policy: RpcPolicy::allowlist(config.methods(name)),
+                    config: config.clone(),
+                };
+                (name.clone(), network)
+            })
+            .collect();
+
+        Self { arch_policy, bitcoin }
+    }
+}
+
+impl RpcState {
+    /// The rules in effect now; a request keeps using the snapshot it started with.
+    pub fn rules(&self) -> Arc<RpcRules> {
+        self.rules.read().unwrap().clone()
+    }
+
+    pub fn set_rules(&self, rules: RpcRules) {
+        *self.rules.write().unwrap() = Arc::new(rules);
+    }
+}
+
+impl AppState {
+    pub fn new(config: SharedConfig, programs: Programs, build_tracker: BuildTracker) -> anyhow::Result<Self> {
+        let current = config.current();
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(current.rpc_request_timeout_secs))
+            .build()?;
+
+        let upstreams = UpstreamPool::new(
+            &current.rpc_upstreams,
+            current.rpc_default_network.clone(),
+            current.rpc_max_block_lag,
+            std::time::Duration::from_secs(current.rpc_max_stall_secs),
+        )?;
+
+        let localnet = Localnet::new(current.localnet.clone(), upstreams.clone())?;
+        let auth = Auth::load(current.api_keys_file.as_deref(), !current.require_api_key)?;
+        let usage = UsageTracker::new(current.quota_anonymous.clone(), current.quota_authenticated.clone());
+
+        Ok(Self {
+            rpc: RpcState {
+                client,
+                upstreams,
+                rules: Arc::new(RwLock::new(Arc::new(RpcRules::from_config(&current)))),
+            },
+            config,
+            auth,
+            build_tracker,
+            localnet,
+            usage,
+            programs,
+        })
+    }
+}
+
+impl FromRef<AppState> for BuildTracker {
+    fn from_ref(state: &AppState) -> Self {
+        state.build_tracker.clone()
+    }
+}
+
+impl FromRef<AppState> for RpcState {
+    fn from_ref(state: &AppState) -> Self {
+        state.rpc.clone()
+    }
+}
+
+impl FromRef<AppState> for Localnet {
+    fn from_ref(state: &AppState) -> Self {
+        state.localnet.clone()
+    }
+}
+
+impl FromRef<AppState> for Auth {
+    fn from_ref(state: &AppState) -> Self {
+        state.auth.clone()
+    }
+}
+
+impl FromRef<AppState> for UsageTracker {
+    fn from_ref(state: &AppState) -> Self {
+        state.usage.clone()
+    }
+}
+
+impl FromRef<AppState> for Programs {
+    fn from_ref(state: &AppState) -> Self {
+        state.programs.clone()
+    }
+}
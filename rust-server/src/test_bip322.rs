@@ -1,110 +1,120 @@
-// Test program to generate reference BIP-322 signatures for comparison with TypeScript
-use arch_program::hash::Hash;
-use arch_program::pubkey::Pubkey;
-use arch_program::sanitized::{ArchMessage, MessageHeader, SanitizedInstruction};
-use arch_sdk::helper::sign_message_bip322;
-use bitcoin::key::{Keypair, UntweakedKeypair};
-use bitcoin::secp256k1::Secp256k1;
-use bitcoin::Network;
-use std::str::FromStr;
-
-pub fn test_message_hashing() {
-    println!("=== Testing ArchMessage Hashing ===\n");
-
-    // Create a test keypair
-    let secp = Secp256k1::new();
-    let (secret_key, _public_key) = secp.generate_keypair(&mut rand::thread_rng());
-    let keypair = UntweakedKeypair::from_secret_key(&secp, &secret_key);
-    let xpubkey = keypair.x_only_public_key().0;
-    let pubkey = Pubkey::from_slice(&xpubkey.serialize());
-
-    println!("Test Keypair:");
-    println!("  Private key: {}", hex::encode(secret_key.secret_bytes()));
-    println!("  Public key: {}", hex::encode(pubkey.serialize()));
-    println!();
-
-    // Create a simple test message
-    let recent_blockhash = Hash::from([1u8; 32]);
-    let program_id = Pubkey::from([2u8; 32]);
-    let instruction_data = vec![0x00, 0x01, 0x02, 0x03];
-
-    let message = ArchMessage {
+//! Reference checks for BIP-322 signing of `ArchMessage`s, kept in sync with
+//! the vectors in `frontend/src/utils/__tests__/test-bip322.ts`.
+
+use base64::Engine;
+use bitcoin::{
+    hashes::{sha256, Hash},
+    key::{Keypair, Secp256k1},
+    sighash::TapSighashType,
+    Address, Network, PrivateKey,
+};
+
+use crate::{
+    arch::{ArchMessage, MessageHeader, SanitizedInstruction},
+    bip322::{self, VerifyError},
+};
+
+/// Output of the original `arch_sdk` run, also used by the TypeScript signer test.
+const PRIVATE_KEY: &str = "b75a71ac262cdf072fc0b8dd813e1ef56ca77b957f7ae0a7998ce7f613b70351";
+const PUBLIC_KEY: &str = "1fbe8883da60fc9239cf2471c5a63fd3286bb1def2d3bb1b409d1ce82095da76";
+const SERIALIZED_MESSAGE: &str = "010000020000001fbe8883da60fc9239cf2471c5a63fd3286bb1def2d3bb1b409d1ce82095da7602020202020202020202020202020202020202020202020202020202020202020101010101010101010101010101010101010101010101010101010101010101010000000101000000000400000000010203";
+const MESSAGE_HASH: &str = "62313762326232643639373832623466323566633066646533623466316339396261656666366139373866363430346135336164616263363961333963383830";
+const SIGNATURE: &str = "bc552cdbfd195014552400c63d4fe8d1e696f274f4a5294fae738dc96af2f7e86ec43b18f6e8076107104ec0691cbc9c4d870240325b5ed7713497e55e3029d0";
+
+fn reference_keypair() -> Keypair {
+    Keypair::from_seckey_str(&Secp256k1::new(), PRIVATE_KEY).unwrap()
+}
+
+fn reference_message() -> ArchMessage {
+    let pubkey: [u8; 32] = hex::decode(PUBLIC_KEY).unwrap().try_into().unwrap();
+    ArchMessage {
         header: MessageHeader {
             num_required_signatures: 1,
             num_readonly_signed_accounts: 0,
             num_readonly_unsigned_accounts: 0,
         },
-        account_keys: vec![pubkey, program_id],
-        recent_blockhash,
+        account_keys: vec![pubkey, [2u8; 32]],
+        recent_blockhash: [1u8; 32],
         instructions: vec![SanitizedInstruction {
             program_id_index: 1,
             accounts: vec![0],
-            data: instruction_data.clone(),
+            data: vec![0x00, 0x01, 0x02, 0x03],
         }],
-    };
+    }
+}
 
-    // Serialize the message
+#[test]
+fn message_serializes_and_hashes_like_arch_sdk() {
+    let keypair = reference_keypair();
+    assert_eq!(hex::encode(keypair.x_only_public_key().0.serialize()), PUBLIC_KEY);
+
+    let message = reference_message();
     let serialized = message.serialize();
-    println!("Serialized message ({} bytes):", serialized.len());
-    println!("  Hex: {}", hex::encode(&serialized));
-    println!();
-
-    // Hash the message
-    let message_hash = message.hash();
-    println!("Message hash ({} bytes):", message_hash.len());
-    println!("  Hex: {}", hex::encode(&message_hash));
-    println!();
-
-    // Let's manually show the double-hash process
-    let first_hash_hex = sha256::digest(&serialized);
-    println!("First hash (hex string): {}", first_hash_hex);
-
-    let first_hash_bytes = first_hash_hex.as_bytes();
-    println!("First hash as UTF-8 bytes ({} bytes): {}", first_hash_bytes.len(), hex::encode(first_hash_bytes));
-
-    let second_hash_hex = sha256::digest(first_hash_bytes);
-    println!("Second hash (hex string): {}", second_hash_hex);
-
-    let second_hash_bytes = second_hash_hex.as_bytes();
-    println!("Second hash as UTF-8 bytes ({} bytes): {}", second_hash_bytes.len(), hex::encode(second_hash_bytes));
-    println!();
-
-    // Sign the message
-    let signature = sign_message_bip322(&keypair, &message_hash, Network::Testnet);
-    println!("BIP-322 Signature:");
-    println!("  Hex: {}", hex::encode(&signature));
-    println!();
-
-    // Verify the signature
-    match arch_sdk::verify_message_bip322(
-        &message_hash,
-        pubkey.serialize(),
-        signature,
-        false,
-        Network::Testnet,
-    ) {
-        Ok(_) => println!("✓ Signature verification successful!"),
-        Err(e) => println!("✗ Signature verification failed: {}", e),
-    }
-    println!();
-
-    // Output JSON for TypeScript testing
-    println!("JSON Test Data:");
-    println!("{{");
-    println!("  \"privateKey\": \"{}\",", hex::encode(secret_key.secret_bytes()));
-    println!("  \"publicKey\": \"{}\",", hex::encode(pubkey.serialize()));
-    println!("  \"serializedMessage\": \"{}\",", hex::encode(&serialized));
-    println!("  \"messageHash\": \"{}\",", hex::encode(&message_hash));
-    println!("  \"signature\": \"{}\"", hex::encode(&signature));
-    println!("}}");
+    assert_eq!(serialized.len(), 121);
+    assert_eq!(hex::encode(&serialized), SERIALIZED_MESSAGE);
+    assert_eq!(ArchMessage::deserialize(&serialized).unwrap(), message);
+
+    // The signed bytes are the hex string of the second hash, not the digest itself
+    let hash = message.hash();
+    assert_eq!(hex::encode(&hash), MESSAGE_HASH);
+    let first = sha256::Hash::hash(&serialized).to_string();
+    assert_eq!(hash, sha256::Hash::hash(first.as_bytes()).to_string().into_bytes());
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+#[test]
+fn arch_sdk_signature_verifies_with_sighash_all() {
+    let signature = hex::decode(SIGNATURE).unwrap();
+    let pubkey = hex::decode(PUBLIC_KEY).unwrap();
 
-    #[test]
-    fn run_test() {
-        test_message_hashing();
-    }
+    let sighash_type = bip322::verify_message(&pubkey, &reference_message().hash(), &signature).unwrap();
+    assert_eq!(sighash_type, TapSighashType::All);
+}
+
+#[test]
+fn signatures_round_trip_and_reject_tampering() {
+    let keypair = reference_keypair();
+    let pubkey = keypair.x_only_public_key().0.serialize();
+    let hash = reference_message().hash();
+    let signature = bip322::sign_message(&keypair, &hash);
+
+    assert_eq!(bip322::verify_message(&pubkey, &hash, &signature).unwrap(), TapSighashType::All);
+
+    let mut with_sighash_byte = signature.to_vec();
+    with_sighash_byte.push(TapSighashType::All as u8);
+    assert!(bip322::verify_message(&pubkey, &hash, &with_sighash_byte).is_ok());
+
+    let mut tampered = hash.clone();
+    tampered[0] ^= 1;
+    assert!(matches!(bip322::verify_message(&pubkey, &tampered, &signature), Err(VerifyError::Mismatch)));
+
+    let other = Keypair::new(&Secp256k1::new(), &mut bitcoin::secp256k1::rand::thread_rng());
+    let other_pubkey = other.x_only_public_key().0.serialize();
+    assert!(matches!(bip322::verify_message(&other_pubkey, &hash, &signature), Err(VerifyError::Mismatch)));
+
+    assert!(matches!(
+        bip322::verify_message(&pubkey, &hash, &signature[..63]),
+        Err(VerifyError::InvalidSignatureLength(63))
+    ));
+}
+
+/// The P2TR "Hello World" vector from the BIP-322 specification.
+#[test]
+fn bip322_spec_vector_verifies() {
+    let secp = Secp256k1::new();
+    let private_key = PrivateKey::from_wif("L3VFeEujGtevx9w18HD1fhRbCH67Az2dpCymeRE1SoPK6XQtaN2k").unwrap();
+    let keypair = Keypair::from_secret_key(&secp, &private_key.inner);
+    let (internal_key, _) = keypair.x_only_public_key();
+    assert_eq!(
+        Address::p2tr(&secp, internal_key, None, Network::Bitcoin).to_string(),
+        "bc1ppv609nr0vr25u07u95waq5lucwfm6tde4nydujnu8npg4q75mr5sxq8lt3"
+    );
+
+    // The spec encodes the full witness stack: one item holding signature + sighash byte
+    let witness = base64::engine::general_purpose::STANDARD
+        .decode("AUHd69PrJQEv+oKTfZ8l+WROBHuy9HKrbFCJu7U1iK2iiEy1vMU5EfMtjc+VSHM7aU0SDbak5IUZRVno2P5mjSafAQ==")
+        .unwrap();
+    assert_eq!(&witness[..2], &[0x01, 0x41]);
+
+    let sighash_type = bip322::verify_message(&internal_key.serialize(), b"Hello World", &witness[2..]).unwrap();
+    assert_eq!(sighash_type, TapSighashType::All);
 }
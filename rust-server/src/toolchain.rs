@@ -0,0 +1,163 @@
+//! The build toolchain, found once at startup: cargo, cargo-build-sbf and the
+//! Solana platform-tools rustc that cargo-build-sbf compiles with. Nothing is
+//! ever installed by the server; missing tools make it refuse to start or
+//! report not ready, depending on `build.require_toolchain`.
+
+use std::{
+    env,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use serde::Serialize;
+use tokio::process::Command;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+/// An installed tool and the first line of its `--version`.
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct Tool {
+    #[schema(value_type = Option<String>)]
+    pub path: Option<PathBuf>,
+    pub version: Option<String>,
+}
+
+impl Tool {
+    fn installed(&self) -> bool {
+        self.path.is_some()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct Toolchain {
+    pub cargo: Tool,
+    #[serde(rename = "cargo-build-sbf")]
+    pub cargo_build_sbf: Tool,
+    /// rustc of the newest platform-tools under `~/.cache/solana`
+    #[serde(rename = "platform-tools")]
+    pub platform_tools: Tool,
+}
+
+impl Toolchain {
+    pub async fn detect() -> Self {
+        let (cargo, cargo_build_sbf, platform_tools) = tokio::join!(
+            probe(find_in_path("cargo")),
+            probe(find_in_path("cargo-build-sbf")),
+            probe(find_platform_tools_rustc()),
+        );
+        let toolchain = Self { cargo, cargo_build_sbf, platform_tools };
+        for (name, tool) in toolchain.tools() {
+            match (&tool.path, &tool.version) {
+                (Some(path), version) => info!(
+                    "Found {} at {}: {}",
+                    name,
+                    path.display(),
+                    version.as_deref().unwrap_or("unknown version")
+                ),
+                (None, _) => warn!("{} is not installed", name),
+            }
+        }
+        toolchain
+    }
+
+    fn tools(&self) -> [(&'static str, &Tool); 3] {
+        [
+            ("cargo", &self.cargo),
+            ("cargo-build-sbf", &self.cargo_build_sbf),
+            ("platform-tools", &self.platform_tools),
+        ]
+    }
+
+    /// Names of the tools builds need but that were not found.
+    pub fn missing(&self) -> Vec<&'static str> {
+        self.tools().into_iter().filter(|(_, tool)| !tool.installed()).map(|(name, _)| name).collect()
+    }
+
+    /// Fails with what is missing, pointing at how to install it outside the server.
+    pub fn ensure_complete(&self) -> anyhow::Result<()> {
+        let missing = self.missing();
+        if missing.is_empty() {
+            return Ok(());
+        }
+        anyhow::bail!(
+            "Build toolchain incomplete, missing {}; install the Solana CLI and run `cargo-build-sbf --install-only`",
+            missing.join(", ")
+        )
+    }
+
+    /// `cargo` as found on `PATH`; only used when the toolchain is complete.
+    pub fn cargo(&self) -> &Path {
+        self.cargo.path.as_deref().unwrap_or(Path::new("cargo"))
+    }
+
+    pub fn cargo_build_sbf(&self) -> &Path {
+        self.cargo_build_sbf.path.as_deref().unwrap_or(Path::new("cargo-build-sbf"))
+    }
+
+    /// Cargo 1.75 can only read the lockfiles newer cargos write with `-Znext-lockfile-bump`.
+    pub fn needs_lockfile_bump(&self) -> bool {
+        self.cargo.version.as_deref().is_some_and(|version| version.contains(" 1.75."))
+    }
+}
+
+pub fn find_in_path(name: &str) -> Option<PathBuf> {
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|dir| dir.join(name))
+        .find(|path| path.is_file())
+}
+
+/// The platform-tools install with the highest version, e.g. `~/.cache/solana/v1.43`.
+fn find_platform_tools_rustc() -> Option<PathBuf> {
+    let cache = PathBuf::from(env::var_os("HOME").unwrap_or_else(|| "/root".into())).join(".cache/solana");
+    std::fs::read_dir(cache)
+        .ok()?
+        .flatten()
+        .filter_map(|entry| {
+            let rustc = entry.path().join("platform-tools/rust/bin/rustc");
+            let version = version_key(&entry.file_name().to_string_lossy());
+            rustc.is_file().then_some((version, rustc))
+        })
+        .max()
+        .map(|(_, rustc)| rustc)
+}
+
+/// Numeric components of a version like `v1.43`, so that it sorts after `v1.5`.
+fn version_key(name: &str) -> Vec<u64> {
+    name.trim_start_matches('v').split('.').map(|part| part.parse().unwrap_or(0)).collect()
+}
+
+async fn probe(path: Option<PathBuf>) -> Tool {
+    let version = match &path {
+        Some(path) => {
+            let output = tokio::time::timeout(Duration::from_secs(5), Command::new(path).arg("--version").output()).await;
+            match output {
+                Ok(Ok(output)) if output.status.success() => {
+                    String::from_utf8_lossy(&output.stdout).lines().next().map(str::to_string)
+                }
+                _ => None,
+            }
+        }
+        None => None,
+    };
+    Tool { path, version }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_tools_and_cargo_quirks() {
+        let tool = |version: &str| Tool { path: Some(PathBuf::from("/bin/true")), version: Some(version.to_string()) };
+        let toolchain = Toolchain {
+            cargo: tool("cargo 1.75.0 (1d8b05cdd 2023-11-20)"),
+            cargo_build_sbf: tool("solana-cargo-build-sbf 2.2.20"),
+            platform_tools: Tool::default(),
+        };
+        assert_eq!(toolchain.missing(), ["platform-tools"]);
+        assert!(toolchain.ensure_complete().is_err());
+        assert!(toolchain.needs_lockfile_bump());
+
+        assert!(version_key("v1.43") > version_key("v1.5"));
+    }
+}
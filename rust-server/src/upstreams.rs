@@ -0,0 +1,460 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, RwLock as StdRwLock},
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use tokio::sync::RwLock;
+use tracing::{debug, error, info, warn};
+use utoipa::ToSchema;
+
+use crate::{
+    error::{Error, ErrorCode, Result},
+    metrics::metrics,
+};
+
+/// Health of a single upstream RPC URL as seen by the last probe or proxied call.
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct UpstreamHealth {
+    pub healthy: bool,
+    pub block_height: Option<u64>,
+    pub latency_ms: Option<u64>,
+    pub last_checked: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_error: Option<String>,
+    pub consecutive_failures: u32,
+    /// When a probe last saw `block_height` go up
+    pub height_advanced_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+pub struct Upstream {
+    pub url: String,
+    health: StdRwLock<UpstreamHealth>,
+}
+
+impl Upstream {
+    fn new(url: String) -> Self {
+        Self {
+            url,
+            // Assume healthy until the first probe says otherwise so requests
+            // are not rejected during startup.
+            health: StdRwLock::new(UpstreamHealth { healthy: true, ..Default::default() }),
+        }
+    }
+
+    pub fn health(&self) -> UpstreamHealth {
+        self.health.read().unwrap().clone()
+    }
+
+    pub fn record_success(&self, latency: Duration) {
+        let mut health = self.health.write().unwrap();
+        health.healthy = true;
+        health.latency_ms = Some(latency.as_millis() as u64);
+        health.consecutive_failures = 0;
+        health.last_error = None;
+    }
+
+    pub fn record_failure(&self, error: impl Into<String>) {
+        let mut health = self.health.write().unwrap();
+        health.healthy = false;
+        health.consecutive_failures += 1;
+        health.last_error = Some(error.into());
+    }
+
+    /// Records a health probe that read block `height`. The upstream is
+    /// unhealthy when it lags `best_height` by more than `limits.max_block_lag`
+    /// blocks, or its height has not advanced for `limits.max_stall`.
+    fn record_probe(
+        &self,
+        height: u64,
+        latency: Duration,
+        best_height: u64,
+        limits: &Limits,
+        now: chrono::DateTime<chrono::Utc>,
+    ) {
+        let mut health = self.health.write().unwrap();
+        if health.block_height.is_none_or(|previous| height > previous) {
+            health.height_advanced_at = Some(now);
+        }
+        health.block_height = Some(height);
+        health.latency_ms = Some(latency.as_millis() as u64);
+
+        let lag = best_height.saturating_sub(height);
+        let stalled = health
+            .height_advanced_at
+            .and_then(|at| (now - at).to_std().ok())
+            .filter(|stalled| !limits.max_stall.is_zero() && *stalled > limits.max_stall);
+        let problem = if lag > limits.max_block_lag {
+            Some(format!("{lag} blocks behind"))
+        } else {
+            stalled.map(|stalled| format!("block height stuck at {height} for {}s", stalled.as_secs()))
+        };
+        match problem {
+            Some(problem) => {
+                health.healthy = false;
+                health.consecutive_failures += 1;
+                health.last_error = Some(problem);
+            }
+            None => {
+                health.healthy = true;
+                health.consecutive_failures = 0;
+                health.last_error = None;
+            }
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct UpstreamStatus {
+    pub url: String,
+    #[serde(flatten)]
+    pub health: UpstreamHealth,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct NetworkStatus {
+    pub default: bool,
+    pub upstreams: Vec<UpstreamStatus>,
+}
+
+/// Named RPC networks, each backed by one or more upstream URLs that are
+/// probed periodically and tried in order of health and latency.
+#[derive(Clone)]
+pub struct UpstreamPool {
+    networks: Arc<RwLock<HashMap<String, Arc<Vec<Upstream>>>>>,
+    settings: Arc<StdRwLock<PoolSettings>>,
+    client: reqwest::Client,
+}
+
+/// What [`UpstreamPool::reconfigure`] replaces besides the networks.
+struct PoolSettings {
+    default_network: String,
+    limits: Limits,
+    /// Networks that came from the config, as opposed to `register`ed at runtime
+    configured: HashSet<String>,
+}
+
+/// How far behind an upstream may fall before probes mark it unhealthy.
+#[derive(Clone)]
+struct Limits {
+    max_block_lag: u64,
+    max_stall: Duration,
+}
+
+impl UpstreamPool {
+    pub fn new(
+        upstreams: &HashMap<String, Vec<String>>,
+        default_network: String,
+        max_block_lag: u64,
+        max_stall: Duration,
+    ) -> anyhow::Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(5))
+            .build()?;
+
+        let networks = upstreams
+            .iter()
+            .map(|(name, urls)| {
+                let upstreams = urls.iter().cloned().map(Upstream::new).collect();
+                (name.clone(), Arc::new(upstreams))
+            })
+            .collect();
+
+        Ok(Self {
+            networks: Arc::new(RwLock::new(networks)),
+            settings: Arc::new(StdRwLock::new(PoolSettings {
+                default_network,
+                limits: Limits { max_block_lag, max_stall },
+                configured: upstreams.keys().cloned().collect(),
+            })),
+            client,
+        })
+    }
+
+    pub fn default_network(&self) -> String {
+        self.settings.read().unwrap().default_network.clone()
+    }
+
+    /// Replaces the configured networks, keeping networks registered at
+    /// runtime. Networks whose URLs did not change keep their health history.
+    pub async fn reconfigure(
+        &self,
+        upstreams: &HashMap<String, Vec<String>>,
+        default_network: String,
+        max_block_lag: u64,
+        max_stall: Duration,
+    ) {
+        let mut networks = self.networks.write().await;
+        let mut settings = self.settings.write().unwrap();
+        for name in &settings.configured {
+            if !upstreams.contains_key(name) {
+                networks.remove(name);
+            }
+        }
+        for (name, urls) in upstreams {
+            let unchanged = networks
+                .get(name)
+                .is_some_and(|current| current.iter().map(|u| &u.url).eq(urls.iter()));
+            if !unchanged {
+                let fresh = urls.iter().cloned().map(Upstream::new).collect();
+                networks.insert(name.clone(), Arc::new(fresh));
+            }
+        }
+        *settings = PoolSettings {
+            default_network,
+            limits: Limits { max_block_lag, max_stall },
+            configured: upstreams.keys().cloned().collect(),
+        };
+    }
+
+    /// Adds or replaces a named network at runtime.
+    pub async fn register(&self, name: &str, urls: Vec<String>) {
+        let upstreams = urls.into_iter().map(Upstream::new).collect();
+        self.networks
+            .write()
+            .await
+            .insert(name.to_string(), Arc::new(upstreams));
+    }
+
+    pub async fn unregister(&self, name: &str) {
+        self.networks.write().await.remove(name);
+    }
+
+    /// Returns the upstreams of `network` in the order they should be tried:
+    /// healthy ones by ascending latency, then unhealthy ones as a last resort.
+    pub async fn candidates(&self, network: &str) -> Option<Vec<UpstreamRef>> {
+        let upstreams = self.networks.read().await.get(network)?.clone();
+
+        let mut order: Vec<(usize, UpstreamHealth)> = upstreams
+            .iter()
+            .enumerate()
+            .map(|(i, upstream)| (i, upstream.health()))
+            .collect();
+        order.sort_by_key(|(_, health)| {
+            (!health.healthy, health.latency_ms.unwrap_or(u64::MAX))
+        });
+
+        Some(
+            order
+                .into_iter()
+                .map(|(index, _)| UpstreamRef { upstreams: upstreams.clone(), index })
+                .collect(),
+        )
+    }
+
+    /// POSTs a JSON-RPC body to `network`, trying each upstream in turn until
+    /// one answers without a transport error or 5xx status.
+    pub async fn send(
+        &self,
+        client: &reqwest::Client,
+        network: &str,
+        method: &str,
+        body: String,
+    ) -> Result<reqwest::Response> {
+        let upstreams = self
+            .candidates(network)
+            .await
+            .ok_or_else(|| Error::coded(ErrorCode::NetworkNotFound, format!("RPC network '{}' not found", network)))?;
+
+        let mut last_error = String::from("No upstreams configured");
+        let mut timed_out = false;
+        for upstream in upstreams {
+            info!("Proxying RPC request to: {} ({})", upstream.url, network);
+            let start = Instant::now();
+
+            let result = client
+                .post(&upstream.url)
+                .header("Content-Type", "application/json")
+                .header("Accept", "application/json")
+                .body(body.clone())
+                .send()
+                .await;
+
+            let ok = result.as_ref().is_ok_and(|response| !response.status().is_server_error());
+            metrics().rpc_attempt(network, upstream.index, method, start.elapsed(), ok);
+
+            match result {
+                Ok(response) if response.status().is_server_error() => {
+                    last_error = format!("{} returned {}", upstream.url, response.status());
+                    timed_out = false;
+                    upstream.record_failure(last_error.clone());
+                }
+                Ok(response) => {
+                    upstream.record_success(start.elapsed());
+                    return Ok(response);
+                }
+                Err(e) => {
+                    last_error = format!("{}: {}", upstream.url, e);
+                    timed_out = e.is_timeout();
+                    upstream.record_failure(last_error.clone());
+                }
+            }
+            warn!("RPC upstream failed, trying next: {}", last_error);
+        }
+
+        error!("All RPC upstreams for '{}' failed", network);
+        Err(Error::upstream(format!("Failed to connect to RPC server: {}", last_error), timed_out))
+    }
+
+    pub async fn status(&self) -> HashMap<String, NetworkStatus> {
+        let networks = self.networks.read().await;
+        let default_network = self.default_network();
+        networks
+            .iter()
+            .map(|(name, upstreams)| {
+                let status = NetworkStatus {
+                    default: *name == default_network,
+                    upstreams: upstreams
+                        .iter()
+                        .map(|u| UpstreamStatus { url: u.url.clone(), health: u.health() })
+                        .collect(),
+                };
+                (name.clone(), status)
+            })
+            .collect()
+    }
+
+    /// Probes every upstream once. An upstream is healthy when it answers
+    /// `get_block_count`, is within `max_block_lag` blocks of the highest
+    /// height reported by the other upstreams of the same network, and its
+    /// height advanced within `max_stall`.
+    pub async fn check_all(&self) {
+        let networks: Vec<(String, Arc<Vec<Upstream>>)> = self
+            .networks
+            .read()
+            .await
+            .iter()
+            .map(|(name, upstreams)| (name.clone(), upstreams.clone()))
+            .collect();
+
+        let limits = self.settings.read().unwrap().limits.clone();
+        for (name, upstreams) in networks {
+            let probes = upstreams.iter().map(|upstream| self.probe(&upstream.url));
+            let results = futures_util::future::join_all(probes).await;
+
+            let best_height = results
+                .iter()
+                .filter_map(|r| r.as_ref().ok().map(|(height, _)| *height))
+                .max();
+
+            for (upstream, result) in upstreams.iter().zip(results) {
+                let now = chrono::Utc::now();
+                match result {
+                    Ok((height, latency)) => {
+                        upstream.record_probe(height, latency, best_height.unwrap_or(height), &limits, now)
+                    }
+                    Err(e) => upstream.record_failure(e),
+                }
+                let mut health = upstream.health.write().unwrap();
+                health.last_checked = Some(now);
+                if !health.healthy {
+                    warn!("RPC upstream {} ({}) unhealthy: {:?}", upstream.url, name, health.last_error);
+                } else {
+                    debug!("RPC upstream {} ({}) healthy at height {:?}", upstream.url, name, health.block_height);
+                }
+            }
+        }
+    }
+
+    async fn probe(&self, url: &str) -> std::result::Result<(u64, Duration), String> {
+        let start = Instant::now();
+        let response: Value = self
+            .client
+            .post(url)
+            .json(&json!({ "jsonrpc": "2.0", "id": "health", "method": "get_block_count", "params": [] }))
+            .send()
+            .await
+            .map_err(|e| e.to_string())?
+            .json()
+            .await
+            .map_err(|e| e.to_string())?;
+        let latency = start.elapsed();
+
+        response["result"]
+            .as_u64()
+            .map(|height| (height, latency))
+            .ok_or_else(|| format!("Unexpected get_block_count response: {response}"))
+    }
+
+    /// Runs `check_all` forever on a fixed interval.
+    pub fn spawn_health_checks(&self, interval: Duration) {
+        let pool = self.clone();
+        tokio::spawn(async move {
+            info!("Checking RPC upstream health every {}s", interval.as_secs());
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                pool.check_all().await;
+            }
+        });
+    }
+}
+
+/// Handle to one upstream that stays valid even if its network is replaced.
+pub struct UpstreamRef {
+    upstreams: Arc<Vec<Upstream>>,
+    index: usize,
+}
+
+impl std::ops::Deref for UpstreamRef {
+    type Target = Upstream;
+
+    fn deref(&self) -> &Upstream {
+        &self.upstreams[self.index]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn candidates_prefer_healthy_low_latency_upstreams() {
+        let pool = UpstreamPool::new(
+            &HashMap::from([(
+                "testnet".to_string(),
+                vec!["http://a".to_string(), "http://b".to_string(), "http://c".to_string()],
+            )]),
+            "testnet".to_string(),
+            5,
+            Duration::from_secs(60),
+        )
+        .unwrap();
+
+        let upstreams = pool.candidates("testnet").await.unwrap();
+        upstreams[0].record_failure("connection refused");
+        upstreams[1].record_success(Duration::from_millis(300));
+        upstreams[2].record_success(Duration::from_millis(20));
+
+        let order: Vec<String> = pool
+            .candidates("testnet")
+            .await
+            .unwrap()
+            .iter()
+            .map(|u| u.url.clone())
+            .collect();
+        assert_eq!(order, ["http://c", "http://b", "http://a"]);
+        assert!(pool.candidates("mainnet").await.is_none());
+    }
+
+    #[test]
+    fn a_sole_upstream_whose_height_stops_advancing_is_unhealthy() {
+        let upstream = Upstream::new("http://a".to_string());
+        let limits = Limits { max_block_lag: 5, max_stall: Duration::from_secs(60) };
+        let start = chrono::Utc::now();
+        let latency = Duration::from_millis(10);
+
+        upstream.record_probe(100, latency, 100, &limits, start);
+        upstream.record_probe(100, latency, 100, &limits, start + chrono::Duration::seconds(30));
+        assert!(upstream.health().healthy);
+
+        upstream.record_probe(100, latency, 100, &limits, start + chrono::Duration::seconds(90));
+        let health = upstream.health();
+        assert!(!health.healthy);
+        assert!(health.last_error.unwrap().contains("stuck at 100"));
+
+        upstream.record_probe(101, latency, 101, &limits, start + chrono::Duration::seconds(95));
+        assert!(upstream.health().healthy);
+    }
+}
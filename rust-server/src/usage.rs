@@ -0,0 +1,281 @@
+//! Per-principal usage accounting and daily quotas. Counters live in memory
+//! and roll over at midnight UTC, when accounts of anonymous callers that
+//! were idle that day are dropped.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard, RwLock},
+};
+
+use chrono::{NaiveDate, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::{
+    auth::Principal,
+    config::Quota,
+    error::{Error, Result},
+};
+
+#[derive(Debug, Clone, Copy, Default, Serialize, ToSchema)]
+pub struct Usage {
+    pub builds: u64,
+    /// CPU seconds the build toolchain used, across all of its processes
+    pub build_seconds: u64,
+    /// Bytes of program binaries produced
+    pub artifact_bytes: u64,
+    pub rpc_calls: u64,
+}
+
+impl Usage {
+    fn add(&mut self, other: &Usage) {
+        self.builds += other.builds;
+        self.build_seconds += other.build_seconds;
+        self.artifact_bytes += other.artifact_bytes;
+        self.rpc_calls += other.rpc_calls;
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct UsageReport {
+    pub principal: String,
+    pub day: NaiveDate,
+    pub today: Usage,
+    pub total: Usage,
+    /// `None` when the principal is not subject to quotas
+    pub limits: Option<Quota>,
+}
+
+#[derive(Default)]
+struct Account {
+    day: Option<NaiveDate>,
+    /// Keyed by client address, so there is one per anonymous caller seen
+    anonymous: bool,
+    today: Usage,
+    total: Usage,
+}
+
+impl Account {
+    fn roll_over(&mut self, today: NaiveDate) {
+        if self.day != Some(today) {
+            self.day = Some(today);
+            self.today = Usage::default();
+        }
+    }
+}
+
+#[derive(Default)]
+struct Accounts {
+    /// Day the accounts were last pruned
+    day: Option<NaiveDate>,
+    by_key: HashMap<String, Account>,
+}
+
+impl Accounts {
+    /// The account of `principal`, rolled over to `today`.
+    fn get(&mut self, principal: &Principal, today: NaiveDate) -> &mut Account {
+        let account = self.by_key.entry(principal.usage_key()).or_insert_with(|| Account {
+            anonymous: principal.is_anonymous(),
+            ..Default::default()
+        });
+        account.roll_over(today);
+        account
+    }
+}
+
+struct Tiers {
+    anonymous: Quota,
+    authenticated: Quota,
+}
+
+#[derive(Clone)]
+pub struct UsageTracker {
+    accounts: Arc<Mutex<Accounts>>,
+    tiers: Arc<RwLock<Tiers>>,
+}
+
+impl UsageTracker {
+    pub fn new(anonymous: Quota, authenticated: Quota) -> Self {
+        Self {
+            accounts: Arc::new(Mutex::new(Accounts::default())),
+            tiers: Arc::new(RwLock::new(Tiers { anonymous, authenticated })),
+        }
+    }
+
+    /// Locks the accounts. On the first call of a new day, anonymous accounts
+    /// not used since an earlier day are dropped: only today's usage counts
+    /// against their quota, and one is created per client address.
+    fn accounts(&self, today: NaiveDate) -> MutexGuard<'_, Accounts> {
+        let mut accounts = self.accounts.lock().unwrap();
+        if accounts.day != Some(today) {
+            accounts.day = Some(today);
+            accounts.by_key.retain(|_, account| !account.anonymous || account.day == Some(today));
+        }
+        accounts
+    }
+
+    /// Replaces the limits of both tiers. Usage already counted today is kept.
+    pub fn set_quotas(&self, anonymous: Quota, authenticated: Quota) {
+        *self.tiers.write().unwrap() = Tiers { anonymous, authenticated };
+    }
+
+    /// Admins are never limited; everyone else gets their tier's quota.
+    fn quota(&self, principal: &Principal) -> Option<Quota> {
+        let tiers = self.tiers.read().unwrap();
+        match principal {
+            Principal { admin: true, .. } => None,
+            principal if principal.is_anonymous() => Some(tiers.anonymous.clone()),
+            _ => Some(tiers.authenticated.clone()),
+        }
+    }
+
+    /// Checks `principal` against its quota and, if there is room, records `usage` for today.
+    fn charge(&self, principal: &Principal, usage: Usage) -> Result<()> {
+        let today = Utc::now().date_naive();
+        let mut accounts = self.accounts(today);
+        let account = accounts.get(principal, today);
+
+        if let Some(quota) = self.quota(principal) {
+            let used = account.today;
+            let starting_build = usage.builds > 0;
+            // Build time and output are only known once a build finishes, so a
+            // new build is refused as soon as either has reached its limit
+            let checks = [
+                ("builds", quota.builds_per_day, used.builds + usage.builds, starting_build),
+                ("build seconds", quota.build_seconds_per_day, used.build_seconds + 1, starting_build),
+                ("artifact bytes", quota.artifact_bytes_per_day, used.artifact_bytes + 1, starting_build),
+                ("RPC calls", quota.rpc_calls_per_day, used.rpc_calls + usage.rpc_calls, usage.rpc_calls > 0),
+            ];
+            let exceeded = checks
+                .into_iter()
+                .find_map(|(name, limit, value, applies)| limit.filter(|&limit| applies && value > limit).map(|limit| (name, limit)));
+            if let Some((name, limit)) = exceeded {
+                return Err(Error::QuotaExceeded(format!(
+                    "daily limit of {} {} reached, resets at midnight UTC",
+                    limit, name
+                )));
+            }
+        }
+
+        account.today.add(&usage);
+        account.total.add(&usage);
+        Ok(())
+    }
+
+    /// Counts a new build, refusing it when the build or build-time quota is spent.
+    pub fn start_build(&self, principal: &Principal) -> Result<()> {
+        self.charge(principal, Usage { builds: 1, ..Default::default() })
+    }
+
+    /// Records what a finished build cost. Never refused: the work is already done.
+    pub fn finish_build(&self, principal: &Principal, build_seconds: u64, artifact_bytes: u64) {
+        let today = Utc::now().date_naive();
+        let mut accounts = self.accounts(today);
+        let account = accounts.get(principal, today);
+        let usage = Usage { build_seconds, artifact_bytes, ..Default::default() };
+        account.today.add(&usage);
+        account.total.add(&usage);
+    }
+
+    /// Counts `calls` JSON-RPC requests, refusing them when the RPC quota would be exceeded.
+    pub fn rpc_calls(&self, principal: &Principal, calls: u64) -> Result<()> {
+        self.charge(principal, Usage { rpc_calls: calls, ..Default::default() })
+    }
+
+    pub fn report(&self, principal: &Principal) -> UsageReport {
+        let today = Utc::now().date_naive();
+        let mut accounts = self.accounts(today);
+        let account = accounts.get(principal, today);
+        UsageReport {
+            principal: principal.usage_key(),
+            day: today,
+            today: account.today,
+            total: account.total,
+            limits: self.quota(principal),
+        }
+    }
+
+    /// Usage of every principal seen since startup, heaviest builders first.
+    pub fn summary(&self) -> Vec<UsageReport> {
+        let today = Utc::now().date_naive();
+        let mut accounts = self.accounts(today);
+        let mut reports: Vec<_> = accounts
+            .by_key
+            .iter_mut()
+            .map(|(key, account)| {
+                account.roll_over(today);
+                UsageReport {
+                    principal: key.clone(),
+                    day: today,
+                    today: account.today,
+                    total: account.total,
+                    limits: None,
+                }
+            })
+            .collect();
+        reports.sort_by(|a, b| b.today.build_seconds.cmp(&a.today.build_seconds).then(a.principal.cmp(&b.principal)));
+        reports
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn principal(id: &str) -> Principal {
+        Principal { id: id.to_string(), admin: false, client_ip: None }
+    }
+
+    #[test]
+    fn quotas_apply_per_principal_and_skip_admins() {
+        let quota = Quota { builds_per_day: Some(1), rpc_calls_per_day: Some(3), ..Default::default() };
+        let usage = UsageTracker::new(Quota::default(), quota);
+        let team = principal("team-a");
+
+        usage.start_build(&team).unwrap();
+        assert!(matches!(usage.start_build(&team), Err(Error::QuotaExceeded(_))));
+        usage.start_build(&principal("team-b")).unwrap();
+
+        usage.rpc_calls(&team, 2).unwrap();
+        assert!(usage.rpc_calls(&team, 2).is_err());
+        usage.rpc_calls(&team, 1).unwrap();
+
+        let admin = Principal { admin: true, ..principal("ops") };
+        usage.start_build(&admin).unwrap();
+        usage.start_build(&admin).unwrap();
+
+        let report = usage.report(&team);
+        assert_eq!((report.today.builds, report.today.rpc_calls), (1, 3));
+    }
+
+    #[test]
+    fn exhausted_build_time_blocks_new_builds() {
+        let quota = Quota { build_seconds_per_day: Some(60), ..Default::default() };
+        let usage = UsageTracker::new(Quota::default(), quota);
+        let team = principal("team-a");
+
+        usage.start_build(&team).unwrap();
+        usage.finish_build(&team, 90, 1024);
+        assert!(matches!(usage.start_build(&team), Err(Error::QuotaExceeded(_))));
+        assert_eq!(usage.report(&team).total.artifact_bytes, 1024);
+    }
+
+    #[test]
+    fn idle_anonymous_accounts_are_dropped_when_the_day_rolls_over() {
+        let usage = UsageTracker::new(Quota::default(), Quota::default());
+        let anonymous = Principal { client_ip: Some("10.0.0.1".parse().unwrap()), ..Principal::anonymous() };
+        usage.rpc_calls(&anonymous, 1).unwrap();
+        usage.rpc_calls(&principal("team-a"), 1).unwrap();
+
+        // Pretend everything so far happened yesterday
+        let yesterday = Utc::now().date_naive().pred_opt().unwrap();
+        {
+            let mut accounts = usage.accounts.lock().unwrap();
+            accounts.day = Some(yesterday);
+            accounts.by_key.values_mut().for_each(|account| account.day = Some(yesterday));
+        }
+
+        let principals: Vec<_> = usage.summary().into_iter().map(|report| report.principal).collect();
+        assert_eq!(principals, ["team-a"]);
+    }
+}